@@ -0,0 +1,61 @@
+use crate::infer::InferredType;
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum BuiltinKind {
+    /// a function taking the given number of arguments
+    Function(usize),
+    Constant(f64),
+}
+
+/// a name provided by the language, visible everywhere unless a definition of the program shadows it
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Builtin {
+    pub name: &'static str,
+    pub kind: BuiltinKind,
+}
+impl Builtin {
+    const fn function(name: &'static str, arity: usize) -> Self {
+        Self { name, kind: BuiltinKind::Function(arity) }
+    }
+    const fn constant(name: &'static str, value: f64) -> Self {
+        Self { name, kind: BuiltinKind::Constant(value) }
+    }
+    pub fn ty(&self) -> InferredType {
+        match self.kind {
+            BuiltinKind::Function(arity) => InferredType::Function(vec![InferredType::AbstractFloat; arity], Box::new(InferredType::AbstractFloat)),
+            BuiltinKind::Constant(_) => InferredType::AbstractFloat,
+        }
+    }
+}
+
+pub const BUILTINS: &[Builtin] = &[
+    Builtin::function("sin", 1),
+    Builtin::function("cos", 1),
+    Builtin::function("tan", 1),
+    Builtin::function("asin", 1),
+    Builtin::function("acos", 1),
+    Builtin::function("atan", 1),
+    Builtin::function("sinh", 1),
+    Builtin::function("cosh", 1),
+    Builtin::function("tanh", 1),
+    Builtin::function("sqrt", 1),
+    Builtin::function("exp", 1),
+    Builtin::function("ln", 1),
+    Builtin::function("log10", 1),
+    Builtin::function("abs", 1),
+    Builtin::function("floor", 1),
+    Builtin::function("ceil", 1),
+    Builtin::function("round", 1),
+    Builtin::function("min", 2),
+    Builtin::function("max", 2),
+    Builtin::constant("pi", std::f64::consts::PI),
+    Builtin::constant("tau", std::f64::consts::TAU),
+    Builtin::constant("e", std::f64::consts::E),
+];
+
+/// the keywords offered by completion
+pub const KEYWORDS: &[&str] = &["let", "fn", "if", "else", "plot", "plot3d", "for", "in", "with", "to", "polar", "slider"];
+
+pub fn builtin(name: &str) -> Option<&'static Builtin> {
+    BUILTINS.iter().find(|x| x.name == name)
+}
@@ -0,0 +1,85 @@
+use crate::{builtins::{BuiltinKind, BUILTINS, KEYWORDS}, parser::compile_source, spanned::Span, symbols::{resolve::resolve_program, visible_symbols, ScopeId}, ProgramSource};
+
+#[derive(PartialEq, Eq, Debug, Hash, salsa::Update, Clone, Copy)]
+pub enum CompletionKind {
+    Function,
+    Variable,
+    Constant,
+    Keyword,
+}
+
+#[derive(PartialEq, Eq, Debug, Hash, salsa::Update, Clone)]
+pub struct Completion {
+    pub label: String,
+    pub kind: CompletionKind,
+}
+
+/// every name visible at the offset with the innermost scope first, followed by the builtins and the keywords
+#[salsa::tracked(returns(ref))]
+pub fn completions(db: &dyn salsa::Database, source: ProgramSource, offset: usize) -> Vec<Completion> {
+    let program = compile_source(db, source);
+    let scope = resolve_program(db, program).scope_at(db, offset).unwrap_or(ScopeId::Program(program));
+    let mut out = Vec::<Completion>::new();
+    let mut push = |label: &str, kind| if !out.iter().any(|x| x.label == label) {
+        out.push(Completion { label: label.to_owned(), kind });
+    };
+    for node in visible_symbols(db, program, scope) {
+        node.functions(db).iter().for_each(|x| push(x.text(db), CompletionKind::Function));
+        node.variables(db).iter().for_each(|x| push(x.text(db), CompletionKind::Variable));
+    }
+    for builtin in BUILTINS {
+        let kind = match builtin.kind {
+            BuiltinKind::Function(_) => CompletionKind::Function,
+            BuiltinKind::Constant(_) => CompletionKind::Constant,
+        };
+        push(builtin.name, kind);
+    }
+    KEYWORDS.iter().for_each(|x| push(x, CompletionKind::Keyword));
+    out
+}
+
+/// the completions for the identifier ending at the offset, together with the span of that identifier
+pub fn complete(db: &dyn salsa::Database, source: ProgramSource, offset: usize) -> (Span, Vec<Completion>) {
+    let text = &source.raw_text(db)[..offset];
+    let start = text.rfind(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).map(|x| x + 1).unwrap_or(0);
+    let prefix = &text[start..];
+    let span = Span::new(start, offset);
+    if prefix.starts_with(|c: char| c.is_ascii_digit()) {
+        return (span, vec![]);
+    }
+    let candidates = completions(db, source, offset).iter()
+        .filter(|x| x.label.starts_with(prefix))
+        .cloned()
+        .collect();
+    (span, candidates)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::GraphingDatabase;
+
+    use super::*;
+
+    fn labels(src: &str) -> Vec<String> {
+        let db = GraphingDatabase::default();
+        let code = ProgramSource::new(&db, src.to_owned());
+        complete(&db, code, src.len()).1.into_iter().map(|x| x.label).collect()
+    }
+
+    #[test]
+    fn scopes(){
+        let db = GraphingDatabase::default();
+        let src = "let a = 1;\nfn foo(x) = { let y = 2; y };";
+        let code = ProgramSource::new(&db, src.to_owned());
+        let out = completions(&db, code, 27);
+        assert_eq!(out[..4].iter().map(|x| x.label.as_str()).collect::<Vec<_>>(), vec!["y", "x", "foo", "a"]);
+        assert_eq!(out.last(), Some(&Completion { label: "slider".to_owned(), kind: CompletionKind::Keyword }));
+    }
+    #[test]
+    fn prefix(){
+        assert_eq!(labels("let sine = 1; let b = si"), vec!["sine", "sin", "sinh"]);
+        assert_eq!(labels("fn foo(x) = x; f"), vec!["foo", "floor", "fn", "for"]);
+        assert_eq!(labels("le"), vec!["let"]);
+        assert!(labels("let a = 1").is_empty());
+    }
+}
@@ -0,0 +1,282 @@
+use logos::Logos;
+
+use crate::{lexer::Token, spanned::Span, ProgramSource};
+
+/// source text which is not part of any token
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Copy)]
+pub enum TriviaKind {
+    Whitespace,
+    Comment,
+}
+
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub text: String,
+    pub span: Span,
+}
+
+/// a token together with its exact source text and the trivia in front of it
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub struct SyntaxToken {
+    pub token: Token,
+    pub text: String,
+    pub span: Span,
+    pub leading: Vec<Trivia>,
+}
+
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Copy)]
+pub enum SyntaxKind {
+    /// the entire source
+    Root,
+    /// the tokens up to and including a `;` at the depth of the enclosing root or braces
+    Statement,
+    /// the tokens after the last `;` of the enclosing root or braces, like the return expression of a block
+    Tail,
+    /// a delimited group, starting at the opening delimiter and ending at its closing delimiter if there is one
+    Group,
+}
+
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub struct SyntaxNode {
+    pub kind: SyntaxKind,
+    pub children: Vec<SyntaxElement>,
+}
+impl SyntaxNode {
+    /// every token in the node in source order
+    pub fn tokens(&self) -> Vec<&SyntaxToken> {
+        let mut out = vec![];
+        self.collect_tokens(&mut out);
+        out
+    }
+    fn collect_tokens<'a>(&'a self, out: &mut Vec<&'a SyntaxToken>) {
+        for child in &self.children {
+            match child {
+                SyntaxElement::Node(x) => x.collect_tokens(out),
+                SyntaxElement::Token(x) => out.push(x),
+            }
+        }
+    }
+    /// the source covered by the tokens of the node, excluding the leading trivia
+    pub fn span(&self) -> Option<Span> {
+        let tokens = self.tokens();
+        Some(tokens.first()?.span.merge(tokens.last()?.span))
+    }
+    fn print(&self, out: &mut String) {
+        for child in &self.children {
+            match child {
+                SyntaxElement::Node(x) => x.print(out),
+                SyntaxElement::Token(x) => {
+                    x.leading.iter().for_each(|trivia| out.push_str(&trivia.text));
+                    out.push_str(&x.text);
+                },
+            }
+        }
+    }
+}
+
+/// a lossless view of the source, printing it reproduces the source byte for byte
+#[salsa::tracked(debug)]
+pub struct ConcreteSyntax<'db> {
+    #[returns(ref)]
+    pub root: SyntaxNode,
+    /// the trivia after the last token
+    #[returns(ref)]
+    pub trailing: Vec<Trivia>,
+}
+impl<'db> ConcreteSyntax<'db> {
+    pub fn print(&self, db: &'db dyn salsa::Database) -> String {
+        let mut out = String::new();
+        self.root(db).print(&mut out);
+        self.trailing(db).iter().for_each(|trivia| out.push_str(&trivia.text));
+        out
+    }
+}
+
+#[salsa::tracked]
+pub fn parse_cst(db: &dyn salsa::Database, code: ProgramSource) -> ConcreteSyntax<'_> {
+    let (root, trailing) = build(code.raw_text(db));
+    ConcreteSyntax::new(db, root, trailing)
+}
+
+/// a node under construction
+struct Frame {
+    kind: SyntaxKind,
+    /// the delimiter closing the group
+    closing: Option<Token>,
+    children: Vec<SyntaxElement>,
+    /// the statement being built, only the root and braces are split into statements
+    statement: Option<Vec<SyntaxElement>>,
+}
+impl Frame {
+    fn new(kind: SyntaxKind, closing: Option<Token>) -> Self {
+        Self { kind, closing, children: vec![], statement: None }
+    }
+    fn splits_statements(&self) -> bool {
+        self.kind == SyntaxKind::Root || self.closing == Some(Token::RBrace)
+    }
+    fn push(&mut self, element: SyntaxElement) {
+        if self.splits_statements() {
+            self.statement.get_or_insert_with(Vec::new).push(element);
+        } else {
+            self.children.push(element);
+        }
+    }
+    fn end_statement(&mut self, kind: SyntaxKind) {
+        if let Some(children) = self.statement.take() {
+            self.children.push(SyntaxElement::Node(SyntaxNode { kind, children }));
+        }
+    }
+    fn finish(mut self) -> SyntaxNode {
+        self.end_statement(SyntaxKind::Tail);
+        SyntaxNode { kind: self.kind, children: self.children }
+    }
+}
+
+fn build(source: &str) -> (SyntaxNode, Vec<Trivia>) {
+    let mut stack = vec![Frame::new(SyntaxKind::Root, None)];
+    let mut leading = vec![];
+    let mut last = 0;
+    for (token, span) in Token::lexer(source).spanned() {
+        if last < span.start {
+            leading.push(Trivia { kind: TriviaKind::Whitespace, text: source[last..span.start].to_owned(), span: Span::new(last, span.start) });
+        }
+        last = span.end;
+        let text = source[span.clone()].to_owned();
+        let span = Span::new(span.start, span.end);
+        let token = token.unwrap_or(Token::Error);
+        if let Token::Comment(_) = token {
+            leading.push(Trivia { kind: TriviaKind::Comment, text, span });
+            continue;
+        }
+        let element = SyntaxElement::Token(SyntaxToken { token: token.clone(), text, span, leading: std::mem::take(&mut leading) });
+        let top = stack.last_mut().expect("the root is never popped");
+        let closing = match token {
+            Token::LParen => Some(Token::RParen),
+            Token::LBrace => Some(Token::RBrace),
+            Token::LBracket => Some(Token::RBracket),
+            _ => None,
+        };
+        if closing.is_some() {
+            let mut group = Frame::new(SyntaxKind::Group, closing);
+            group.children.push(element);
+            stack.push(group);
+        } else if top.closing.is_some() && top.closing.as_ref() == Some(&token) {
+            let mut group = stack.pop().expect("only groups have a closing delimiter");
+            group.end_statement(SyntaxKind::Tail);
+            group.children.push(element);
+            let group = group.finish();
+            stack.last_mut().expect("the root is never popped").push(SyntaxElement::Node(group));
+        } else {
+            top.push(element);
+            if token == Token::Semicolon && top.splits_statements() {
+                top.end_statement(SyntaxKind::Statement);
+            }
+        }
+    }
+    if last < source.len() {
+        leading.push(Trivia { kind: TriviaKind::Whitespace, text: source[last..].to_owned(), span: Span::new(last, source.len()) });
+    }
+    // close the groups missing their closing delimiter
+    while stack.len() > 1 {
+        let group = stack.pop().expect("checked by the loop").finish();
+        stack.last_mut().expect("the root is never popped").push(SyntaxElement::Node(group));
+    }
+    let root = stack.pop().expect("the root is never popped").finish();
+    (root, leading)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::GraphingDatabase;
+
+    use super::*;
+
+    /// the sources used by the other tests in the crate and a few malformed ones
+    const INPUTS: &[&str] = &[
+        "_",
+        "a",
+        "20",
+        "1.25",
+        "a + b",
+        "(a + b) * c",
+        "{\n            let a = 5;\n            a * 2\n        } * { 5 + 3}",
+        "fn fib(0) = 1;",
+        "fn fib(x: u8) -> u8 = x + 2;",
+        "let a = a * 2 ;",
+        "let (a, b) = a * 2 ;",
+        "let a: u8 = a * 2 ;",
+        "(1, _)",
+        "(a, _)",
+        "\n    let x = 1;\n    let a = 2;\n    fn foo(x) = {\n        let a = 2 * x;\n        a\n    };\n    let y = {\n        fn bar(x) = 2 * x;\n        2 * 3 //bar(x)\n    };\n    ",
+        "// header\r\nlet a = 1; // one\r\n",
+        "let a = 1 $ 2; é",
+        "fn foo(x = { (];",
+        "}}) let",
+        "",
+        "   ",
+    ];
+
+    fn roundtrip(src: &str) {
+        let db = GraphingDatabase::default();
+        let code = ProgramSource::new(&db, src.to_owned());
+        assert_eq!(parse_cst(&db, code).print(&db), src);
+    }
+
+    #[test]
+    fn roundtrip_inputs(){
+        INPUTS.iter().for_each(|x| roundtrip(x));
+    }
+    #[test]
+    fn roundtrip_prefixes(){
+        for input in INPUTS {
+            for (i, _) in input.char_indices() {
+                roundtrip(&input[..i]);
+                roundtrip(&input[i..]);
+            }
+        }
+    }
+    #[test]
+    fn roundtrip_generated(){
+        // a fixed linear congruential generator keeps the generated inputs reproducible
+        let alphabet = ["let", "fn", " ", "\n", "\t", "x", "1", "2.5", "=", ";", "(", ")", "{", "}", "[", ",", "+", "//c", "é", "$", "->", ":"];
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        for _ in 0..500 {
+            let mut src = String::new();
+            for _ in 0..(state % 40) {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                src.push_str(alphabet[(state >> 33) as usize % alphabet.len()]);
+            }
+            roundtrip(&src);
+        }
+    }
+    #[test]
+    fn structure(){
+        let (root, trailing) = build("let a = { let b = 1; b };\n// end\n");
+        let statements = root.children.iter()
+            .map(|x| match x {
+                SyntaxElement::Node(x) => x.kind,
+                SyntaxElement::Token(_) => panic!("the root only holds statements"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(statements, vec![SyntaxKind::Statement]);
+        assert_eq!(root.span(), Some(Span::new(0, 25)));
+        assert_eq!(trailing.iter().map(|x| x.kind).collect::<Vec<_>>(), vec![TriviaKind::Whitespace, TriviaKind::Comment, TriviaKind::Whitespace]);
+        let SyntaxElement::Node(statement) = &root.children[0] else { unreachable!() };
+        let SyntaxElement::Node(block) = &statement.children[3] else { panic!("expected the block group") };
+        assert_eq!(block.kind, SyntaxKind::Group);
+        let kinds = block.children.iter()
+            .filter_map(|x| match x {
+                SyntaxElement::Node(x) => Some(x.kind),
+                SyntaxElement::Token(_) => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(kinds, vec![SyntaxKind::Statement, SyntaxKind::Tail]);
+    }
+}
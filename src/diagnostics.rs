@@ -0,0 +1,144 @@
+use ariadne::{Config, Label, Report, ReportKind, Source};
+use serde::Serialize;
+
+use crate::{builtins::builtin, lexer::{lex_statement, statement_sources, Token}, parser::compile_source, spanned::Span, symbols::resolve::resolve_program, ParseError, ProgramSource};
+
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+}
+
+/// extra information attached to a diagnostic, optionally pointing at a second location in the source
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Serialize)]
+pub struct Note {
+    pub span: Option<Span>,
+    pub message: String,
+}
+
+/// a message about the source code meant to be shown to the user
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Serialize)]
+pub struct Diagnostic {
+    pub span: Span,
+    pub severity: Severity,
+    pub message: String,
+    pub notes: Vec<Note>,
+}
+impl Diagnostic {
+    pub fn error(span: Span, message: impl Into<String>) -> Self {
+        Self {
+            span,
+            severity: Severity::Error,
+            message: message.into(),
+            notes: vec![],
+        }
+    }
+    pub fn with_note(mut self, span: Option<Span>, message: impl Into<String>) -> Self {
+        self.notes.push(Note { span, message: message.into() });
+        self
+    }
+}
+
+/// renders the diagnostics the way they are shown in a terminal
+pub fn render(diagnostics: &[Diagnostic], name: &str, source: &str, color: bool) -> String {
+    let range = |span: Span| span.start.min(source.len())..span.end.min(source.len());
+    let mut out = vec![];
+    for diagnostic in diagnostics {
+        let kind = match diagnostic.severity {
+            Severity::Error => ReportKind::Error,
+        };
+        let mut report = Report::build(kind, (name, range(diagnostic.span)))
+            .with_config(Config::default().with_color(color))
+            .with_message(&diagnostic.message)
+            .with_label(Label::new((name, range(diagnostic.span))).with_message(&diagnostic.message));
+        for note in &diagnostic.notes {
+            report = match note.span {
+                Some(span) => report.with_label(Label::new((name, range(span))).with_message(&note.message)),
+                None => report.with_note(&note.message),
+            };
+        }
+        report.finish()
+            .write((name, Source::from(source)), &mut out)
+            .expect("writing to memory can not fail");
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// every problem found while lexing and parsing the source
+pub fn syntax_errors(db: &dyn salsa::Database, source: ProgramSource) -> Vec<Diagnostic> {
+    let text = source.raw_text(db);
+    let mut out = statement_sources(db, source).iter()
+        .flat_map(|&(x, offset)| lex_statement(db, x).tokens(db).iter().map(move |x| (&x.0, x.1.shift(offset))))
+        .filter(|x| *x.0 == Token::Error)
+        .map(|(_, span)| match text.get(span.start..span.end) {
+            // the digits of an integer which does not fit into 64 bits
+            Some(digits) if digits.bytes().all(|x| x.is_ascii_digit()) => Diagnostic::error(span, "invalid token")
+                .with_note(None, format!("integers are at most {}, write {digits}.0 for a float", i64::MAX)),
+            _ => Diagnostic::error(span, "invalid token"),
+        })
+        .collect::<Vec<_>>();
+    compile_source(db, source);
+    out.extend(compile_source::accumulated::<ParseError>(db, source).into_iter()
+        .map(|x| Diagnostic::error(Span::new(x.start, x.end), x.message.clone()))
+    );
+    out
+}
+
+/// every problem found while lexing, parsing and resolving the source
+///
+/// tracked because reading the accumulated parse errors is redone in every revision, the queries checking a source
+/// are then only executed again when its problems changed
+#[salsa::tracked]
+pub fn check_source(db: &dyn salsa::Database, source: ProgramSource) -> Vec<Diagnostic> {
+    let mut out = syntax_errors(db, source);
+    let program = compile_source(db, source);
+    out.extend(resolve_program(db, program).references(db).iter()
+        .filter(|x| x.definition.is_none() && builtin(&x.name).is_none())
+        .map(|x| Diagnostic::error(x.span, format!("cannot find `{}` in this scope", x.name)))
+    );
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::GraphingDatabase;
+
+    use super::*;
+
+    fn check(src: &str) -> Vec<Diagnostic> {
+        let db = GraphingDatabase::default();
+        let code = ProgramSource::new(&db, src.to_owned());
+        check_source(&db, code)
+    }
+
+    #[test]
+    fn valid(){
+        assert!(check("let a = 1; fn foo(x) = x * a;").is_empty());
+    }
+    #[test]
+    fn parse_error(){
+        let out = check("let a = 1 +;");
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].span, Span::new(11, 12));
+    }
+    #[test]
+    fn unresolved(){
+        let out = check("let a = b;");
+        assert_eq!(out, vec![Diagnostic::error(Span::new(8, 9), "cannot find `b` in this scope")]);
+    }
+    #[test]
+    fn builtins(){
+        assert!(check("let a = sin(pi) * 2;").is_empty());
+    }
+    #[test]
+    fn rendered(){
+        let out = render(&check("let a = b;"), "test.gl", "let a = b;", false);
+        assert!(out.contains("cannot find `b` in this scope"));
+        assert!(out.contains("test.gl:1:9"));
+    }
+    #[test]
+    fn invalid_token(){
+        let out = check("let a = 1 $ 2;");
+        assert_eq!(out[0], Diagnostic::error(Span::new(10, 11), "invalid token"));
+    }
+}
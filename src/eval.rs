@@ -0,0 +1,513 @@
+use std::{collections::HashMap, fmt::Display};
+
+use serde::{ser::SerializeSeq, Serialize};
+
+use crate::{builtins::{builtin, Builtin, BuiltinKind}, diagnostics::{check_source, Diagnostic}, parser::{compile_source, expression::Expression, function::Function, literal::Literal, operations::BinaryOp, pattern::Pattern, slider::Slider, statement::Statement}, spanned::{Span, Spanned}, symbols::{resolve::{resolve_program, DefinitionKind, Resolution}, ScopeId}, ProgramSource};
+
+/// the deepest the function calls can be nested before evaluation stops
+const MAX_DEPTH: usize = 256;
+
+/// the result of evaluating an expression
+#[derive(PartialEq, Debug, Clone)]
+pub enum Value {
+    Int(i64),
+    Float(f64),
+    /// the result of a comparison
+    Bool(bool),
+    /// a tuple, the empty tuple is the value of a block without return expression
+    Tuple(Vec<Value>),
+    /// a function defined in the program, by the index of its definition
+    Function(usize),
+    Builtin(&'static str),
+}
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Int(x) => write!(f, "{x}"),
+            Self::Float(x) => write!(f, "{x:?}"),
+            Self::Bool(x) => write!(f, "{x}"),
+            Self::Tuple(x) if x.len() == 1 => write!(f, "({},)", x[0]),
+            Self::Tuple(x) => write!(f, "({})", x.iter().map(|ell| ell.to_string()).collect::<Vec<_>>().join(", ")),
+            Self::Function(_) | Self::Builtin(_) => write!(f, "<fn>"),
+        }
+    }
+}
+/// numbers are written as json numbers, tuples as arrays and functions as `"<fn>"`
+impl Serialize for Value {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Int(x) => serializer.serialize_i64(*x),
+            Self::Float(x) => serializer.serialize_f64(*x),
+            Self::Bool(x) => serializer.serialize_bool(*x),
+            Self::Tuple(x) => {
+                let mut seq = serializer.serialize_seq(Some(x.len()))?;
+                for ell in x {
+                    seq.serialize_element(ell)?;
+                }
+                seq.end()
+            },
+            Self::Function(_) | Self::Builtin(_) => serializer.serialize_str("<fn>"),
+        }
+    }
+}
+impl Value {
+    /// the value as a float, integers are converted
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Self::Int(x) => Some(*x as f64),
+            Self::Float(x) => Some(*x),
+            _ => None,
+        }
+    }
+}
+
+/// a slider of the program with its evaluated range and step and the value it has
+#[derive(PartialEq, Debug, Clone)]
+pub struct SliderState {
+    pub name: String,
+    pub range: (f64, f64),
+    pub step: f64,
+    pub value: f64,
+}
+
+/// an error stopping the evaluation
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct EvalError {
+    pub span: Span,
+    pub message: String,
+}
+impl From<EvalError> for Diagnostic {
+    fn from(value: EvalError) -> Self {
+        Diagnostic::error(value.span, value.message)
+    }
+}
+
+/// why a source could not be run
+#[derive(PartialEq, Debug, Clone)]
+pub enum RunError {
+    /// the program has errors, nothing was evaluated
+    Invalid(Vec<Diagnostic>),
+    /// evaluation stopped, with the bindings evaluated before the error
+    Failed(Vec<(String, Value)>, EvalError),
+}
+
+/// evaluates every top level let statement in order and returns the names they bind with their values
+pub fn run_source(db: &dyn salsa::Database, source: ProgramSource) -> Result<Vec<(String, Value)>, RunError> {
+    evaluate_source(db, source).map(|x| x.1)
+}
+
+/// evaluates every top level let statement in order, the evaluator can then be used to call the functions of the
+/// source
+pub fn evaluate_source<'db>(db: &'db dyn salsa::Database, source: ProgramSource) -> Result<(Evaluator<'db>, Vec<(String, Value)>), RunError> {
+    evaluate_source_with(db, source, &[])
+}
+
+/// evaluates the source like [`evaluate_source`] with the sliders of the given names at the given values instead of
+/// at the start of their range
+pub fn evaluate_source_with<'db>(db: &'db dyn salsa::Database, source: ProgramSource, sliders: &[(String, f64)]) -> Result<(Evaluator<'db>, Vec<(String, Value)>), RunError> {
+    let diagnostics = check_source(db, source);
+    if !diagnostics.is_empty() {
+        return Err(RunError::Invalid(diagnostics));
+    }
+    let program = compile_source(db, source);
+    let resolution = resolve_program(db, program);
+    let mut evaluator = Evaluator::new(db, resolution);
+    evaluator.slider_values = sliders.iter().cloned().collect();
+    let mut out = vec![];
+    for statement in program.statements(db) {
+        let Statement::Variable(variable) = statement else { continue };
+        if let Err(err) = evaluator.statement(statement) {
+            return Err(RunError::Failed(out, err));
+        }
+        let mut names = vec![];
+        pattern_sites(variable.name(db), &mut names);
+        for site in names {
+            let index = evaluator.sites[&site];
+            let definition = &resolution.definitions(db)[index];
+            if definition.scope == ScopeId::Program(program) {
+                out.push((definition.name.clone(), evaluator.frames[0][&index].clone()));
+            }
+        }
+    }
+    Ok((evaluator, out))
+}
+
+fn pattern_sites(pattern: &Pattern<'_>, out: &mut Vec<Span>) {
+    match pattern {
+        Pattern::Variable(x) => out.push(x.span()),
+        Pattern::Tuple(x) => x.iter().for_each(|ell| pattern_sites(ell, out)),
+        _ => {}
+    }
+}
+
+/// evaluates expressions using the resolution to find the binding of every name
+pub struct Evaluator<'db> {
+    db: &'db dyn salsa::Database,
+    resolution: Resolution<'db>,
+    references: HashMap<Span, usize>,
+    /// the definition introduced at each site
+    sites: HashMap<Span, usize>,
+    /// the values bound by each active call, the first frame holds the top level bindings
+    frames: Vec<HashMap<usize, Value>>,
+    /// the values of the sliders by name, sliders without one are at the start of their range
+    slider_values: HashMap<String, f64>,
+    sliders: Vec<SliderState>,
+}
+impl<'db> Evaluator<'db> {
+    pub fn new(db: &'db dyn salsa::Database, resolution: Resolution<'db>) -> Self {
+        let references = resolution.references(db).iter()
+            .filter_map(|x| Some((x.span, x.definition?)))
+            .collect();
+        let sites = resolution.definitions(db).iter().enumerate()
+            .flat_map(|(i, x)| x.sites.iter().map(move |site| (*site, i)))
+            .collect();
+        Self { db, resolution, references, sites, frames: vec![HashMap::new()], slider_values: HashMap::new(), sliders: vec![] }
+    }
+    /// the sliders evaluated so far in order
+    pub fn sliders(&self) -> &[SliderState] {
+        &self.sliders
+    }
+    pub fn statement(&mut self, statement: &Statement<'db>) -> Result<(), EvalError> {
+        if let Statement::Variable(x) = statement {
+            let body = x.body(self.db);
+            let name = x.name(self.db);
+            let value = match (&*body, &**name) {
+                (Expression::Slider(slider), Pattern::Variable(variable)) => self.slider(slider, variable.text(self.db))?,
+                _ => self.expression(&body)?,
+            };
+            if !self.bind(name, &value) {
+                return Err(EvalError { span: name.span(), message: format!("the pattern does not match the value {value}") });
+            }
+        }
+        Ok(())
+    }
+    /// binds the parts of the value to the names of the pattern, false when the value does not match
+    fn bind(&mut self, pattern: &Pattern<'db>, value: &Value) -> bool {
+        match (pattern, value) {
+            (Pattern::Wildcard(_), _) => true,
+            (Pattern::Variable(x), _) => {
+                let index = self.sites[&x.span()];
+                self.frames.last_mut().expect("there is always a frame").insert(index, value.clone());
+                true
+            },
+            (Pattern::Literal(x), _) => match (&**x, value) {
+                (Literal::AbstractInt(a), Value::Int(b)) => *a as i64 == *b,
+                (Literal::AbstractInt(a), Value::Float(b)) => *a as f64 == *b,
+                (Literal::AbstractFloat(a), value) => value.as_float() == Some(a.0),
+                _ => false,
+            },
+            (Pattern::Tuple(parts), Value::Tuple(values)) if parts.len() == values.len() => {
+                parts.iter().zip(values).all(|(part, value)| self.bind(part, value))
+            },
+            _ => false,
+        }
+    }
+    fn slider(&mut self, slider: &Slider<'db>, name: &str) -> Result<Value, EvalError> {
+        let mut number = |expression: &Spanned<Expression<'db>>| {
+            let value = self.expression(expression)?;
+            value.as_float().ok_or_else(|| EvalError { span: expression.span(), message: format!("a slider takes numbers, not {value}") })
+        };
+        let (min, max) = (number(&slider.range.start)?, number(&slider.range.end)?);
+        if !min.is_finite() || !max.is_finite() {
+            return Err(EvalError { span: slider.range.span(), message: format!("the range {min}..{max} has to have finite ends") });
+        }
+        if min >= max {
+            return Err(EvalError { span: slider.range.span(), message: format!("the range {min}..{max} is empty, the start has to be below the end") });
+        }
+        let step = match &slider.step {
+            Some(step) => match number(step)? {
+                x if x > 0.0 => x,
+                x => return Err(EvalError { span: step.span(), message: format!("the step of a slider has to be above 0, not {x}") }),
+            },
+            None => (max - min) / 100.0,
+        };
+        let value = self.slider_values.get(name).map_or(min, |x| x.clamp(min, max));
+        self.sliders.push(SliderState { name: name.to_owned(), range: (min, max), step, value });
+        Ok(Value::Float(value))
+    }
+    /// the value of a top level name, builtins are used when the program does not define the name
+    pub fn global(&self, name: &str) -> Option<Value> {
+        let definitions = self.resolution.definitions(self.db);
+        let top_level = definitions.iter()
+            .rposition(|x| x.name == name && x.kind != DefinitionKind::Parameter && matches!(x.scope, ScopeId::Program(_)));
+        match top_level {
+            Some(index) if definitions[index].kind == DefinitionKind::Function => Some(Value::Function(index)),
+            Some(index) => self.frames[0].get(&index).cloned(),
+            None => builtin(name).map(builtin_value),
+        }
+    }
+    /// the number of arguments the function takes, None for values which are not functions
+    pub fn arity(&self, function: &Value) -> Option<usize> {
+        match function {
+            Value::Builtin(name) => match builtin(name)?.kind {
+                BuiltinKind::Function(arity) => Some(arity),
+                BuiltinKind::Constant(_) => None,
+            },
+            Value::Function(index) => self.resolution.definitions(self.db)[*index].statements.iter()
+                .find_map(|x| match x {
+                    Statement::Function(x) => Some(x.args(self.db).len()),
+                    _ => None,
+                }),
+            _ => None,
+        }
+    }
+    fn lookup(&self, span: Span, name: &str) -> Result<Value, EvalError> {
+        let Some(index) = self.references.get(&span) else {
+            return match builtin(name) {
+                Some(x) => Ok(builtin_value(x)),
+                None => Err(EvalError { span, message: format!("cannot find `{name}` in this scope") }),
+            };
+        };
+        if self.resolution.definitions(self.db)[*index].kind == DefinitionKind::Function {
+            return Ok(Value::Function(*index));
+        }
+        self.frames.iter().rev()
+            .find_map(|frame| frame.get(index))
+            .cloned()
+            .ok_or_else(|| EvalError { span, message: format!("`{name}` is used before it has a value") })
+    }
+    pub fn expression(&mut self, expression: &Spanned<Expression<'db>>) -> Result<Value, EvalError> {
+        let span = expression.span();
+        match &**expression {
+            Expression::Todo(_) => Err(EvalError { span, message: "reached a todo expression".to_owned() }),
+            Expression::Literal(x) => Ok(match &**x {
+                Literal::AbstractInt(x) => Value::Int(*x as i64),
+                Literal::AbstractFloat(x) => Value::Float(x.0),
+            }),
+            Expression::Variable(x) => self.lookup(x.span(), x.text(self.db)),
+            Expression::Negate(x) => match self.expression(x)? {
+                Value::Int(x) => x.checked_neg().map(Value::Int).ok_or_else(|| EvalError { span, message: format!("cannot negate {x}") }),
+                Value::Float(x) => Ok(Value::Float(-x)),
+                other => Err(EvalError { span, message: format!("cannot negate {other}") }),
+            },
+            Expression::Tuple(parts) => Ok(Value::Tuple(parts.iter().map(|x| self.expression(x)).collect::<Result<_, _>>()?)),
+            Expression::Binary(l, op, r) => {
+                let l = self.expression(l)?;
+                let r = self.expression(r)?;
+                binary(&l, op, &r).ok_or_else(|| EvalError { span, message: format!("cannot apply `{}` to {l} and {r}", op.symbol()) })
+            },
+            Expression::FunctionCall(name, args) => {
+                let function = self.lookup(name.span(), name.text(self.db))?;
+                let args = args.iter().map(|x| self.expression(x)).collect::<Result<Vec<_>, _>>()?;
+                self.call(&function, &args, span)
+            },
+            Expression::Block(x) => {
+                for statement in x.statements(self.db) {
+                    self.statement(statement)?;
+                }
+                match x.return_expr(self.db) {
+                    Some(expr) => self.expression(expr),
+                    None => Ok(Value::Tuple(vec![])),
+                }
+            },
+            Expression::Slider(_) => Err(EvalError { span, message: "a slider is bound to a single name, like let a = slider(0..10);".to_owned() }),
+            Expression::If => unreachable!("if expressions are never parsed"),
+        }
+    }
+    /// evaluates the expression with every value bound to the name introduced at its site, like the variables of a plot
+    pub fn expression_with(&mut self, bindings: &[(Span, Value)], expression: &Spanned<Expression<'db>>) -> Result<Value, EvalError> {
+        let frame = bindings.iter().map(|(site, value)| (self.sites[site], value.clone())).collect();
+        self.frames.push(frame);
+        let out = self.expression(expression);
+        self.frames.pop();
+        out
+    }
+    /// calls a function value with the arguments, the span is used for errors
+    pub fn call(&mut self, function: &Value, args: &[Value], span: Span) -> Result<Value, EvalError> {
+        match function {
+            Value::Builtin(name) => call_builtin(name, args).ok_or_else(|| EvalError {
+                span,
+                message: format!("`{name}` can not be called with ({})", args.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(", ")),
+            }),
+            Value::Function(index) => {
+                if self.frames.len() > MAX_DEPTH {
+                    return Err(EvalError { span, message: "the calls are nested too deep".to_owned() });
+                }
+                let definition = &self.resolution.definitions(self.db)[*index];
+                let name = definition.name.clone();
+                let clauses = definition.statements.iter()
+                    .filter_map(|x| match x {
+                        Statement::Function(x) => Some(*x),
+                        _ => None,
+                    })
+                    .collect::<Vec<Function<'db>>>();
+                for clause in clauses {
+                    let patterns = clause.args(self.db);
+                    if patterns.len() != args.len() {
+                        continue;
+                    }
+                    self.frames.push(HashMap::new());
+                    if patterns.iter().zip(args).all(|((pattern, _), value)| self.bind(pattern, value)) {
+                        let out = self.expression(clause.body(self.db));
+                        self.frames.pop();
+                        return out;
+                    }
+                    self.frames.pop();
+                }
+                Err(EvalError { span, message: format!("no clause of `{name}` matches the arguments") })
+            },
+            other => Err(EvalError { span, message: format!("{other} is not a function") }),
+        }
+    }
+}
+
+fn binary(l: &Value, op: &BinaryOp, r: &Value) -> Option<Value> {
+    if op.is_comparison() {
+        return compare(l, op, r).map(Value::Bool);
+    }
+    match (l, r) {
+        // tuples are combined part by part, a number is combined with every part
+        (Value::Tuple(a), Value::Tuple(b)) if a.len() == b.len() => a.iter().zip(b).map(|(a, b)| binary(a, op, b)).collect::<Option<_>>().map(Value::Tuple),
+        (Value::Tuple(a), b) if b.as_float().is_some() => a.iter().map(|a| binary(a, op, b)).collect::<Option<_>>().map(Value::Tuple),
+        (a, Value::Tuple(b)) if a.as_float().is_some() => b.iter().map(|b| binary(a, op, b)).collect::<Option<_>>().map(Value::Tuple),
+        (Value::Int(a), Value::Int(b)) => match op {
+            BinaryOp::Add => a.checked_add(*b),
+            BinaryOp::Subtract => a.checked_sub(*b),
+            BinaryOp::Multiply => a.checked_mul(*b),
+            BinaryOp::Divide => a.checked_div(*b),
+            // negative powers of integers are fractions
+            BinaryOp::Power if *b < 0 => return Some(Value::Float((*a as f64).powf(*b as f64))),
+            BinaryOp::Power => u32::try_from(*b).ok().and_then(|b| a.checked_pow(b)),
+            _ => unreachable!("comparisons are evaluated by compare"),
+        }.map(Value::Int),
+        _ => {
+            let (a, b) = (l.as_float()?, r.as_float()?);
+            Some(Value::Float(match op {
+                BinaryOp::Add => a + b,
+                BinaryOp::Subtract => a - b,
+                BinaryOp::Multiply => a * b,
+                BinaryOp::Divide => a / b,
+                BinaryOp::Power => a.powf(b),
+                _ => unreachable!("comparisons are evaluated by compare"),
+            }))
+        },
+    }
+}
+
+/// compares numbers by value, tuples and booleans can only be compared for equality
+fn compare(l: &Value, op: &BinaryOp, r: &Value) -> Option<bool> {
+    let equal = match (l, r) {
+        (Value::Tuple(a), Value::Tuple(b)) => a.len() == b.len() && a.iter().zip(b).all(|(a, b)| compare(a, &BinaryOp::Equal, b) == Some(true)),
+        (Value::Bool(a), Value::Bool(b)) => a == b,
+        (Value::Int(a), Value::Int(b)) => return Some(holds(a, op, b)),
+        _ => return Some(holds(l.as_float()?, op, r.as_float()?)),
+    };
+    match op {
+        BinaryOp::Equal => Some(equal),
+        BinaryOp::NotEqual => Some(!equal),
+        _ => None,
+    }
+}
+
+fn holds<T: PartialOrd>(a: T, op: &BinaryOp, b: T) -> bool {
+    match op {
+        BinaryOp::Equal => a == b,
+        BinaryOp::NotEqual => a != b,
+        BinaryOp::Less => a < b,
+        BinaryOp::LessEqual => a <= b,
+        BinaryOp::Greater => a > b,
+        BinaryOp::GreaterEqual => a >= b,
+        _ => unreachable!("only comparisons hold or not"),
+    }
+}
+
+fn builtin_value(builtin: &Builtin) -> Value {
+    match builtin.kind {
+        BuiltinKind::Constant(value) => Value::Float(value),
+        BuiltinKind::Function(_) => Value::Builtin(builtin.name),
+    }
+}
+
+fn call_builtin(name: &str, args: &[Value]) -> Option<Value> {
+    let args = args.iter().map(Value::as_float).collect::<Option<Vec<_>>>()?;
+    let out = match (name, &args[..]) {
+        ("sin", [x]) => x.sin(),
+        ("cos", [x]) => x.cos(),
+        ("tan", [x]) => x.tan(),
+        ("asin", [x]) => x.asin(),
+        ("acos", [x]) => x.acos(),
+        ("atan", [x]) => x.atan(),
+        ("sinh", [x]) => x.sinh(),
+        ("cosh", [x]) => x.cosh(),
+        ("tanh", [x]) => x.tanh(),
+        ("sqrt", [x]) => x.sqrt(),
+        ("exp", [x]) => x.exp(),
+        ("ln", [x]) => x.ln(),
+        ("log10", [x]) => x.log10(),
+        ("abs", [x]) => x.abs(),
+        ("floor", [x]) => x.floor(),
+        ("ceil", [x]) => x.ceil(),
+        ("round", [x]) => x.round(),
+        ("min", [a, b]) => a.min(*b),
+        ("max", [a, b]) => a.max(*b),
+        _ => return None,
+    };
+    Some(Value::Float(out))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::GraphingDatabase;
+
+    use super::*;
+
+    fn run(src: &str) -> Result<Vec<String>, RunError> {
+        let db = GraphingDatabase::default();
+        let code = ProgramSource::new(&db, src.to_owned());
+        run_source(&db, code).map(|x| x.into_iter().map(|(name, value)| format!("{name} = {value}")).collect())
+    }
+
+    #[test]
+    fn arithmetic(){
+        assert_eq!(run("let a = 1 + 2 * 3; let b = a / 2; let c = a / 2.0;").unwrap(), vec!["a = 7", "b = 3", "c = 3.5"]);
+        assert_eq!(run("let a = -2 ^ 2; let b = 2 ^ 3 ^ 2; let c = 2 ^ -1; let d = 4.0 ^ 0.5;").unwrap(), vec!["a = -4", "b = 512", "c = 0.5", "d = 2.0"]);
+    }
+    #[test]
+    fn tuples(){
+        let src = "let a = (3, 1); let foo = (a + 1) * 2; let (x, y) = foo; let b = (1, (2.5,)) - (1, (0.5,));";
+        assert_eq!(run(src).unwrap(), vec!["a = (3, 1)", "foo = (8, 4)", "x = 8", "y = 4", "b = (0, (2.0,))"]);
+        assert!(matches!(run("let a = (1, 2) + (1, 2, 3);"), Err(RunError::Failed(_, EvalError { message, .. })) if message == "cannot apply `+` to (1, 2) and (1, 2, 3)"));
+    }
+    #[test]
+    fn comparisons(){
+        let src = "let a = 1 + 1 == 2; let b = 2.5 < 2; let c = (1, 2) != (1, 2.0); let d = 3 >= 3 == (1 <= 0.5);";
+        assert_eq!(run(src).unwrap(), vec!["a = true", "b = false", "c = false", "d = false"]);
+        assert!(matches!(run("let a = (1, 2) < (1, 3);"), Err(RunError::Failed(_, EvalError { message, .. })) if message == "cannot apply `<` to (1, 2) and (1, 3)"));
+    }
+    #[test]
+    fn sliders(){
+        assert_eq!(run("let a = slider(0..10, step 0.5); let b = a + 1; let c = slider(-1..1);").unwrap(), vec!["a = 0.0", "b = 1.0", "c = -1.0"]);
+        let db = GraphingDatabase::default();
+        let code = ProgramSource::new(&db, "let k = 2; let a = slider(0..k * 5, step 0.5); let b = a * k;".to_owned());
+        let (evaluator, values) = evaluate_source_with(&db, code, &[("a".to_owned(), 2.5)]).unwrap();
+        assert_eq!(values[2], ("b".to_owned(), Value::Float(5.0)));
+        assert_eq!(evaluator.sliders(), [SliderState { name: "a".to_owned(), range: (0.0, 10.0), step: 0.5, value: 2.5 }]);
+        // values outside of the range are moved to its ends
+        let (_, values) = evaluate_source_with(&db, code, &[("a".to_owned(), 20.0)]).unwrap();
+        assert_eq!(values[1], ("a".to_owned(), Value::Float(10.0)));
+        assert!(matches!(run("let a = slider(1..0);"), Err(RunError::Failed(_, EvalError { message, .. })) if message == "the range 1..0 is empty, the start has to be below the end"));
+        assert!(matches!(run("let a = slider(0..1.0 / 0.0);"), Err(RunError::Failed(_, EvalError { message, .. })) if message == "the range 0..inf has to have finite ends"));
+        assert!(matches!(run("let a = slider(0..1, step 0);"), Err(RunError::Failed(_, EvalError { message, .. })) if message == "the step of a slider has to be above 0, not 0"));
+        assert!(matches!(run("let (a, b) = slider(0..1);"), Err(RunError::Failed(_, EvalError { message, .. })) if message.starts_with("a slider is bound to a single name")));
+    }
+    #[test]
+    fn functions(){
+        let src = "fn fib(0) = 1; fn fib(1) = 1; fn fib(x) = fib(x - 1) + fib(x - 2); let a = fib(10);";
+        assert_eq!(run(src).unwrap(), vec!["a = 89"]);
+        assert_eq!(run("fn twice(f, x) = f(f(x)); let a = twice(sqrt, 16);").unwrap(), vec!["a = 2.0"]);
+    }
+    #[test]
+    fn blocks(){
+        let src = "let k = 3; let y = { let k = 2; fn bar(x) = x * k; bar(5) }; let z = {};";
+        assert_eq!(run(src).unwrap(), vec!["k = 3", "y = 10", "z = ()"]);
+    }
+    #[test]
+    fn errors(){
+        let Err(RunError::Failed(done, err)) = run("let a = 1; let b = a / 0;") else { panic!() };
+        assert_eq!(done, vec![("a".to_owned(), Value::Int(1))]);
+        assert_eq!(err.span, Span::new(19, 24));
+        assert!(matches!(run("let a = _;"), Err(RunError::Failed(_, EvalError { message, .. })) if message == "reached a todo expression"));
+        assert!(matches!(run("fn f(x) = f(x); let a = f(1);"), Err(RunError::Failed(_, EvalError { message, .. })) if message == "the calls are nested too deep"));
+        assert!(matches!(run("let a = b;"), Err(RunError::Invalid(_))));
+    }
+}
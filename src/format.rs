@@ -0,0 +1,398 @@
+use crate::{diagnostics::{syntax_errors, Diagnostic}, lexer::{lex_statement, statement_sources, Token}, parser::{block::BlockExpression, compile_source, expression::Expression, literal::Literal, operations::BinaryOp, pattern::Pattern, plot::{OptionValue, PlotRange}, statement::Statement, ty::Type, Program}, spanned::{Span, Spanned}, ProgramSource};
+
+const INDENT: &str = "    ";
+
+/// formats the source, refusing when the program has errors as the formatter would drop the invalid code
+pub fn format_source(db: &dyn salsa::Database, source: ProgramSource) -> Result<String, Vec<Diagnostic>> {
+    let errors = syntax_errors(db, source);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    let program = compile_source(db, source);
+    let comments = statement_sources(db, source).iter()
+        .flat_map(|&(x, offset)| lex_statement(db, x).comments(db).iter().map(move |(text, span)| (text.clone(), span.shift(offset))))
+        .collect::<Vec<_>>();
+    Ok(format_program(db, program, source.raw_text(db), &comments))
+}
+
+/// prints the program back to canonical source
+///
+/// the original source is only used to place the comments and to keep blank lines between statements
+pub fn format_program(db: &dyn salsa::Database, program: Program<'_>, source: &str, comments: &[(String, Span)]) -> String {
+    let mut printer = Printer {
+        db,
+        source,
+        comments,
+        next_comment: 0,
+        indent: 0,
+        out: String::new(),
+    };
+    printer.statements(program.statements(db).iter());
+    printer.comments_before(usize::MAX);
+    printer.out
+}
+
+struct Printer<'a> {
+    db: &'a dyn salsa::Database,
+    source: &'a str,
+    comments: &'a [(String, Span)],
+    /// index of the first comment which has not been printed yet
+    next_comment: usize,
+    indent: usize,
+    out: String,
+}
+impl<'a> Printer<'a> {
+    fn newline(&mut self) {
+        self.out.push('\n');
+        for _ in 0..self.indent {
+            self.out.push_str(INDENT);
+        }
+    }
+    /// prints every remaining comment starting before the offset, each on its own line
+    fn comments_before(&mut self, offset: usize) {
+        while let Some((text, span)) = self.comments.get(self.next_comment) {
+            if span.start >= offset {
+                break;
+            }
+            if !self.out.is_empty() {
+                self.newline();
+            }
+            self.out.push_str(text.trim_end());
+            self.next_comment += 1;
+        }
+    }
+    /// prints a comment on the same line as the code ending at the offset
+    fn trailing_comment(&mut self, offset: usize) {
+        let Some((text, span)) = self.comments.get(self.next_comment) else { return };
+        let between = self.source.get(offset..span.start).unwrap_or("\n");
+        if !between.contains('\n') {
+            self.out.push(' ');
+            self.out.push_str(text.trim_end());
+            self.next_comment += 1;
+        }
+    }
+    /// keeps a single blank line where the source has one or more
+    fn blank_line(&mut self, previous_end: usize, start: usize) {
+        let between = self.source.get(previous_end..start).unwrap_or_default();
+        let first_comment = self.comments.get(self.next_comment)
+            .map(|x| x.1.start.saturating_sub(previous_end))
+            .unwrap_or(between.len())
+            .min(between.len());
+        if between[..first_comment].matches('\n').count() > 1 {
+            self.out.push('\n');
+        }
+    }
+
+    fn statements<'b, 'db: 'b>(&mut self, statements: impl Iterator<Item = &'b Statement<'db>>) {
+        let mut previous_end = None;
+        for statement in statements {
+            let span = statement.span(self.db);
+            if let Some(end) = previous_end {
+                self.blank_line(end, span.start);
+            }
+            self.comments_before(span.start);
+            if !self.out.is_empty() {
+                self.newline();
+            }
+            self.statement(statement);
+            self.trailing_comment(span.end + 1);
+            previous_end = Some(span.end);
+        }
+    }
+    fn statement(&mut self, statement: &Statement<'_>) {
+        match statement {
+            Statement::Variable(x) => {
+                self.out.push_str("let ");
+                self.pattern(x.name(self.db));
+                if let Some(ty) = x.ty(self.db) {
+                    self.out.push_str(": ");
+                    self.ty(&ty);
+                }
+                self.out.push_str(" = ");
+                self.expression(&x.body(self.db));
+            },
+            Statement::Function(x) => {
+                self.out.push_str("fn ");
+                self.out.push_str(x.name(self.db).text(self.db));
+                self.out.push('(');
+                for (i, (pattern, ty)) in x.args(self.db).iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.pattern(pattern);
+                    if let Some(ty) = ty {
+                        self.out.push_str(": ");
+                        self.ty(ty);
+                    }
+                }
+                self.out.push(')');
+                if let Some(ty) = x.return_type(self.db) {
+                    self.out.push_str(" -> ");
+                    self.ty(&ty);
+                }
+                self.out.push_str(" = ");
+                self.expression(x.body(self.db));
+            },
+            Statement::Plot(x) => {
+                self.out.push_str(if x.surface(self.db) { "plot3d " } else { "plot " });
+                for (i, series) in x.series(self.db).iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    if series.polar.is_some() {
+                        self.out.push_str("polar ");
+                    }
+                    self.expression(&series.expression);
+                }
+                self.out.push_str(" for ");
+                self.out.push_str(x.variable(self.db).text(self.db));
+                self.out.push_str(" in ");
+                self.range(x.range(self.db));
+                if let Some(second) = x.second(self.db) {
+                    self.out.push_str(", ");
+                    self.out.push_str(second.variable.text(self.db));
+                    self.out.push_str(" in ");
+                    self.range(&second.range);
+                }
+                for (i, option) in x.options(self.db).iter().enumerate() {
+                    self.out.push_str(if i == 0 { " with " } else { ", " });
+                    self.out.push_str(&option.name);
+                    match &option.value {
+                        Some(OptionValue::String(x)) => self.out.push_str(&format!(" {}", Token::String((**x).clone()))),
+                        Some(OptionValue::Range(x)) => {
+                            self.out.push(' ');
+                            self.range(x);
+                        },
+                        Some(OptionValue::Expression(x)) => {
+                            self.out.push(' ');
+                            self.expression(x);
+                        },
+                        None => {},
+                    }
+                }
+                if let Some(output) = x.output(self.db) {
+                    self.out.push_str(&format!(" to {}", Token::String((**output).clone())));
+                }
+            },
+        }
+        self.out.push(';');
+    }
+    fn range(&mut self, range: &PlotRange<'_>) {
+        self.expression(&range.start);
+        self.out.push_str("..");
+        self.expression(&range.end);
+    }
+    fn pattern(&mut self, pattern: &Pattern<'_>) {
+        match pattern {
+            Pattern::Wildcard(_) => self.out.push('_'),
+            Pattern::Variable(x) => self.out.push_str(x.text(self.db)),
+            Pattern::Literal(x) => self.literal(x),
+            Pattern::Tuple(x) => {
+                self.out.push('(');
+                for (i, ell) in x.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.pattern(ell);
+                }
+                if x.len() == 1 {
+                    self.out.push(',');
+                }
+                self.out.push(')');
+            },
+        }
+    }
+    fn ty(&mut self, ty: &Type<'_>) {
+        match ty {
+            Type::Inferred(_) => self.out.push('_'),
+            Type::Type(x) => self.out.push_str(x.text(self.db)),
+            Type::Tuple(x) => {
+                self.out.push('(');
+                for (i, ell) in x.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.ty(ell);
+                }
+                self.out.push(')');
+            },
+        }
+    }
+    fn literal(&mut self, literal: &Literal) {
+        match literal {
+            Literal::AbstractInt(x) => self.out.push_str(&x.to_string()),
+            Literal::AbstractFloat(x) => {
+                // the lexer requires a fractional part, debug formatting of a whole float can omit it in exponent form
+                let text = format!("{:?}", x.0);
+                match text.find(['e', 'E']) {
+                    Some(i) if !text[..i].contains('.') => {
+                        self.out.push_str(&text[..i]);
+                        self.out.push_str(".0");
+                        self.out.push_str(&text[i..]);
+                    },
+                    _ => self.out.push_str(&text),
+                }
+            },
+        }
+    }
+    fn expression(&mut self, expression: &Spanned<Expression<'_>>) {
+        match &**expression {
+            Expression::Todo(_) => self.out.push('_'),
+            Expression::Literal(x) => self.literal(x),
+            Expression::Variable(x) => self.out.push_str(x.text(self.db)),
+            Expression::Negate(x) => {
+                self.out.push('-');
+                // only powers bind tighter than the negation
+                let parens = matches!(&***x, Expression::Binary(_, op, _) if **op != BinaryOp::Power);
+                self.parenthesized(x, parens);
+            },
+            Expression::Binary(l, op, r) => {
+                self.operand(l, op, false);
+                self.out.push(' ');
+                self.out.push_str(op.symbol());
+                self.out.push(' ');
+                self.operand(r, op, true);
+            },
+            Expression::FunctionCall(name, args) => {
+                self.out.push_str(name.text(self.db));
+                self.out.push('(');
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.expression(arg);
+                }
+                self.out.push(')');
+            },
+            Expression::Tuple(x) => {
+                self.out.push('(');
+                for (i, ell) in x.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.expression(ell);
+                }
+                if x.len() == 1 {
+                    self.out.push(',');
+                }
+                self.out.push(')');
+            },
+            Expression::Block(x) => self.block(*x, expression.span().end),
+            Expression::Slider(x) => {
+                self.out.push_str("slider(");
+                self.range(&x.range);
+                if let Some(step) = &x.step {
+                    self.out.push_str(", step ");
+                    self.expression(step);
+                }
+                self.out.push(')');
+            },
+            Expression::If => unreachable!("if expressions are never parsed"),
+        }
+    }
+    /// prints an operand of a binary operation, adding the parentheses the precedence requires
+    fn operand(&mut self, operand: &Spanned<Expression<'_>>, parent: &BinaryOp, right: bool) {
+        let parens = match &**operand {
+            Expression::Binary(_, op, _) => op.precedence() < parent.precedence()
+                || (op.precedence() == parent.precedence() && right != parent.is_right_associative()),
+            // (-x)^2 is not -x^2
+            Expression::Negate(_) => *parent == BinaryOp::Power && !right,
+            _ => false,
+        };
+        self.parenthesized(operand, parens);
+    }
+    fn parenthesized(&mut self, expression: &Spanned<Expression<'_>>, parens: bool) {
+        if parens {
+            self.out.push('(');
+        }
+        self.expression(expression);
+        if parens {
+            self.out.push(')');
+        }
+    }
+    fn block(&mut self, block: BlockExpression<'_>, end: usize) {
+        let statements = block.statements(self.db);
+        let return_expr = block.return_expr(self.db);
+        if statements.is_empty() && return_expr.is_none() {
+            self.out.push_str("{}");
+            return;
+        }
+        self.out.push('{');
+        self.indent += 1;
+        self.statements(statements.iter().map(|x| &**x));
+        if let Some(expr) = return_expr {
+            if let Some(last) = statements.last() {
+                self.blank_line(last.span().end, expr.span().start);
+            }
+            self.comments_before(expr.span().start);
+            self.newline();
+            self.expression(expr);
+            self.trailing_comment(expr.span().end);
+        }
+        self.comments_before(end);
+        self.indent -= 1;
+        self.newline();
+        self.out.push('}');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::GraphingDatabase;
+
+    use super::*;
+
+    fn format(src: &str) -> String {
+        let db = GraphingDatabase::default();
+        let code = ProgramSource::new(&db, src.to_owned());
+        format_source(&db, code).unwrap()
+    }
+
+    #[test]
+    fn spacing(){
+        assert_eq!(format("let a=1+2*b  ;fn foo( x:u8 )->u8=x/2;"), "let a = 1 + 2 * b;\nfn foo(x: u8) -> u8 = x / 2;");
+    }
+    #[test]
+    fn precedence(){
+        assert_eq!(format("let a = (1 + 2) * (3 - (4 - 5));"), "let a = (1 + 2) * (3 - (4 - 5));");
+        assert_eq!(format("let a = ((1 + 2) + 3) * 4.0;"), "let a = (1 + 2 + 3) * 4.0;");
+        assert_eq!(format("let a = (2 ^ 3) ^ 2 + 2 ^ (3 ^ 2);"), "let a = (2 ^ 3) ^ 2 + 2 ^ 3 ^ 2;");
+        assert_eq!(format("let a = -(x ^ 2) + (-x) ^ 2 - -(1 + x);"), "let a = -x ^ 2 + (-x) ^ 2 - -(1 + x);");
+        assert_eq!(format("let a = (x==1)!=(y>=2-1);"), "let a = x == 1 != (y >= 2 - 1);");
+    }
+    #[test]
+    fn tuples(){
+        assert_eq!(format("let a = ((1,2),(3,) ,());"), "let a = ((1, 2), (3,), ());");
+        assert_eq!(format("let a = ((1 + 2));"), "let a = 1 + 2;");
+    }
+    #[test]
+    fn plot(){
+        let src = "plot sin(x),x^2 for x in -5..5 with title \"a \\\"b\\\"\",grid,y -1..2*pi to \"out.svg\";";
+        assert_eq!(format(src), "plot sin(x), x ^ 2 for x in -5..5 with title \"a \\\"b\\\"\", grid, y -1..2 * pi to \"out.svg\";");
+        assert_eq!(format("plot (cos(t),sin(t)),polar 2*t for t in 0..tau;"), "plot (cos(t), sin(t)), polar 2 * t for t in 0..tau;");
+        assert_eq!(format("plot x^2+y^2==1 for x in -2..2,y in -2..2;"), "plot x ^ 2 + y ^ 2 == 1 for x in -2..2, y in -2..2;");
+    }
+    #[test]
+    fn blocks(){
+        let src = "let y = { fn bar(x) = 2 * x; let z = {}; bar(3) };";
+        assert_eq!(format(src), "let y = {\n    fn bar(x) = 2 * x;\n    let z = {};\n    bar(3)\n};");
+    }
+    #[test]
+    fn comments(){
+        let src = "// header\nlet a = 1; // one\n\n\n// about b\nlet b = { let c = 2; // two\n c //ret\n // end\n};\n// trailing";
+        let expected = "// header\nlet a = 1; // one\n\n// about b\nlet b = {\n    let c = 2; // two\n    c //ret\n    // end\n};\n// trailing";
+        assert_eq!(format(src), expected);
+    }
+    #[test]
+    fn idempotent(){
+        let src = "fn fib(0)=1;fn fib(1)=1;\n// recursion\nfn fib(x:u64)=fib(x-1)+fib(x-2);let (a,b)=(_);";
+        let once = format(src);
+        assert_eq!(format(&once), once);
+    }
+    #[test]
+    fn refuses_invalid(){
+        let db = GraphingDatabase::default();
+        let code = ProgramSource::new(&db, "let a = ;".to_owned());
+        assert!(format_source(&db, code).is_err());
+    }
+}
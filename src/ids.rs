@@ -1,7 +1,5 @@
-use std::sync::atomic::{AtomicU64, Ordering};
-
 use chumsky::{input::ValueInput, prelude::*};
-use crate::{lexer::Token, spanned::Span};
+use crate::{lexer::Token, parser::Extra, spanned::Span};
 
 
 #[salsa::interned(debug)]
@@ -10,7 +8,7 @@ pub struct VariableId<'db>{
     pub text: String,
 }
 impl<'db> VariableId<'db> {
-    pub fn parser<'src, I: ValueInput<'src, Token = Token, Span = Span>>(db: &'db dyn salsa::Database) -> impl Parser<'src, I, Self> + Clone {
+    pub fn parser<'src, I: ValueInput<'src, Token = Token, Span = Span>>(db: &'db dyn salsa::Database) -> impl Parser<'src, I, Self, Extra<'src>> + Clone {
         select! { Token::Identifier(x) => x }
             .map(|x| Self::new(db, x))
     }
@@ -22,7 +20,7 @@ pub struct FunctionId<'db> {
     pub text: String,
 }
 impl<'db> FunctionId<'db> {
-    pub fn parser<'src, I: ValueInput<'src, Token = Token, Span = Span>>(db: &'db dyn salsa::Database) -> impl Parser<'src, I, Self> + Clone {
+    pub fn parser<'src, I: ValueInput<'src, Token = Token, Span = Span>>(db: &'db dyn salsa::Database) -> impl Parser<'src, I, Self, Extra<'src>> + Clone {
         select! { Token::Identifier(x) => x }
             .map(|x| Self::new(db, x))
     }
@@ -34,7 +32,7 @@ pub struct TypeId<'db>{
     pub text: String,
 }
 impl<'db> TypeId<'db> {
-    pub fn parser<'src, I: ValueInput<'src, Token = Token, Span = Span>>(db: &'db dyn salsa::Database) -> impl Parser<'src, I, Self> + Clone {
+    pub fn parser<'src, I: ValueInput<'src, Token = Token, Span = Span>>(db: &'db dyn salsa::Database) -> impl Parser<'src, I, Self, Extra<'src>> + Clone {
         select! {Token::Identifier(x) => x}
             .map(|x| Self::new(db, x))
     }
@@ -0,0 +1,232 @@
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{builtins::builtin, parser::{expression::Expression, literal::Literal, pattern::Pattern, statement::Statement, ty::Type}, spanned::Span, symbols::resolve::{DefinitionKind, Resolution}};
+
+/// a type as far as it can be inferred without type checking the program
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub enum InferredType {
+    /// an integer literal which has not been constrained to a specific type yet
+    AbstractInt,
+    /// a float literal which has not been constrained to a specific type yet
+    AbstractFloat,
+    /// a type written by the user like u8
+    Named(String),
+    /// a tuple, the empty tuple is the type of a block without return expression
+    Tuple(Vec<InferredType>),
+    /// a function with its argument types and return type
+    Function(Vec<InferredType>, Box<InferredType>),
+    /// the type could not be inferred
+    Unknown,
+}
+impl Display for InferredType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let join = |items: &[InferredType]| items.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(", ");
+        match self {
+            Self::AbstractInt => write!(f, "{{integer}}"),
+            Self::AbstractFloat => write!(f, "{{float}}"),
+            Self::Named(x) => write!(f, "{x}"),
+            Self::Tuple(x) if x.len() == 1 => write!(f, "({},)", x[0]),
+            Self::Tuple(x) => write!(f, "({})", join(x)),
+            Self::Function(args, ret) => write!(f, "fn({}) -> {ret}", join(args)),
+            Self::Unknown => write!(f, "_"),
+        }
+    }
+}
+impl InferredType {
+    pub fn from_annotation(db: &dyn salsa::Database, ty: &Type<'_>) -> Self {
+        match ty {
+            Type::Inferred(_) => Self::Unknown,
+            Type::Type(x) => Self::Named(x.text(db).clone()),
+            Type::Tuple(x) => Self::Tuple(x.iter().map(|ell| Self::from_annotation(db, ell)).collect()),
+        }
+    }
+    /// the type of an arithmetic operation between both types
+    fn combine(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::AbstractInt, Self::AbstractInt) => Self::AbstractInt,
+            (Self::AbstractFloat | Self::AbstractInt, Self::AbstractFloat | Self::AbstractInt) => Self::AbstractFloat,
+            (Self::Named(a), Self::Named(b)) if a == b => Self::Named(a),
+            // literals take the type of the other operand
+            (Self::Named(a), Self::AbstractInt | Self::AbstractFloat) | (Self::AbstractInt | Self::AbstractFloat, Self::Named(a)) => Self::Named(a),
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// infers the type of a definition from its annotations and the expressions it is bound to
+pub fn infer_definition<'db>(db: &'db dyn salsa::Database, resolution: Resolution<'db>, definition: usize) -> InferredType {
+    let references = resolution.references(db).iter()
+        .map(|x| (x.span, x.definition))
+        .collect();
+    let mut inferer = Inferer { db, resolution, references, visiting: vec![] };
+    inferer.definition(definition)
+}
+
+struct Inferer<'db> {
+    db: &'db dyn salsa::Database,
+    resolution: Resolution<'db>,
+    references: HashMap<Span, Option<usize>>,
+    /// definitions currently being inferred, used to stop at recursive definitions
+    visiting: Vec<usize>,
+}
+impl<'db> Inferer<'db> {
+    fn definition(&mut self, index: usize) -> InferredType {
+        if self.visiting.contains(&index) {
+            return InferredType::Unknown;
+        }
+        self.visiting.push(index);
+        let definition = &self.resolution.definitions(self.db)[index];
+        let site = definition.sites[0];
+        let out = match (definition.kind, &definition.statements[..]) {
+            (DefinitionKind::Variable, [Statement::Variable(x)]) => {
+                let ty = match x.ty(self.db) {
+                    Some(ty) => InferredType::from_annotation(self.db, &ty),
+                    None => self.expression(&x.body(self.db)),
+                };
+                select(x.name(self.db), ty, site)
+            },
+            (DefinitionKind::Parameter, [Statement::Function(x)]) => {
+                x.args(self.db).iter()
+                    .find_map(|(pattern, ty)| {
+                        let ty = ty.as_ref().map(|ty| InferredType::from_annotation(self.db, ty)).unwrap_or(InferredType::Unknown);
+                        select(pattern, ty, site)
+                    })
+            },
+            // the variable of a plot takes every value of its range
+            (DefinitionKind::Parameter, [Statement::Plot(_)]) => Some(InferredType::AbstractFloat),
+            (DefinitionKind::Function, clauses) => Some(self.function(clauses)),
+            _ => None,
+        };
+        self.visiting.pop();
+        out.unwrap_or(InferredType::Unknown)
+    }
+    fn function(&mut self, clauses: &[Statement<'db>]) -> InferredType {
+        let clauses = clauses.iter()
+            .filter_map(|x| match x {
+                Statement::Function(x) => Some(*x),
+                _ => None
+            })
+            .collect::<Vec<_>>();
+        let arity = clauses.first().map(|x| x.args(self.db).len()).unwrap_or(0);
+        let args = (0..arity)
+            .map(|i| clauses.iter()
+                .filter_map(|clause| clause.args(self.db).get(i).map(|(pattern, ty)| match (&**pattern, ty) {
+                    (_, Some(ty)) => InferredType::from_annotation(self.db, ty),
+                    (Pattern::Literal(x), None) => literal(x),
+                    _ => InferredType::Unknown,
+                }))
+                .find(|x| *x != InferredType::Unknown)
+                .unwrap_or(InferredType::Unknown)
+            )
+            .collect();
+        let ret = clauses.iter()
+            .find_map(|clause| clause.return_type(self.db))
+            .map(|ty| InferredType::from_annotation(self.db, &ty))
+            .or_else(|| clauses.iter()
+                .map(|clause| self.expression(clause.body(self.db)))
+                .find(|x| *x != InferredType::Unknown)
+            )
+            .unwrap_or(InferredType::Unknown);
+        InferredType::Function(args, Box::new(ret))
+    }
+    fn reference(&mut self, span: Span, name: &str) -> InferredType {
+        match (self.references.get(&span), builtin(name)) {
+            (Some(Some(definition)), _) => self.definition(*definition),
+            (_, Some(builtin)) => builtin.ty(),
+            _ => InferredType::Unknown,
+        }
+    }
+    fn expression(&mut self, expression: &Expression<'db>) -> InferredType {
+        match expression {
+            Expression::Literal(x) => literal(x),
+            Expression::Variable(x) => self.reference(x.span(), x.text(self.db)),
+            Expression::FunctionCall(name, _) => match self.reference(name.span(), name.text(self.db)) {
+                InferredType::Function(_, ret) => *ret,
+                _ => InferredType::Unknown,
+            },
+            Expression::Negate(x) => self.expression(x),
+            Expression::Tuple(parts) => InferredType::Tuple(parts.iter().map(|x| self.expression(x)).collect()),
+            Expression::Binary(_, op, _) if op.is_comparison() => InferredType::Named("bool".to_owned()),
+            Expression::Binary(l, _, r) => {
+                let l = self.expression(l);
+                let r = self.expression(r);
+                l.combine(r)
+            },
+            Expression::Block(x) => match x.return_expr(self.db) {
+                Some(expr) => self.expression(expr),
+                None => InferredType::Tuple(vec![]),
+            },
+            Expression::Slider(_) => InferredType::AbstractFloat,
+            Expression::Todo(_) | Expression::If => InferredType::Unknown,
+        }
+    }
+}
+
+fn literal(literal: &Literal) -> InferredType {
+    match literal {
+        Literal::AbstractInt(_) => InferredType::AbstractInt,
+        Literal::AbstractFloat(_) => InferredType::AbstractFloat,
+    }
+}
+
+/// the part of the type bound to the name at the site, None if the pattern does not bind the site
+fn select(pattern: &Pattern<'_>, ty: InferredType, site: Span) -> Option<InferredType> {
+    match pattern {
+        Pattern::Variable(x) if x.span() == site => Some(ty),
+        Pattern::Tuple(parts) => {
+            let types = match ty {
+                InferredType::Tuple(types) if types.len() == parts.len() => types,
+                _ => vec![InferredType::Unknown; parts.len()],
+            };
+            parts.iter().zip(types).find_map(|(part, ty)| select(part, ty, site))
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{lexer::lex_source, parser::compile_tokenstream, symbols::resolve::resolve_program, GraphingDatabase, ProgramSource};
+
+    use super::*;
+
+    fn infer(src: &str, name: &str) -> String {
+        let db = GraphingDatabase::default();
+        let code = ProgramSource::new(&db, src.to_owned());
+        let program = compile_tokenstream(&db, lex_source(&db, code));
+        let resolution = resolve_program(&db, program);
+        let definition = resolution.definitions(&db).iter()
+            .rposition(|x| x.name == name)
+            .unwrap();
+        infer_definition(&db, resolution, definition).to_string()
+    }
+
+    #[test]
+    fn literals(){
+        assert_eq!(infer("let a = 1;", "a"), "{integer}");
+        assert_eq!(infer("let a = 1 + 2.5;", "a"), "{float}");
+        assert_eq!(infer("let a: u8 = 1; let b = a * 2;", "b"), "u8");
+        assert_eq!(infer("let a = 1 < 2.5;", "a"), "bool");
+    }
+    #[test]
+    fn block(){
+        assert_eq!(infer("let a = { let x = 2; x };", "a"), "{integer}");
+        assert_eq!(infer("let a = { let x = 2; };", "a"), "()");
+        assert_eq!(infer("let a = (1, 2.5 * 2, (3,));", "a"), "({integer}, {float}, ({integer},))");
+    }
+    #[test]
+    fn recursive_function(){
+        let src = "fn fib(0) = 1; fn fib(1) = 1; fn fib(x) = fib(x - 1) + fib(x - 2);";
+        assert_eq!(infer(src, "fib"), "fn({integer}) -> {integer}");
+        assert_eq!(infer("fn foo(x: u8) = x; let a = foo(1);", "a"), "u8");
+    }
+    #[test]
+    fn unknown_parameter(){
+        assert_eq!(infer("fn foo(x) = x;", "x"), "_");
+    }
+    #[test]
+    fn plot_variable(){
+        assert_eq!(infer("plot -x ^ 2 for x in 0..1;", "x"), "{float}");
+        assert_eq!(infer("plot x == y for x in 0..1, y in 0..1;", "y"), "{float}");
+    }
+}
@@ -1,24 +1,30 @@
 use logos::Logos;
 use ordered_float::OrderedFloat;
+use serde::Serialize;
 
-use crate::{spanned::Span, ProgramSource};
+use crate::{parser::serialize::ordered_float, spanned::Span, ProgramSource};
+#[cfg(test)]
 use crate::stream::Stream;
 
 
 #[salsa::tracked(debug)]
 pub struct LexedSource<'db> {
     #[returns(ref)]
-    pub tokens: Vec<(Token, Span)>
+    pub tokens: Vec<(Token, Span)>,
+    /// the comments removed from the token stream, kept so tools like the formatter can put them back
+    #[returns(ref)]
+    pub comments: Vec<(String, Span)>,
 }
 
 
-#[derive(Logos, Debug, PartialEq, Clone, Hash, Eq)]
-#[logos(skip r"[ \t\n\f]+")]
+#[derive(Logos, Debug, PartialEq, Clone, Hash, Eq, Serialize, salsa::Update)]
+#[logos(skip r"[ \t\r\n\f]+")]
 pub enum Token {
     // ===== Literals =====
-    #[regex(r"[0-9]+", |lex| lex.slice().parse::<i64>().unwrap())]
+    #[regex(r"[0-9]+", |lex| lex.slice().parse::<i64>().ok())]
     Int(i64),
 
+    #[serde(serialize_with = "ordered_float")]
     #[regex(r"[0-9]+\.[0-9]+([eE][+-]?[0-9]+)?", |lex| OrderedFloat(lex.slice().parse::<f64>().unwrap()))]
     Float(OrderedFloat<f64>),
 
@@ -27,6 +33,10 @@ pub enum Token {
     #[token("false")]
     False,
 
+    /// a string literal holding the text between the quotes, `\"` and `\\` are unescaped
+    #[regex(r#""([^"\\]|\\.)*""#, |lex| unescape(lex.slice()))]
+    String(String),
+
     // ===== Identifiers =====
     #[regex(r"[a-zA-Z_][a-zA-Z0-9_]*", |lex| lex.slice().to_owned())]
     Identifier(String),
@@ -38,6 +48,14 @@ pub enum Token {
     // ===== Operators =====
     #[token("=")]
     Equals,
+    #[token("==")]
+    DoubleEquals,
+    #[token("!=")]
+    NotEquals,
+    #[token(">=")]
+    GreaterEquals,
+    #[token("<=")]
+    LessEquals,
     #[token("&")]
     Ampersand,
     #[token("|")]
@@ -58,6 +76,8 @@ pub enum Token {
     Percent,
     #[token("!")]
     Bang,
+    #[token("^")]
+    Caret,
 
     // ===== Delimiters / punctuation =====
     #[token("(")]
@@ -78,6 +98,8 @@ pub enum Token {
     Semicolon,
     #[token(":")]
     DPoint,
+    #[token("..")]
+    DotDot,
 
     // ===== Keywords =====
     #[token("let")]
@@ -88,38 +110,204 @@ pub enum Token {
     If,
     #[token("else")]
     Else,
+    #[token("plot")]
+    Plot,
+    #[token("plot3d")]
+    Plot3d,
+    #[token("for")]
+    For,
+    #[token("in")]
+    In,
+    #[token("with")]
+    With,
+    #[token("to")]
+    To,
+    #[token("polar")]
+    Polar,
+    #[token("slider")]
+    Slider,
 
-    // ===== Comments (optional) =====
-    #[regex(r"//[^\n]*", logos::skip)]
-    Comment,
+    // ===== Comments =====
+    // never part of the token stream given to the parser, see LexedSource::comments
+    #[regex(r"//[^\n]*", |lex| lex.slice().to_owned())]
+    Comment(String),
 
     // ===== Error =====
     Error,
 }
 
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            Self::Int(x) => return write!(f, "{x}"),
+            Self::Comment(x) => return write!(f, "{x}"),
+            Self::Float(x) => return write!(f, "{x}"),
+            Self::Identifier(x) => return write!(f, "{x}"),
+            Self::String(x) => return write!(f, "\"{}\"", x.replace('\\', "\\\\").replace('"', "\\\"")),
+            Self::True => "true",
+            Self::False => "false",
+            Self::Wildcard => "_",
+            Self::Equals => "=",
+            Self::DoubleEquals => "==",
+            Self::NotEquals => "!=",
+            Self::GreaterEquals => ">=",
+            Self::LessEquals => "<=",
+            Self::Ampersand => "&",
+            Self::Pipe => "|",
+            Self::GreaterThan => ">",
+            Self::LessThan => "<",
+            Self::Plus => "+",
+            Self::Minus => "-",
+            Self::Star => "*",
+            Self::Slash => "/",
+            Self::Percent => "%",
+            Self::Bang => "!",
+            Self::Caret => "^",
+            Self::LParen => "(",
+            Self::RParen => ")",
+            Self::LBrace => "{",
+            Self::RBrace => "}",
+            Self::LBracket => "[",
+            Self::RBracket => "]",
+            Self::Comma => ",",
+            Self::Semicolon => ";",
+            Self::DPoint => ":",
+            Self::DotDot => "..",
+            Self::Let => "let",
+            Self::Fn => "fn",
+            Self::If => "if",
+            Self::Else => "else",
+            Self::Plot => "plot",
+            Self::Plot3d => "plot3d",
+            Self::For => "for",
+            Self::In => "in",
+            Self::With => "with",
+            Self::To => "to",
+            Self::Polar => "polar",
+            Self::Slider => "slider",
+            Self::Error => "invalid token",
+        };
+        f.write_str(text)
+    }
+}
+
+/// the text of a string literal without its quotes and escapes
+fn unescape(literal: &str) -> String {
+    let mut out = String::new();
+    let mut chars = literal[1..literal.len() - 1].chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => out.extend(chars.next()),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
 #[salsa::tracked]
 pub fn lex_source(db: &dyn salsa::Database, code: ProgramSource) -> LexedSource<'_> {
-    let source = code.raw_text(db);
-    println!("running lexer");
-    let tokenstream = Token::lexer(source).spanned()
+    let (tokens, comments) = lex(code.raw_text(db), 0);
+    LexedSource::new(db, tokens, comments)
+}
+
+/// the text of every comment with its span, the comments are not part of the token stream
+type Comments = Vec<(String, Span)>;
+
+/// the tokens and the comments of the text, with their spans moved by the offset of the text in its source
+fn lex(text: &str, offset: usize) -> (Vec<(Token, Span)>, Comments) {
+    let (comments, tokens) = Token::lexer(text).spanned()
         .map(|(tok, span)| {
-            let span = Span::new(span.start, span.end);
+            let span = Span::new(span.start + offset, span.end + offset);
             let token = tok.unwrap_or(Token::Error);
             (token, span)
-        });
-    LexedSource::new(db, tokenstream.collect())
+        })
+        .partition::<Vec<_>, _>(|x| matches!(x.0, Token::Comment(_)));
+    let comments = comments.into_iter()
+        .map(|(token, span)| match token {
+            Token::Comment(x) => (x, span),
+            _ => unreachable!("only comments are partitioned out"),
+        })
+        .collect();
+    (tokens, comments)
+}
+
+/// the source of a single top level statement, including the whitespace and comments before it
+///
+/// only the text is interned, a statement which moves in the program keeps its tokens and syntax tree
+#[salsa::interned(debug)]
+pub struct StatementSource<'db> {
+    #[returns(ref)]
+    pub text: String,
+}
+
+/// splits the program at every `;` outside of any delimiters and string literals so each statement can be lexed and parsed on its own,
+/// every statement is paired with the position of its text in the program source
+#[salsa::tracked(returns(ref))]
+pub fn statement_sources(db: &dyn salsa::Database, code: ProgramSource) -> Vec<(StatementSource<'_>, usize)> {
+    let source = code.raw_text(db);
+    split_statements(source).into_iter()
+        .map(|(start, end)| (StatementSource::new(db, source[start..end].to_owned()), start))
+        .collect()
+}
+
+/// the byte ranges of the top level statements, the last range holds the text after the last statement
+pub fn split_statements(source: &str) -> Vec<(usize, usize)> {
+    let mut out = vec![];
+    let mut start = 0;
+    let mut depth = 0usize;
+    let mut chars = source.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '/' if chars.peek().map(|x| x.1) == Some('/') => {
+                while chars.next_if(|x| x.1 != '\n').is_some() {}
+            },
+            '"' => {
+                while let Some((_, c)) = chars.next() {
+                    match c {
+                        '\\' => _ = chars.next(),
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            },
+            '(' | '{' | '[' => depth += 1,
+            ')' | '}' | ']' => depth = depth.saturating_sub(1),
+            ';' if depth == 0 => {
+                out.push((start, i + 1));
+                start = i + 1;
+            },
+            _ => {}
+        }
+    }
+    if start < source.len() {
+        out.push((start, source.len()));
+    }
+    out
+}
+
+/// the depth of the delimiters left open at the end of the source, used to detect incomplete input
+pub fn open_delimiters(source: &str) -> usize {
+    let mut depth = 0usize;
+    for (token, _) in Token::lexer(source).spanned() {
+        match token {
+            Ok(Token::LParen | Token::LBrace | Token::LBracket) => depth += 1,
+            Ok(Token::RParen | Token::RBrace | Token::RBracket) => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    depth
+}
+
+/// the tokens and comments of a statement, their spans start at the start of the statement text
+#[salsa::tracked]
+pub fn lex_statement<'db>(db: &'db dyn salsa::Database, code: StatementSource<'db>) -> LexedSource<'db> {
+    let (tokens, comments) = lex(code.text(db), 0);
+    LexedSource::new(db, tokens, comments)
 }
 
 #[cfg(test)]
 pub fn lex_to_stream(source: &str) -> Stream<impl Iterator<Item = (Token, Span)>>
 {
-
-    let tokenstream = Token::lexer(source).spanned()
-        .map(|(tok, span)| {
-            let span = Span::new(span.start, span.end);
-            let token = tok.unwrap_or(Token::Error);
-            (token, span)
-        });
-    Stream::from_iter(tokenstream)
+    Stream::from_iter(lex(source, 0).0)
 }
 
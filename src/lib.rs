@@ -0,0 +1,50 @@
+pub mod stream;
+pub mod spanned;
+pub mod lexer;
+pub mod parser;
+pub mod ids;
+pub mod symbols;
+pub mod diagnostics;
+pub mod rename;
+pub mod infer;
+pub mod builtins;
+pub mod eval;
+pub mod output;
+pub mod plot;
+pub mod completion;
+pub mod lsp;
+pub mod format;
+pub mod cst;
+pub mod repl;
+
+#[salsa::db]
+#[derive(Clone, Default)]
+pub struct GraphingDatabase {
+    storage: salsa::Storage<Self>
+}
+impl GraphingDatabase {
+    /// a database calling the callback for every salsa event, used to observe which queries are recomputed
+    pub fn with_event_callback(callback: impl Fn(salsa::Event) + Send + Sync + 'static) -> Self {
+        Self {
+            storage: salsa::Storage::new(Some(Box::new(callback)))
+        }
+    }
+}
+#[salsa::db]
+impl salsa::Database for GraphingDatabase{}
+
+
+#[salsa::input(debug)]
+pub struct ProgramSource {
+    #[returns(ref)]
+    pub raw_text: String
+}
+
+
+#[salsa::accumulator]
+#[derive(Debug)]
+pub struct ParseError {
+    pub start: usize,
+    pub end: usize,
+    pub message: String,
+}
@@ -0,0 +1,429 @@
+use std::{collections::HashMap, io::{self, BufRead, Write}};
+
+use salsa::Setter;
+use serde_json::{json, Value};
+
+use crate::{completion::{completions, CompletionKind}, diagnostics::check_source, infer::infer_definition, parser::compile_source, rename::rename, symbols::{resolve::{resolve_program, DefinitionKind}, ScopeId}, GraphingDatabase, ProgramSource};
+
+use position::LineIndex;
+
+pub mod position;
+pub mod transport;
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const REQUEST_FAILED: i64 = -32803;
+
+// protocol constants for completion items and document symbols
+const COMPLETION_FUNCTION: u64 = 3;
+const COMPLETION_VARIABLE: u64 = 6;
+const COMPLETION_KEYWORD: u64 = 14;
+const COMPLETION_CONSTANT: u64 = 21;
+const SYMBOL_FUNCTION: u64 = 12;
+const SYMBOL_VARIABLE: u64 = 13;
+
+/// a language server over a single database, every open document is a separate program source
+pub struct Server {
+    db: GraphingDatabase,
+    documents: HashMap<String, ProgramSource>,
+    /// the sources of closed documents, reused when a document is opened as inputs are never freed
+    closed: Vec<ProgramSource>,
+    /// the source renamed programs are checked in, reused as inputs are never freed
+    scratch: ProgramSource,
+    shutdown: bool,
+    exited: bool,
+}
+impl Default for Server {
+    fn default() -> Self {
+        let db = GraphingDatabase::default();
+        let scratch = ProgramSource::new(&db, String::new());
+        Self { db, documents: HashMap::new(), closed: vec![], scratch, shutdown: false, exited: false }
+    }
+}
+impl Server {
+    pub fn exited(&self) -> bool {
+        self.exited
+    }
+    /// handles a message from the client and returns the messages to send back
+    pub fn handle(&mut self, message: &Value) -> Vec<Value> {
+        let params = &message["params"];
+        match (message["method"].as_str(), message.get("id")) {
+            (Some(method), Some(id)) => {
+                let response = match self.request(method, params) {
+                    Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+                    Err((code, message)) => json!({"jsonrpc": "2.0", "id": id, "error": {"code": code, "message": message}}),
+                };
+                vec![response]
+            },
+            (Some(method), None) => self.notification(method, params),
+            // responses to requests from the server, none are sent
+            _ => vec![],
+        }
+    }
+
+    fn request(&mut self, method: &str, params: &Value) -> Result<Value, (i64, String)> {
+        if self.shutdown {
+            return Err((INVALID_REQUEST, "the server is shutting down".to_owned()));
+        }
+        match method {
+            "initialize" => Ok(json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "hoverProvider": true,
+                    "completionProvider": {},
+                    "definitionProvider": true,
+                    "documentSymbolProvider": true,
+                    "renameProvider": true,
+                },
+                "serverInfo": {"name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION")},
+            })),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Value::Null)
+            },
+            "textDocument/hover" => self.with_position(params, hover),
+            "textDocument/completion" => self.with_position(params, completion),
+            "textDocument/definition" => {
+                let uri = params["textDocument"]["uri"].clone();
+                self.with_position(params, |db, source, offset| definition(db, source, offset, &uri))
+            },
+            "textDocument/documentSymbol" => {
+                let source = self.document(params)?;
+                Ok(document_symbols(&self.db, source))
+            },
+            "textDocument/rename" => {
+                let uri = params["textDocument"]["uri"].clone();
+                let new_name = params["newName"].as_str()
+                    .ok_or((INVALID_PARAMS, "missing newName".to_owned()))?
+                    .to_owned();
+                let (source, offset) = self.with_position(params, |_, source, offset| (source, offset))?;
+                match rename(&mut self.db, source, self.scratch, offset, &new_name) {
+                    Ok(edits) => {
+                        let index = LineIndex::new(source.raw_text(&self.db));
+                        let edits = edits.iter()
+                            .map(|x| json!({"range": index.range(x.span), "newText": x.new_text}))
+                            .collect::<Vec<_>>();
+                        Ok(json!({"changes": {uri.as_str().unwrap_or_default(): edits}}))
+                    },
+                    Err(diagnostic) => Err((REQUEST_FAILED, diagnostic.message)),
+                }
+            },
+            _ => Err((METHOD_NOT_FOUND, format!("unsupported method {method}"))),
+        }
+    }
+    fn notification(&mut self, method: &str, params: &Value) -> Vec<Value> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default().to_owned();
+        match method {
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.update(uri.clone(), text.to_owned());
+                vec![self.publish_diagnostics(&uri)]
+            },
+            "textDocument/didChange" => {
+                // only full document synchronisation is supported, the last change holds the entire text
+                let Some(text) = params["contentChanges"].as_array().and_then(|x| x.last()).and_then(|x| x["text"].as_str()) else {
+                    return vec![];
+                };
+                self.update(uri.clone(), text.to_owned());
+                vec![self.publish_diagnostics(&uri)]
+            },
+            "textDocument/didClose" => {
+                if let Some(source) = self.documents.remove(&uri) {
+                    source.set_raw_text(&mut self.db).to(String::new());
+                    self.closed.push(source);
+                }
+                vec![json!({
+                    "jsonrpc": "2.0",
+                    "method": "textDocument/publishDiagnostics",
+                    "params": {"uri": uri, "diagnostics": []},
+                })]
+            },
+            "exit" => {
+                self.exited = true;
+                vec![]
+            },
+            _ => vec![],
+        }
+    }
+
+    fn update(&mut self, uri: String, text: String) {
+        match self.documents.get(&uri) {
+            Some(source) => {
+                source.set_raw_text(&mut self.db).to(text);
+            },
+            None => {
+                let source = match self.closed.pop() {
+                    Some(source) => {
+                        source.set_raw_text(&mut self.db).to(text);
+                        source
+                    },
+                    None => ProgramSource::new(&self.db, text),
+                };
+                self.documents.insert(uri, source);
+            }
+        }
+    }
+    fn document(&self, params: &Value) -> Result<ProgramSource, (i64, String)> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        self.documents.get(uri)
+            .copied()
+            .ok_or_else(|| (INVALID_PARAMS, format!("unknown document {uri}")))
+    }
+    fn with_position<T>(
+        &self,
+        params: &Value,
+        f: impl FnOnce(&GraphingDatabase, ProgramSource, usize) -> T
+    ) -> Result<T, (i64, String)> {
+        let source = self.document(params)?;
+        let offset = LineIndex::new(source.raw_text(&self.db))
+            .offset(&params["position"])
+            .ok_or((INVALID_PARAMS, "invalid position".to_owned()))?;
+        Ok(f(&self.db, source, offset))
+    }
+    fn publish_diagnostics(&self, uri: &str) -> Value {
+        let source = self.documents[uri];
+        let index = LineIndex::new(source.raw_text(&self.db));
+        let diagnostics = check_source(&self.db, source).into_iter()
+            .map(|x| {
+                let related = x.notes.iter()
+                    .filter_map(|note| note.span.map(|span| json!({
+                        "location": {"uri": uri, "range": index.range(span)},
+                        "message": note.message,
+                    })))
+                    .collect::<Vec<_>>();
+                json!({
+                    "range": index.range(x.span),
+                    "severity": 1,
+                    "source": env!("CARGO_PKG_NAME"),
+                    "message": x.message,
+                    "relatedInformation": related,
+                })
+            })
+            .collect::<Vec<_>>();
+        json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": {"uri": uri, "diagnostics": diagnostics},
+        })
+    }
+}
+
+fn hover(db: &GraphingDatabase, source: ProgramSource, offset: usize) -> Value {
+    let program = compile_source(db, source);
+    let resolution = resolve_program(db, program);
+    let Some(index) = resolution.definition_at(db, offset) else {
+        return Value::Null;
+    };
+    let definition = &resolution.definitions(db)[index];
+    let contains = |span: &crate::spanned::Span| span.start <= offset && offset <= span.end;
+    let span = definition.sites.iter().copied()
+        .chain(resolution.references_to(db, index).map(|x| x.span))
+        .find(contains);
+    let ty = infer_definition(db, resolution, index);
+    let mut out = json!({
+        "contents": {"kind": "markdown", "value": format!("```\n{}: {ty}\n```", definition.name)},
+    });
+    if let Some(span) = span {
+        out["range"] = LineIndex::new(source.raw_text(db)).range(span);
+    }
+    out
+}
+
+fn completion(db: &GraphingDatabase, source: ProgramSource, offset: usize) -> Value {
+    completions(db, source, offset).iter()
+        .map(|x| {
+            let kind = match x.kind {
+                CompletionKind::Function => COMPLETION_FUNCTION,
+                CompletionKind::Variable => COMPLETION_VARIABLE,
+                CompletionKind::Constant => COMPLETION_CONSTANT,
+                CompletionKind::Keyword => COMPLETION_KEYWORD,
+            };
+            json!({"label": x.label, "kind": kind})
+        })
+        .collect()
+}
+
+fn definition(db: &GraphingDatabase, source: ProgramSource, offset: usize, uri: &Value) -> Value {
+    let program = compile_source(db, source);
+    let resolution = resolve_program(db, program);
+    let Some(index) = resolution.definition_at(db, offset) else {
+        return Value::Null;
+    };
+    let lines = LineIndex::new(source.raw_text(db));
+    resolution.definitions(db)[index].sites.iter()
+        .map(|span| json!({"uri": uri, "range": lines.range(*span)}))
+        .collect()
+}
+
+fn document_symbols(db: &GraphingDatabase, source: ProgramSource) -> Value {
+    let program = compile_source(db, source);
+    let resolution = resolve_program(db, program);
+    let lines = LineIndex::new(source.raw_text(db));
+    resolution.definitions(db).iter()
+        .filter(|x| x.scope == ScopeId::Program(program))
+        .map(|x| {
+            let kind = match x.kind {
+                DefinitionKind::Function => SYMBOL_FUNCTION,
+                _ => SYMBOL_VARIABLE,
+            };
+            let range = lines.range(x.sites[0]);
+            json!({"name": x.name, "kind": kind, "range": range, "selectionRange": range})
+        })
+        .collect()
+}
+
+/// serves the protocol until the client sends the exit notification or closes the input
+pub fn run(mut input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    let mut server = Server::default();
+    while let Some(message) = transport::read_message(&mut input)? {
+        let responses = match message {
+            Ok(message) => server.handle(&message),
+            // the id of a malformed request is unknown, so the error is sent without one
+            Err(err) => vec![json!({"jsonrpc": "2.0", "id": null, "error": {"code": PARSE_ERROR, "message": format!("invalid json: {err}")}})],
+        };
+        for response in responses {
+            transport::write_message(&mut output, &response)?;
+        }
+        if server.exited() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    /// an in process client, every request is answered before the next one is sent
+    struct Client {
+        server: Server,
+        next_id: u64,
+    }
+    impl Client {
+        fn new() -> Self {
+            let mut client = Self { server: Server::default(), next_id: 0 };
+            client.request("initialize", json!({"capabilities": {}}));
+            client.notify("initialized", json!({}));
+            client
+        }
+        fn request(&mut self, method: &str, params: Value) -> Value {
+            self.next_id += 1;
+            let out = self.server.handle(&json!({"jsonrpc": "2.0", "id": self.next_id, "method": method, "params": params}));
+            assert_eq!(out.len(), 1);
+            assert_eq!(out[0]["id"], json!(self.next_id));
+            out[0].clone()
+        }
+        fn notify(&mut self, method: &str, params: Value) -> Vec<Value> {
+            self.server.handle(&json!({"jsonrpc": "2.0", "method": method, "params": params}))
+        }
+        fn open(&mut self, text: &str) -> Vec<Value> {
+            self.notify("textDocument/didOpen", json!({"textDocument": {"uri": "file:///a.gl", "languageId": "gl", "version": 1, "text": text}}))
+        }
+        fn at(&mut self, method: &str, line: u64, character: u64) -> Value {
+            self.request(method, json!({"textDocument": {"uri": "file:///a.gl"}, "position": {"line": line, "character": character}}))["result"].clone()
+        }
+    }
+
+    #[test]
+    fn diagnostics_follow_changes(){
+        let mut client = Client::new();
+        let out = client.open("let a = b;");
+        assert_eq!(out[0]["method"], "textDocument/publishDiagnostics");
+        assert_eq!(out[0]["params"]["diagnostics"][0]["message"], "cannot find `b` in this scope");
+        let out = client.notify("textDocument/didChange", json!({
+            "textDocument": {"uri": "file:///a.gl", "version": 2},
+            "contentChanges": [{"text": "let b = 1;\nlet a = b;"}],
+        }));
+        assert_eq!(out[0]["params"]["diagnostics"], json!([]));
+    }
+    #[test]
+    fn hover(){
+        let mut client = Client::new();
+        client.open("let a = 1.5;\nlet b = a * 2;");
+        let out = client.at("textDocument/hover", 1, 4);
+        assert_eq!(out["contents"]["value"], "```\nb: {float}\n```");
+        assert_eq!(client.at("textDocument/hover", 1, 8)["range"]["start"], json!({"line": 1, "character": 8}));
+    }
+    #[test]
+    fn completion(){
+        let mut client = Client::new();
+        client.open("let a = 1;\nfn foo(x) = { let y = 2; y };");
+        let out = client.at("textDocument/completion", 1, 27);
+        let labels = out.as_array().unwrap().iter().map(|x| x["label"].as_str().unwrap()).collect::<Vec<_>>();
+        assert_eq!(labels[..4], ["y", "x", "foo", "a"]);
+        assert!(labels.contains(&"sin"));
+    }
+    #[test]
+    fn goto_definition(){
+        let mut client = Client::new();
+        client.open("fn f(0) = 1;\nfn f(x) = f(x - 1);");
+        let out = client.at("textDocument/definition", 1, 10);
+        assert_eq!(out.as_array().unwrap().len(), 2);
+        assert_eq!(out[1]["range"]["start"], json!({"line": 1, "character": 3}));
+    }
+    #[test]
+    fn document_symbols(){
+        let mut client = Client::new();
+        client.open("let a = 1;\nfn f(x) = { let y = x; y };");
+        let out = client.request("textDocument/documentSymbol", json!({"textDocument": {"uri": "file:///a.gl"}}));
+        let names = out["result"].as_array().unwrap().iter().map(|x| x["name"].as_str().unwrap()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["f", "a"]);
+    }
+    #[test]
+    fn reopen_reuses_source(){
+        let mut client = Client::new();
+        client.open("let a = b;");
+        let first = client.server.documents["file:///a.gl"];
+        client.notify("textDocument/didClose", json!({"textDocument": {"uri": "file:///a.gl"}}));
+        assert!(client.server.documents.is_empty());
+        let out = client.open("let a = 1;");
+        assert_eq!(client.server.documents["file:///a.gl"], first);
+        assert_eq!(out[0]["params"]["diagnostics"], json!([]));
+        assert_eq!(client.at("textDocument/hover", 0, 4)["contents"]["value"], "```\na: {integer}\n```");
+    }
+    #[test]
+    fn unknown_method(){
+        let mut client = Client::new();
+        let out = client.request("workspace/symbol", json!({}));
+        assert_eq!(out["error"]["code"], json!(METHOD_NOT_FOUND));
+    }
+    #[test]
+    fn stdio_session(){
+        let mut input = vec![];
+        for message in [
+            json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}}),
+            json!({"jsonrpc": "2.0", "method": "textDocument/didOpen", "params": {"textDocument": {"uri": "file:///a.gl", "text": "let a = ;"}}}),
+            json!({"jsonrpc": "2.0", "id": 2, "method": "shutdown"}),
+            json!({"jsonrpc": "2.0", "method": "exit"}),
+        ] {
+            transport::write_message(&mut input, &message).unwrap();
+        }
+        let mut output = vec![];
+        run(Cursor::new(input), &mut output).unwrap();
+        let mut output = Cursor::new(output);
+        let mut messages = vec![];
+        while let Some(x) = transport::read_message(&mut output).unwrap() {
+            messages.push(x.unwrap());
+        }
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[1]["params"]["diagnostics"].as_array().unwrap().len(), 1);
+        assert_eq!(messages[2]["id"], json!(2));
+    }
+    #[test]
+    fn malformed_message(){
+        let mut input = b"Content-Length: 9\r\n\r\n{\"id\": 1,".to_vec();
+        transport::write_message(&mut input, &json!({"jsonrpc": "2.0", "id": 2, "method": "shutdown"})).unwrap();
+        let mut output = vec![];
+        run(Cursor::new(input), &mut output).unwrap();
+        let mut output = Cursor::new(output);
+        let error = transport::read_message(&mut output).unwrap().unwrap().unwrap();
+        assert_eq!(error["error"]["code"], json!(PARSE_ERROR));
+        assert_eq!(error["id"], Value::Null);
+        assert_eq!(transport::read_message(&mut output).unwrap().unwrap().unwrap()["id"], json!(2));
+    }
+}
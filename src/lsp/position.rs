@@ -0,0 +1,55 @@
+use serde_json::{json, Value};
+
+use crate::spanned::Span;
+
+/// converts between byte offsets and the line/utf16 column positions used by the protocol
+pub struct LineIndex<'a> {
+    text: &'a str,
+    /// byte offset of the start of each line
+    lines: Vec<usize>,
+}
+impl<'a> LineIndex<'a> {
+    pub fn new(text: &'a str) -> Self {
+        let lines = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self { text, lines }
+    }
+    pub fn position(&self, offset: usize) -> Value {
+        let offset = offset.min(self.text.len());
+        let line = self.lines.partition_point(|x| *x <= offset) - 1;
+        let character = self.text[self.lines[line]..offset].encode_utf16().count();
+        json!({"line": line, "character": character})
+    }
+    pub fn range(&self, span: Span) -> Value {
+        json!({"start": self.position(span.start), "end": self.position(span.end)})
+    }
+    pub fn offset(&self, position: &Value) -> Option<usize> {
+        let line = position.get("line")?.as_u64()? as usize;
+        let character = position.get("character")?.as_u64()? as usize;
+        let start = *self.lines.get(line)?;
+        let end = self.lines.get(line + 1).copied().unwrap_or(self.text.len());
+        let mut units = 0;
+        for (i, c) in self.text[start..end].char_indices() {
+            if units >= character {
+                return Some(start + i);
+            }
+            units += c.len_utf16();
+        }
+        Some(end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offsets(){
+        let index = LineIndex::new("let a = 1;\nlet é = a;\n");
+        assert_eq!(index.position(15), json!({"line": 1, "character": 4}));
+        assert_eq!(index.position(17), json!({"line": 1, "character": 5}));
+        assert_eq!(index.offset(&json!({"line": 1, "character": 5})), Some(17));
+        assert_eq!(index.offset(&json!({"line": 0, "character": 4})), Some(4));
+    }
+}
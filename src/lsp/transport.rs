@@ -0,0 +1,62 @@
+use std::io::{self, BufRead, Write};
+
+use serde_json::Value;
+
+/// reads a single message, None when the input is closed and the json error when the body is not valid json
+pub fn read_message(input: &mut impl BufRead) -> io::Result<Option<Result<Value, serde_json::Error>>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':')
+            && name.eq_ignore_ascii_case("Content-Length")
+        {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+    let length = length.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length header"))?;
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    Ok(Some(serde_json::from_slice(&body)))
+}
+
+pub fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = serde_json::to_string(message)?;
+    write!(output, "Content-Length: {}\r\n\r\n{body}", body.len())?;
+    output.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn roundtrip(){
+        let mut buffer = vec![];
+        write_message(&mut buffer, &json!({"jsonrpc": "2.0", "method": "exit"})).unwrap();
+        write_message(&mut buffer, &json!({"text": "é"})).unwrap();
+        let mut input = Cursor::new(buffer);
+        assert_eq!(read_message(&mut input).unwrap().unwrap().unwrap(), json!({"jsonrpc": "2.0", "method": "exit"}));
+        assert_eq!(read_message(&mut input).unwrap().unwrap().unwrap(), json!({"text": "é"}));
+        assert!(read_message(&mut input).unwrap().is_none());
+    }
+    #[test]
+    fn malformed_body(){
+        let mut buffer = b"Content-Length: 9\r\n\r\n{\"id\": 1,".to_vec();
+        write_message(&mut buffer, &json!({"jsonrpc": "2.0", "method": "exit"})).unwrap();
+        let mut input = Cursor::new(buffer);
+        assert!(read_message(&mut input).unwrap().unwrap().is_err());
+        assert_eq!(read_message(&mut input).unwrap().unwrap().unwrap(), json!({"jsonrpc": "2.0", "method": "exit"}));
+        assert!(read_message(&mut input).unwrap().is_none());
+    }
+}
@@ -1,99 +1,321 @@
-use chumsky::{extra::ParserExtra, input::{IterInput, ValueInput}, pratt::{self, infix, left}, prelude::*};
-use logos::Logos;
-use ordered_float::OrderedFloat;
+use std::{io::{self, IsTerminal, Read}, path::{Path, PathBuf}, process::ExitCode};
 
+use bpaf::{construct, long, positional, pure, Args, OptionParser, Parser};
+use graphing_language::{diagnostics::{check_source, render, Diagnostic}, eval::{RunError, Value}, format::format_source, lexer::lex_source, lsp, output::{OutputFormat, Record}, plot::{axes::{Axes, NumberFormat, Scale}, plot_source_in, terminal, write_file, FileFormat, ImageSize, Window, DPI_LIMITS, PIXEL_LIMITS}, repl::{self, commands, highlight::color_enabled}, spanned::Spanned, GraphingDatabase, ProgramSource};
 
-mod stream;
-mod spanned;
-mod lexer;
-mod parser;
-mod ids;
-mod symbols;
-use ids::*;
-use parser::*;
-use lexer::*;
-use salsa::Setter;
-use stream::Stream;
-use spanned::Span;
-use symbols::{create_scope_parent_table, create_symbol_table};
-
-#[salsa::db]
-#[derive(Clone, Default)]
-struct GraphingDatabase {
-    storage: salsa::Storage<Self>
-}
-#[salsa::db]
-impl salsa::Database for GraphingDatabase{}
+/// the program ran or was checked without problems
+const EXIT_OK: u8 = 0;
+/// the program has errors, they are printed to stderr
+const EXIT_INVALID: u8 = 1;
+/// the arguments could not be parsed
+const EXIT_USAGE: u8 = 2;
+/// the program is valid but evaluating it failed
+const EXIT_RUNTIME: u8 = 3;
+/// reading the input or writing the output failed
+const EXIT_IO: u8 = 4;
 
+const EXIT_CODES: &str = "\
+Exit codes:
+ 0 success
+ 1 the program has errors
+ 2 invalid arguments
+ 3 evaluating the program failed
+ 4 reading or writing failed";
 
-#[salsa::input(debug)]
-pub struct ProgramSource {
-    #[returns(ref)]
-    raw_text: String
+#[derive(Debug, Clone)]
+enum Command {
+    /// start an interactive session after loading the definitions of the file, the default without a command
+    Repl(Option<PathBuf>),
+    /// serve the language server protocol over stdin and stdout
+    Lsp,
+    /// evaluate the file, print the value of every top level binding and draw or write its plots
+    Run(OutputFormat, Option<FileFormat>, ImageSize, Axes, Window, String),
+    /// print the problems in the file without evaluating it
+    Check(OutputFormat, String),
+    /// print the file formatted to stdout
+    Fmt(String),
+    /// print every token of the file
+    Tokens(OutputFormat, String),
+    /// print the syntax tree of every statement of the file
+    Ast(OutputFormat, String),
 }
 
-
-#[salsa::accumulator]
-#[derive(Debug)]
-pub struct ParseError {
-    pub start: usize,
-    pub end: usize,
-    pub message: String,
+fn options() -> OptionParser<Command> {
+    let file = || positional::<String>("FILE").help("the program to read, - for stdin");
+    let load = || long("load")
+        .help("load the definitions of a file written by :save")
+        .argument::<PathBuf>("FILE")
+        .optional()
+        .map(Command::Repl);
+    let repl = load()
+        .to_options()
+        .descr("Start an interactive session")
+        .command("repl");
+    let lsp = pure(Command::Lsp)
+        .to_options()
+        .descr("Run the language server over stdin and stdout")
+        .command("lsp");
+    let format = || long("format")
+        .help("write human readable output or one json object per line")
+        .argument::<OutputFormat>("human|json")
+        .fallback(OutputFormat::Human);
+    let svg = long("svg")
+        .help("write every plot as svg, plots without a `to` file are written to FILE-<n>.svg in the current directory")
+        .req_flag(FileFormat::Svg);
+    let png = long("png")
+        .help("write every plot as png, plots without a `to` file are written to FILE-<n>.png in the current directory")
+        .req_flag(FileFormat::Png);
+    let files = construct!([svg, png]).optional();
+    let pixels = |name: &'static str, help: &'static str| long(name)
+        .help(help)
+        .argument::<f64>("PIXELS")
+        .guard(|x| (PIXEL_LIMITS.0..=PIXEL_LIMITS.1).contains(x), "the size has to be between 16 and 4096 pixels")
+        .optional();
+    let width = pixels("width", "the width of written plots which do not give one, in pixels at 96 dpi");
+    let height = pixels("height", "the height of written plots which do not give one, in pixels at 96 dpi");
+    let dpi = long("dpi")
+        .help("the resolution of png plots which do not give one")
+        .argument::<f64>("DPI")
+        .guard(|x| (DPI_LIMITS.0..=DPI_LIMITS.1).contains(x), "the dpi has to be between 24 and 384")
+        .optional();
+    let size = construct!(ImageSize { width, height, dpi });
+    let scale = |name: &'static str, help: &'static str| long(name)
+        .help(help)
+        .argument::<String>("linear|log")
+        .parse(|x| Scale::of(&x).ok_or(format!("unknown scale `{x}`, expected linear or log")))
+        .optional();
+    let x_scale = scale("xscale", "the scale of the x axis of plots which do not give one");
+    let y_scale = scale("yscale", "the scale of the y axis of plots which do not give one");
+    let x_label = long("xlabel").help("the label of the x axis of plots which do not give one").argument::<String>("TEXT").optional();
+    let y_label = long("ylabel").help("the label of the y axis of plots which do not give one").argument::<String>("TEXT").optional();
+    let grid = long("grid")
+        .help("draw lines across plots at the ticks, unless a plot turns them on or off itself")
+        .argument::<String>("on|off")
+        .parse(|x| match x.as_str() {
+            "on" => Ok(true),
+            "off" => Ok(false),
+            _ => Err(format!("unknown grid `{x}`, expected on or off")),
+        })
+        .optional();
+    let format_numbers = long("numbers")
+        .help("write the tick labels plainly or with si prefixes like k and m, the `format` option of a plot wins over it")
+        .argument::<String>("plain|si")
+        .parse(|x| NumberFormat::of(&x).ok_or(format!("unknown number format `{x}`, expected plain or si")))
+        .optional();
+    let axes = construct!(Axes { x_scale, y_scale, x_label, y_label, grid, format(format_numbers) });
+    let range = |name: &'static str, help: &'static str| long(name)
+        .help(help)
+        .argument::<String>("MIN..MAX")
+        .parse(|x| parse_range(&x))
+        .optional();
+    let x_range = range("xrange", "sample and draw every plot over this x range instead of its own");
+    let y_range = range("yrange", "draw every plot over this y range instead of its own");
+    let window = construct!(x_range, y_range).map(|(x_range, y_range)| Window { x_range, y_range, ..Default::default() });
+    let run = construct!(Command::Run(format(), files, size, axes, window, file()))
+        .to_options()
+        .descr("Evaluate the file, print the top level bindings and draw its plots")
+        .command("run");
+    let check = construct!(Command::Check(format(), file()))
+        .to_options()
+        .descr("Report the problems in the file without evaluating it")
+        .command("check");
+    let fmt = file()
+        .map(Command::Fmt)
+        .to_options()
+        .descr("Print the file in canonical formatting")
+        .command("fmt");
+    let tokens = construct!(Command::Tokens(format(), file()))
+        .to_options()
+        .descr("Print the tokens of the file")
+        .command("tokens");
+    let ast = construct!(Command::Ast(format(), file()))
+        .to_options()
+        .descr("Print the syntax tree of the file")
+        .command("ast");
+    let default = load();
+    construct!([repl, lsp, run, check, fmt, tokens, ast, default])
+        .to_options()
+        .descr("An in development graphing programming language")
+        .footer(EXIT_CODES)
+        .version(env!("CARGO_PKG_VERSION"))
 }
 
+/// the range written as two numbers with `..` between them, like -1..2.5
+fn parse_range(text: &str) -> Result<(f64, f64), String> {
+    let error = || format!("invalid range `{text}`, expected two numbers like -1..2.5");
+    let (min, max) = text.split_once("..").ok_or_else(error)?;
+    let (min, max) = (min.trim().parse::<f64>().map_err(|_| error())?, max.trim().parse::<f64>().map_err(|_| error())?);
+    if min.is_finite() && max.is_finite() && min < max {
+        Ok((min, max))
+    } else {
+        Err(format!("invalid range `{text}`, the start has to be below the end"))
+    }
+}
 
+/// the text of the file, or of stdin when the path is -
+fn read_input(path: &str) -> io::Result<String> {
+    if path == "-" {
+        let mut text = String::new();
+        io::stdin().read_to_string(&mut text)?;
+        Ok(text)
+    } else {
+        std::fs::read_to_string(path)
+    }
+}
 
+/// a loaded program with the name used when reporting problems
+struct Input {
+    db: GraphingDatabase,
+    source: ProgramSource,
+    name: String,
+}
+impl Input {
+    fn load(path: &str) -> Result<Self, ExitCode> {
+        let text = read_input(path).map_err(|err| {
+            eprintln!("unable to read {path}: {err}");
+            ExitCode::from(EXIT_IO)
+        })?;
+        let db = GraphingDatabase::default();
+        let source = ProgramSource::new(&db, text);
+        let name = if path == "-" { "<stdin>".to_owned() } else { path.to_owned() };
+        Ok(Self { db, source, name })
+    }
+    fn report(&self, diagnostics: &[Diagnostic]) {
+        eprint!("{}", render(diagnostics, &self.name, self.source.raw_text(&self.db), io::stderr().is_terminal()));
+    }
+    /// writes the problems to stderr, or as json lines to stdout
+    fn report_as(&self, format: OutputFormat, diagnostics: &[Diagnostic]) {
+        match format {
+            OutputFormat::Human => self.report(diagnostics),
+            OutputFormat::Json => for diagnostic in diagnostics {
+                print!("{}", Record::Diagnostic(diagnostic).to_line());
+            },
+        }
+    }
+}
 
+fn execute(command: Command) -> Result<(), ExitCode> {
+    match command {
+        Command::Repl(load) => repl::run(load.as_deref()).map_err(|err| {
+            eprintln!("repl failed: {err}");
+            ExitCode::from(EXIT_IO)
+        }),
+        Command::Lsp => lsp::run(io::stdin().lock(), io::stdout().lock()).map_err(|err| {
+            eprintln!("language server failed: {err}");
+            ExitCode::from(EXIT_IO)
+        }),
+        Command::Run(format, files, size, axes, window, path) => {
+            let input = Input::load(&path)?;
+            let print = |values: &[(String, Value)]| for (name, value) in values {
+                match format {
+                    OutputFormat::Human => println!("{name} = {value}"),
+                    OutputFormat::Json => print!("{}", Record::Value { name, value }.to_line()),
+                }
+            };
+            match plot_source_in(&input.db, input.source, Window { x_scale: axes.x_scale, ..window }) {
+                Ok((values, plots)) => {
+                    print(&values);
+                    let stem = match Path::new(&path).file_stem() {
+                        Some(stem) if path != "-" => stem.to_string_lossy().into_owned(),
+                        _ => "plot".to_owned(),
+                    };
+                    let (width, height) = terminal::DEFAULT_SIZE;
+                    for (i, plot) in plots.into_iter().enumerate() {
+                        let mut plot = plot.with_axes(&axes);
+                        if let (None, Some(files)) = (&plot.output, files) {
+                            plot.output = Some(format!("{stem}-{}.{}", i + 1, files.extension()));
+                        }
+                        if let Some(output) = &plot.output {
+                            write_file(&plot, Path::new(output), size).map_err(|message| {
+                                eprintln!("{message}");
+                                ExitCode::from(EXIT_IO)
+                            })?;
+                        }
+                        match (format, &plot.output) {
+                            (OutputFormat::Human, Some(output)) => println!("plot written to {output}"),
+                            (OutputFormat::Human, None) => print!("{}", terminal::render_plot(&plot, width, height, color_enabled(io::stdout().is_terminal()))),
+                            (OutputFormat::Json, _) => print!("{}", Record::Plot(&plot).to_line()),
+                        }
+                    }
+                    Ok(())
+                },
+                Err(RunError::Invalid(diagnostics)) => {
+                    input.report_as(format, &diagnostics);
+                    Err(ExitCode::from(EXIT_INVALID))
+                },
+                Err(RunError::Failed(values, err)) => {
+                    print(&values);
+                    input.report_as(format, &[err.into()]);
+                    Err(ExitCode::from(EXIT_RUNTIME))
+                },
+            }
+        },
+        Command::Check(format, path) => {
+            let input = Input::load(&path)?;
+            let diagnostics = check_source(&input.db, input.source);
+            if diagnostics.is_empty() {
+                Ok(())
+            } else {
+                input.report_as(format, &diagnostics);
+                Err(ExitCode::from(EXIT_INVALID))
+            }
+        },
+        Command::Fmt(path) => {
+            let input = Input::load(&path)?;
+            match format_source(&input.db, input.source) {
+                Ok(formatted) => {
+                    println!("{formatted}");
+                    Ok(())
+                },
+                Err(diagnostics) => {
+                    input.report(&diagnostics);
+                    Err(ExitCode::from(EXIT_INVALID))
+                }
+            }
+        },
+        Command::Tokens(OutputFormat::Human, path) => {
+            let input = Input::load(&path)?;
+            print!("{}", commands::tokens(&input.db, input.source));
+            Ok(())
+        },
+        Command::Tokens(OutputFormat::Json, path) => {
+            let input = Input::load(&path)?;
+            for (token, span) in lex_source(&input.db, input.source).tokens(&input.db) {
+                println!("{}", serde_json::to_string(&Spanned::new(token.clone(), *span)).expect("tokens serialize to json"));
+            }
+            Ok(())
+        },
+        Command::Ast(format, path) => {
+            let input = Input::load(&path)?;
+            let tree = match format {
+                OutputFormat::Human => commands::program_ast(&input.db, input.source),
+                OutputFormat::Json => commands::program_json(&input.db, input.source),
+            };
+            match tree {
+                Ok(tree) => {
+                    print!("{tree}");
+                    Ok(())
+                },
+                Err(diagnostics) => {
+                    input.report_as(format, &diagnostics);
+                    Err(ExitCode::from(EXIT_INVALID))
+                }
+            }
+        },
+    }
+}
 
-fn main(){
-    return;
-    let mut db = GraphingDatabase::default();
-    println!("run 1");
-    let src = r#"
-    let x = 1;
-    let a = 2;
-    fn foo(x) = {
-        let a = 2 * x;
-        a
+fn main() -> ExitCode {
+    let command = match options().run_inner(Args::current_args()) {
+        Ok(command) => command,
+        Err(failure) => {
+            failure.print_message(100);
+            let code = failure.exit_code();
+            return ExitCode::from(if code == 0 { EXIT_OK } else { EXIT_USAGE });
+        }
     };
-    let y = {
-        fn bar(x) = 2 * x;
-        2 * 3 //bar(x)
-    };
-    "#;
-    let src = ProgramSource::new(&db, src.to_owned());
-    let lexed = lex_source(&db, src);
-    let compiled = compile_tokenstream(&db, lexed);
-    
-    let parent_map = create_scope_parent_table(&db, compiled);
-    let symbol_table = create_symbol_table(&db, compiled);
-    let table = symbol_table.items(&db);
-    // simulate a change to the code
-    let src2 = r#"
-    let x = 1;
-    let a = 2;
-    fn foo(x) = {
-        let a = 2 * x;
-        a
-    };
-    let y = {
-        fn bar(x) = 2 * x;
-        2 * 3 //bar(x)
-    };
-    "#;
-    let a = src.set_raw_text(&mut db).to(src2.to_owned());
-    println!("run 2");
-
-    let lexed = lex_source(&db, src);
-    let compiled = compile_tokenstream(&db, lexed);
-    
-    let parent_map = create_scope_parent_table(&db, compiled);
-    let symbol_table = create_symbol_table(&db, compiled);
-    let table = symbol_table.items(&db);
-
-    //let compiled = compile_tokenstream(&db, lexed);
-    //let compiled = compiled.statements(&db);
-
-
-    //println!("{compiled:#?}");
-}
\ No newline at end of file
+    match execute(command) {
+        Ok(()) => ExitCode::from(EXIT_OK),
+        Err(code) => code,
+    }
+}
@@ -0,0 +1,75 @@
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::{diagnostics::Diagnostic, eval::Value, plot::SampledPlot};
+
+/// how the results of a command are written
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum OutputFormat {
+    /// values as `name = value` and diagnostics rendered for a terminal
+    #[default]
+    Human,
+    /// one json object per line, see [`Record`]
+    Json,
+}
+impl FromStr for OutputFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(Self::Human),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("unknown format `{s}`, expected human or json")),
+        }
+    }
+}
+
+/// a single line of json output, the `type` field tells the kinds apart
+#[derive(PartialEq, Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Record<'a> {
+    Diagnostic(&'a Diagnostic),
+    /// a top level binding with its evaluated value
+    Value {
+        name: &'a str,
+        value: &'a Value,
+    },
+    /// a top level plot with the points of every series
+    Plot(&'a SampledPlot),
+}
+impl Record<'_> {
+    /// the record as a json object followed by a newline
+    pub fn to_line(&self) -> String {
+        let mut out = serde_json::to_string(self).expect("records contain no maps with non string keys");
+        out.push('\n');
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{plot::Series, spanned::Span};
+
+    use super::*;
+
+    #[test]
+    fn records(){
+        let diagnostic = Diagnostic::error(Span::new(4, 5), "cannot find `b` in this scope").with_note(None, "did you mean `a`");
+        assert_eq!(
+            Record::Diagnostic(&diagnostic).to_line(),
+            "{\"type\":\"diagnostic\",\"span\":{\"start\":4,\"end\":5},\"severity\":\"error\",\"message\":\"cannot find `b` in this scope\",\"notes\":[{\"span\":null,\"message\":\"did you mean `a`\"}]}\n"
+        );
+        let value = Value::Tuple(vec![Value::Int(1), Value::Float(0.5), Value::Builtin("sin")]);
+        assert_eq!(Record::Value { name: "a", value: &value }.to_line(), "{\"type\":\"value\",\"name\":\"a\",\"value\":[1,0.5,\"<fn>\"]}\n");
+        let plot = SampledPlot { title: None, x_range: (0.0, 1.0), y_range: None, series: vec![Series { label: "x".to_owned(), lines: vec![vec![(0.0, 0.0), (1.0, 1.0)]] }], output: None, size: Default::default(), equal_aspect: false, surface: None, axes: Default::default() };
+        assert_eq!(
+            Record::Plot(&plot).to_line(),
+            "{\"type\":\"plot\",\"title\":null,\"x_range\":[0.0,1.0],\"y_range\":null,\"series\":[{\"label\":\"x\",\"lines\":[[[0.0,0.0],[1.0,1.0]]]}],\"output\":null,\"size\":{\"width\":null,\"height\":null,\"dpi\":null},\"equal_aspect\":false,\"axes\":{\"x_scale\":null,\"y_scale\":null,\"x_label\":null,\"y_label\":null,\"grid\":null,\"format\":null}}\n"
+        );
+    }
+    #[test]
+    fn parse(){
+        assert_eq!("json".parse(), Ok(OutputFormat::Json));
+        assert!("xml".parse::<OutputFormat>().is_err());
+    }
+}
@@ -1,7 +1,5 @@
-use std::hash::DefaultHasher;
-
 use chumsky::{input::ValueInput, prelude::*};
-use crate::{ids::BlockId, lexer::Token, spanned::{Span, Spanned, SpannedParser}};
+use crate::{lexer::Token, parser::Extra, spanned::{Span, Spanned, SpannedParser}};
 
 use super::{expression::Expression, statement::Statement};
 
@@ -18,9 +16,9 @@ pub struct BlockExpression<'db>{
 impl<'db> BlockExpression<'db> {
     pub fn parser<'src, I: ValueInput<'src, Span = Span, Token = Token>>(
         db: &'db dyn salsa::Database,
-        expr_parser: impl Parser<'src, I, Expression<'db>> + 'src + Clone,
-        statement_parser: impl Parser<'src, I, Statement<'db>> + 'src + Clone,
-    ) -> impl Parser<'src, I, Self> + Clone
+        expr_parser: impl Parser<'src, I, Expression<'db>, Extra<'src>> + 'src + Clone,
+        statement_parser: impl Parser<'src, I, Statement<'db>, Extra<'src>> + 'src + Clone,
+    ) -> impl Parser<'src, I, Self, Extra<'src>> + Clone
     where 'db: 'src
     {
         let statements = statement_parser
@@ -1,7 +1,7 @@
-use crate::{ids::VariableId, lexer::Token, spanned::{Span, Spanned, SpannedParser}};
+use crate::{ids::{FunctionId, VariableId}, lexer::Token, parser::Extra, spanned::{Span, Spanned, SpannedParser}};
 
-use super::{block::BlockExpression, literal::Literal, operations::BinaryOp, statement::Statement};
-use chumsky::{input::{MapExtra, ValueInput}, pratt::{infix, left}, prelude::*};
+use super::{block::BlockExpression, literal::Literal, operations::BinaryOp, slider::Slider, statement::Statement};
+use chumsky::{input::ValueInput, pratt::{infix, left, prefix, right}, prelude::*};
 
 
 
@@ -13,63 +13,105 @@ pub enum Expression<'db> {
     Literal(Spanned<Literal>),
     /// a variable expression
     Variable(Spanned<VariableId<'db>>),
+    /// a negated expression -a
+    Negate(Box<Spanned<Expression<'db>>>),
     /// a binary expression of the form (a operand b)
     Binary(Box<Spanned<Expression<'db>>>, Spanned<BinaryOp>, Box<Spanned<Expression<'db>>>),
+    /// a tuple expression (a, b), a single element needs a trailing comma (a,) as (a) only groups it
+    Tuple(Vec<Spanned<Expression<'db>>>),
     /// a function call expression foo(a, b)
-    FunctionCall,
+    FunctionCall(Spanned<FunctionId<'db>>, Vec<Spanned<Expression<'db>>>),
     /// a block expression let a = { let x = 2; x * 3 }
     Block(BlockExpression<'db>),
+    /// a number which is changed while the program runs, only parsed as the value of a let statement
+    Slider(Box<Slider<'db>>),
     /// an if expression if foo { a } else { b }
+    #[allow(dead_code)] // not parsed yet
     If
 }
 impl<'db> Expression<'db> {
     pub fn parser<'src, I: ValueInput<'src, Span = Span, Token = Token>>(
         db: &'db dyn salsa::Database,
-        statement_parser: impl Parser<'src, I, Statement<'db>> + 'src + Clone
-    ) -> impl Parser<'src, I, Self> + Clone
+        statement_parser: impl Parser<'src, I, Statement<'db>, Extra<'src>> + 'src + Clone
+    ) -> impl Parser<'src, I, Self, Extra<'src>> + Clone
     where 'db: 'src{
         recursive(move |atom| {
             let todo = just(Token::Wildcard)
                 .ignored()
                 .spanned()
-                .map(|x| Self::Todo(x));
+                .map(Self::Todo);
 
             let literal = Literal::parser::<'src, I>()
                 .spanned()
-                .map(|x| Self::Literal(x));
+                .map(Self::Literal);
 
             let variable = VariableId::parser(db)
                 .spanned()
-                .map(|x| Self::Variable(x));
+                .map(Self::Variable);
 
+            let call = FunctionId::parser(db)
+                .spanned()
+                .then(
+                    atom.clone()
+                        .spanned()
+                        .separated_by(just(Token::Comma))
+                        .allow_trailing()
+                        .collect::<Vec<_>>()
+                        .delimited_by(just(Token::LParen), just(Token::RParen))
+                )
+                .map(|(name, args)| Self::FunctionCall(name, args));
+
+            // parentheses around a single expression without a trailing comma only group it
             let parens = atom.clone()
-                .delimited_by(just(Token::LParen), just(Token::RParen));
+                .spanned()
+                .then(just(Token::Comma).ignore_then(atom.clone().spanned()).repeated().collect::<Vec<_>>())
+                .then(just(Token::Comma).or_not())
+                .or_not()
+                .delimited_by(just(Token::LParen), just(Token::RParen))
+                .map(|parts| match parts {
+                    Some(((first, rest), None)) if rest.is_empty() => first.into_inner(),
+                    Some(((first, rest), _)) => Self::Tuple(std::iter::once(first).chain(rest).collect()),
+                    None => Self::Tuple(vec![]),
+                });
 
             let block = BlockExpression::parser(db, atom.clone(), statement_parser)
-                .map(|x| Self::Block(x));
+                .map(Self::Block);
 
 
             let atom = choice((
                 todo,
                 literal,
+                call,
                 variable,
                 parens,
                 block
             )).spanned();
 
 
+            // negation binds tighter than the other operators except ^, -x^2 is -(x^2), comparisons bind the loosest
             atom.pratt((
-                infix(left(2), BinaryOp::multiply().spanned(), |l, op, r, e| {
+                infix(right(5), BinaryOp::power().spanned(), |l, op, r, e| {
                     Spanned::new(Self::Binary(Box::new(l), op, Box::new(r)), e.span())
                 }),
-                infix(left(2), BinaryOp::divide().spanned(), |l, op, r, e| {
+                prefix(4, just(Token::Minus), |_, x, e| {
+                    Spanned::new(Self::Negate(Box::new(x)), e.span())
+                }),
+
+                infix(left(3), BinaryOp::multiply().spanned(), |l, op, r, e| {
+                    Spanned::new(Self::Binary(Box::new(l), op, Box::new(r)), e.span())
+                }),
+                infix(left(3), BinaryOp::divide().spanned(), |l, op, r, e| {
                     Spanned::new(Self::Binary(Box::new(l), op, Box::new(r)), e.span())
                 }),
 
-                infix(left(1), BinaryOp::add().spanned(), |l, op, r, e| {
+                infix(left(2), BinaryOp::add().spanned(), |l, op, r, e| {
                     Spanned::new(Self::Binary(Box::new(l), op, Box::new(r)), e.span())
                 }),
-                infix(left(1), BinaryOp::subtract().spanned(), |l, op, r, e| {
+                infix(left(2), BinaryOp::subtract().spanned(), |l, op, r, e| {
+                    Spanned::new(Self::Binary(Box::new(l), op, Box::new(r)), e.span())
+                }),
+
+                infix(left(1), BinaryOp::compare().spanned(), |l, op, r, e| {
                     Spanned::new(Self::Binary(Box::new(l), op, Box::new(r)), e.span())
                 }),
             )).map(|x| 
@@ -84,7 +126,7 @@ impl<'db> Expression<'db> {
 
 #[cfg(test)]
 mod tests {
-    use crate::{lexer::{lex_source, lex_to_stream}, stream::Stream, GraphingDatabase, ProgramSource};
+    use crate::{lexer::lex_source, stream::Stream, GraphingDatabase, ProgramSource};
 
     use super::*;
 
@@ -92,7 +134,7 @@ mod tests {
     fn compile_expression<'db>(db: &'db dyn salsa::Database, code: ProgramSource) -> Expression<'db> {
         let lexed = lex_source(db, code);
         let tokenstream = lexed.tokens(db);
-        let stream = Stream::from_iter(tokenstream.to_owned().into_iter());
+        let stream = Stream::from_iter(tokenstream.iter().cloned());
         let sp = Statement::parser(db);
         Expression::parser(db, sp).parse(stream).unwrap()
     }
@@ -123,7 +165,7 @@ mod tests {
         let dbs = GraphingDatabase::default();
         let code = ProgramSource::new(&dbs, "a + b".to_owned());
         let out = compile_expression(&dbs, code);
-        let Expression::Binary(a, b, c) = out else { panic!() };
+        let Expression::Binary(_, b, _) = out else { panic!() };
         assert_eq!(*b, BinaryOp::Add);
     }
     #[test]
@@ -131,9 +173,9 @@ mod tests {
         let dbs = GraphingDatabase::default();
         let code = ProgramSource::new(&dbs, "(a + b) * c".to_owned());
         let out = compile_expression(&dbs, code);
-        let Expression::Binary(a, b, c) = out else { panic!() };
+        let Expression::Binary(a, b, _) = out else { panic!() };
         assert_eq!(*b, BinaryOp::Multiply);
-        let Expression::Binary(a, b, c) = &**a else { panic!() };
+        let Expression::Binary(_, b, _) = &**a else { panic!() };
         assert_eq!(**b, BinaryOp::Add);
     }
     #[test]
@@ -155,4 +197,50 @@ mod tests {
         assert_eq!(x.statements(&dbs).len(), 0);
         assert!(x.return_expr(&dbs).is_some());
     }
-}
\ No newline at end of file
+    #[test]
+    fn call(){
+        let dbs = GraphingDatabase::default();
+        let code = ProgramSource::new(&dbs, "fib(x - 1, 2) + a".to_owned());
+        let out = compile_expression(&dbs, code);
+        let Expression::Binary(l, _, r) = out else { panic!() };
+        let Expression::FunctionCall(name, args) = &**l else { panic!() };
+        assert_eq!(name.text(&dbs), "fib");
+        assert_eq!(args.len(), 2);
+        assert!(matches!(**r, Expression::Variable(_)));
+    }
+    #[test]
+    fn power(){
+        let dbs = GraphingDatabase::default();
+        let code = ProgramSource::new(&dbs, "-x ^ 2 ^ 3 * 2".to_owned());
+        let out = compile_expression(&dbs, code);
+        let Expression::Binary(l, op, _) = out else { panic!() };
+        assert_eq!(*op, BinaryOp::Multiply);
+        let Expression::Negate(x) = &**l else { panic!() };
+        let Expression::Binary(_, op, r) = &***x else { panic!() };
+        assert_eq!(**op, BinaryOp::Power);
+        assert!(matches!(&***r, Expression::Binary(..)));
+    }
+    #[test]
+    fn comparison(){
+        let dbs = GraphingDatabase::default();
+        let code = ProgramSource::new(&dbs, "x ^ 2 + y ^ 2 <= 1 - a".to_owned());
+        let out = compile_expression(&dbs, code);
+        let Expression::Binary(l, op, r) = out else { panic!() };
+        assert_eq!(*op, BinaryOp::LessEqual);
+        assert!(matches!(&**l, Expression::Binary(_, op, _) if **op == BinaryOp::Add));
+        assert!(matches!(&**r, Expression::Binary(_, op, _) if **op == BinaryOp::Subtract));
+    }
+    #[test]
+    fn tuple(){
+        let dbs = GraphingDatabase::default();
+        let parts = |src: &str| match compile_expression(&dbs, ProgramSource::new(&dbs, src.to_owned())) {
+            Expression::Tuple(x) => Some(x.len()),
+            _ => None,
+        };
+        assert_eq!(parts("(cos(t), sin(t))"), Some(2));
+        assert_eq!(parts("(a, (b, c),)"), Some(2));
+        assert_eq!(parts("(a,)"), Some(1));
+        assert_eq!(parts("()"), Some(0));
+        assert_eq!(parts("((a))"), None);
+    }
+}
@@ -1,6 +1,6 @@
 use chumsky::{input::ValueInput, prelude::*};
 
-use crate::{ids::FunctionId, lexer::Token, spanned::{Span, Spanned, SpannedParser}};
+use crate::{ids::FunctionId, lexer::Token, parser::Extra, spanned::{Span, Spanned, SpannedParser}};
 
 use super::{expression::Expression, pattern::Pattern, statement::Statement, ty::Type};
 
@@ -15,8 +15,8 @@ pub struct Function<'db> {
 impl<'db> Function<'db> {
     pub fn parser<'src, I: ValueInput<'src, Span = Span, Token = Token>>(
         db: &'db dyn salsa::Database,
-        statement_parser: impl Parser<'src, I, Statement<'db>> + 'src + Clone
-    ) -> impl Parser<'src, I, Self> + Clone
+        statement_parser: impl Parser<'src, I, Statement<'db>, Extra<'src>> + 'src + Clone
+    ) -> impl Parser<'src, I, Self, Extra<'src>> + Clone
     where 'db: 'src
     {
         let name = FunctionId::parser(db).spanned();
@@ -59,7 +59,7 @@ mod tests {
     #[salsa::tracked]
     fn test_compile_function<'db>(db: &'db dyn salsa::Database, ls: LexedSource<'db>) -> Function<'db> {
         let tokenstream = ls.tokens(db);
-        let stream = Stream::from_iter(tokenstream.to_owned().into_iter());
+        let stream = Stream::from_iter(tokenstream.iter().cloned());
         let sp = Statement::parser(db);
         Function::parser(db, sp).parse(stream).unwrap()
     }
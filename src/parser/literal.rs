@@ -1,19 +1,21 @@
 use chumsky::{input::ValueInput, prelude::*};
 use ordered_float::OrderedFloat;
+use serde::Serialize;
 
-use crate::{lexer::Token, spanned::{Span, Spanned, SpannedParser}};
+use crate::{lexer::Token, parser::{serialize::ordered_float, Extra}, spanned::Span};
 
 
 /// a parsed literal
-#[derive(PartialEq, Eq, Debug, Hash, salsa::Update, Clone)]
+#[derive(PartialEq, Eq, Debug, Hash, salsa::Update, Clone, Serialize)]
 pub enum Literal {
     // any integer signed or not, stored as an u64 but may represent a negative number
     AbstractInt(u64),
     // any float, may represent a f32
+    #[serde(serialize_with = "ordered_float")]
     AbstractFloat(OrderedFloat<f64>),
 }
 impl Literal {
-    pub fn parser<'src, I: ValueInput<'src, Token = Token, Span = Span>>() -> impl Parser<'src, I, Self> + Clone
+    pub fn parser<'src, I: ValueInput<'src, Token = Token, Span = Span>>() -> impl Parser<'src, I, Self, Extra<'src>> + Clone
     {
         let atom = select! {
             Token::Int(x) => Self::AbstractInt(x as u64),
@@ -1,6 +1,9 @@
 use chumsky::{input::ValueInput, prelude::*};
 
-use crate::{lexer::{LexedSource, Token}, spanned::Span, stream::Stream};
+use salsa::Accumulator;
+
+use self::shift::Shift;
+use crate::{lexer::{lex_source, lex_statement, statement_sources, LexedSource, StatementSource, Token}, spanned::{Span, Spanned, SpannedParser}, stream::Stream, ParseError, ProgramSource};
 
 
 pub mod literal;
@@ -11,8 +14,14 @@ pub mod expression;
 pub mod variable;
 pub mod function;
 pub mod statement;
+pub mod plot;
+pub mod slider;
 pub mod block;
+pub mod serialize;
+pub mod shift;
 
+/// the parser configuration shared by every parser, errors carry the span and the expected tokens
+pub type Extra<'src> = extra::Err<Rich<'src, Token, Span>>;
 
 
 #[salsa::tracked(debug)]
@@ -22,7 +31,7 @@ pub struct Program<'db> {
     pub statements: Vec<statement::Statement<'db>>
 }
 impl<'db> Program<'db> {
-    pub fn parser<'src, I: ValueInput<'src, Span = Span, Token = Token>>(db: &'db dyn salsa::Database) -> impl Parser<'src, I, Self> + Clone
+    pub fn parser<'src, I: ValueInput<'src, Span = Span, Token = Token>>(db: &'db dyn salsa::Database) -> impl Parser<'src, I, Self, Extra<'src>> + Clone
     where 'db: 'src
     {
         statement::Statement::parser(db)
@@ -34,8 +43,135 @@ impl<'db> Program<'db> {
 
 #[salsa::tracked]
 pub fn compile_tokenstream<'db>(db: &'db dyn salsa::Database, tokenstream: LexedSource<'db>) -> Program<'db> {
-    println!("compiling");
     let tokenstream = tokenstream.tokens(db);
-    let stream = Stream::from_iter(tokenstream.to_owned().into_iter());
-    Program::parser(db).parse(stream).unwrap()
-}
\ No newline at end of file
+    let end = tokenstream.last().map(|x| x.1.end).unwrap_or(0);
+    let stream = Stream::from_iter(tokenstream.clone());
+    let (program, errors) = Program::parser(db).parse(stream).into_output_errors();
+    accumulate_errors(db, errors, (0, end));
+    program.unwrap_or_else(|| Program::new(db, vec![]))
+}
+
+/// parses every top level statement on its own, unchanged statements keep the results of the previous revision
+/// even when they moved, only their spans are shifted to the position of the statement
+#[salsa::tracked]
+pub fn compile_source<'db>(db: &'db dyn salsa::Database, code: ProgramSource) -> Program<'db> {
+    let mut statements = vec![];
+    for &(source, offset) in statement_sources(db, code) {
+        let (statement, errors) = parse_statement(db, source);
+        for (span, message) in errors {
+            ParseError { start: span.start + offset, end: span.end + offset, message: message.clone() }.accumulate(db);
+        }
+        statements.extend(statement.as_ref().map(|x| x.shifted(db, offset)));
+    }
+    Program::new(db, statements)
+}
+
+/// the statement and the errors found while parsing it, with spans from the start of the statement text
+#[salsa::tracked(returns(ref))]
+pub fn parse_statement<'db>(db: &'db dyn salsa::Database, code: StatementSource<'db>) -> (Option<statement::Statement<'db>>, Vec<(Span, String)>) {
+    let tokenstream = lex_statement(db, code).tokens(db);
+    let (start, end) = match (tokenstream.first(), tokenstream.last()) {
+        (Some(first), Some(last)) => (first.1.start, last.1.end),
+        // only whitespace and comments
+        _ => return (None, vec![]),
+    };
+    let stream = Stream::from_iter(tokenstream.clone());
+    let (statement, errors) = statement::Statement::parser(db).parse(stream).into_output_errors();
+    (statement, error_spans(errors, (start, end)))
+}
+
+/// parses the entire source as a single expression
+#[salsa::tracked]
+pub fn parse_expression<'db>(db: &'db dyn salsa::Database, code: ProgramSource) -> Option<Spanned<expression::Expression<'db>>> {
+    let tokenstream = lex_source(db, code).tokens(db);
+    let end = tokenstream.last().map(|x| x.1.end).unwrap_or(0);
+    let stream = Stream::from_iter(tokenstream.clone());
+    let (expression, errors) = expression::Expression::parser(db, statement::Statement::parser(db))
+        .spanned()
+        .parse(stream)
+        .into_output_errors();
+    accumulate_errors(db, errors, (0, end));
+    expression
+}
+
+/// reports the errors of a parser as [`ParseError`]s
+fn accumulate_errors(db: &dyn salsa::Database, errors: Vec<Rich<'_, Token, Span>>, bounds: (usize, usize)) {
+    for (span, message) in error_spans(errors, bounds) {
+        ParseError { start: span.start, end: span.end, message }.accumulate(db);
+    }
+}
+
+/// the errors of a parser with their spans kept inside of the parsed tokens, errors at the end of input have an
+/// unbounded span
+fn error_spans(errors: Vec<Rich<'_, Token, Span>>, (start, end): (usize, usize)) -> Vec<(Span, String)> {
+    errors.into_iter()
+        .map(|error| {
+            let error_start = error.span().start.clamp(start, end);
+            let error_end = error.span().end.clamp(error_start, end);
+            (Span::new(error_start, error_end), error.reason().to_string())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use salsa::Setter;
+
+    use crate::{symbols::create_symbol_table, GraphingDatabase};
+
+    use super::*;
+
+    #[test]
+    fn same_as_tokenstream(){
+        let db = GraphingDatabase::default();
+        let code = ProgramSource::new(&db, "let a = 1; // one\nfn foo(x) = { let y = x; y };\n".to_owned());
+        let whole = compile_tokenstream(&db, lex_source(&db, code));
+        let split = compile_source(&db, code);
+        assert_eq!(whole.statements(&db).len(), 2);
+        for (a, b) in whole.statements(&db).iter().zip(split.statements(&db)) {
+            assert_eq!(a.span(&db), b.span(&db));
+        }
+    }
+    #[test]
+    fn errors_per_statement(){
+        let db = GraphingDatabase::default();
+        let code = ProgramSource::new(&db, "let a = ; let b = 2; let c =".to_owned());
+        let program = compile_source(&db, code);
+        assert_eq!(program.statements(&db).len(), 1);
+        let errors = compile_source::accumulated::<ParseError>(&db, code);
+        assert_eq!(errors.iter().map(|x| x.start).collect::<Vec<_>>(), vec![8, 28]);
+    }
+    #[test]
+    fn reuses_unchanged_statements(){
+        let executed = Arc::new(Mutex::new(vec![]));
+        let mut db = GraphingDatabase::with_event_callback({
+            let executed = executed.clone();
+            move |event| if let salsa::EventKind::WillExecute { database_key } = event.kind {
+                executed.lock().unwrap().push(format!("{database_key:?}"));
+            }
+        });
+        let code = ProgramSource::new(&db, "fn foo(x) = { x * 2 }; let a = 1;".to_owned());
+        create_symbol_table(&db, compile_source(&db, code));
+        executed.lock().unwrap().clear();
+
+        code.set_raw_text(&mut db).to("fn foo(x) = { x * 2 }; let a = 2;".to_owned());
+        create_symbol_table(&db, compile_source(&db, code));
+        let count = |query: &str| executed.lock().unwrap().iter()
+            .filter(|x| x.starts_with(query))
+            .count();
+        assert_eq!(count("parse_statement"), 1);
+        assert_eq!(count("function_symbol_table"), 0);
+        assert_eq!(count("variable_symbol_table"), 1);
+
+        // a longer first statement moves the second one, which is still not lexed or parsed again
+        executed.lock().unwrap().clear();
+        code.set_raw_text(&mut db).to("fn foo(x) = { x * 20 }; let a = 2;".to_owned());
+        let program = compile_source(&db, code);
+        create_symbol_table(&db, program);
+        assert_eq!(count("parse_statement"), 1);
+        assert_eq!(count("lex_statement"), 1);
+        assert_eq!(program.statements(&db)[1].span(&db), Span::new(28, 33));
+    }
+}
@@ -1,9 +1,10 @@
 use chumsky::{input::ValueInput, prelude::*};
+use serde::Serialize;
 
-use crate::{lexer::Token, spanned::Span};
+use crate::{lexer::Token, parser::Extra, spanned::Span};
 
 /// operations with 2 operands (a op b)
-#[derive(PartialEq, Eq, Debug, Hash, salsa::Update, Clone)]
+#[derive(PartialEq, Eq, Debug, Hash, salsa::Update, Clone, Serialize)]
 pub enum BinaryOp{
     /// +
     Add,
@@ -13,18 +14,79 @@ pub enum BinaryOp{
     Multiply,
     /// /
     Divide,
+    /// ^, raises the left operand to the power of the right operand
+    Power,
+    /// ==
+    Equal,
+    /// !=
+    NotEqual,
+    /// <
+    Less,
+    /// <=
+    LessEqual,
+    /// >
+    Greater,
+    /// >=
+    GreaterEqual,
 }
 impl BinaryOp {
-    pub fn add<'src, I: ValueInput<'src, Token = Token, Span = Span>>() -> impl Parser<'src, I, Self> + Clone {
+    /// the operator as written in the source
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::Add => "+",
+            Self::Subtract => "-",
+            Self::Multiply => "*",
+            Self::Divide => "/",
+            Self::Power => "^",
+            Self::Equal => "==",
+            Self::NotEqual => "!=",
+            Self::Less => "<",
+            Self::LessEqual => "<=",
+            Self::Greater => ">",
+            Self::GreaterEqual => ">=",
+        }
+    }
+    /// binding strength of the operator, higher binds tighter
+    pub fn precedence(&self) -> u16 {
+        match self {
+            Self::Equal | Self::NotEqual | Self::Less | Self::LessEqual | Self::Greater | Self::GreaterEqual => 0,
+            Self::Add | Self::Subtract => 1,
+            Self::Multiply | Self::Divide => 2,
+            Self::Power => 3,
+        }
+    }
+    /// whether the operation compares its operands, giving true or false
+    pub fn is_comparison(&self) -> bool {
+        self.precedence() == 0
+    }
+    /// whether `a op b op c` groups as `a op (b op c)`
+    pub fn is_right_associative(&self) -> bool {
+        matches!(self, Self::Power)
+    }
+    pub fn add<'src, I: ValueInput<'src, Token = Token, Span = Span>>() -> impl Parser<'src, I, Self, Extra<'src>> + Clone {
         just(Token::Plus).to(Self::Add)
     }
-    pub fn subtract<'src, I: ValueInput<'src, Token = Token, Span = Span>>() -> impl Parser<'src, I, Self> + Clone {
+    pub fn subtract<'src, I: ValueInput<'src, Token = Token, Span = Span>>() -> impl Parser<'src, I, Self, Extra<'src>> + Clone {
         just(Token::Minus).to(Self::Subtract)
     }
-    pub fn multiply<'src, I: ValueInput<'src, Token = Token, Span = Span>>() -> impl Parser<'src, I, Self> + Clone {
+    pub fn multiply<'src, I: ValueInput<'src, Token = Token, Span = Span>>() -> impl Parser<'src, I, Self, Extra<'src>> + Clone {
         just(Token::Star).to(Self::Multiply)
     }
-    pub fn divide<'src, I: ValueInput<'src, Token = Token, Span = Span>>() -> impl Parser<'src, I, Self> + Clone {
+    pub fn divide<'src, I: ValueInput<'src, Token = Token, Span = Span>>() -> impl Parser<'src, I, Self, Extra<'src>> + Clone {
         just(Token::Slash).to(Self::Divide)
     }
-}
\ No newline at end of file
+    /// any of the comparisons ==, !=, <, <=, > and >=
+    pub fn compare<'src, I: ValueInput<'src, Token = Token, Span = Span>>() -> impl Parser<'src, I, Self, Extra<'src>> + Clone {
+        select! {
+            Token::DoubleEquals => Self::Equal,
+            Token::NotEquals => Self::NotEqual,
+            Token::LessThan => Self::Less,
+            Token::LessEquals => Self::LessEqual,
+            Token::GreaterThan => Self::Greater,
+            Token::GreaterEquals => Self::GreaterEqual,
+        }
+    }
+    pub fn power<'src, I: ValueInput<'src, Token = Token, Span = Span>>() -> impl Parser<'src, I, Self, Extra<'src>> + Clone {
+        just(Token::Caret).to(Self::Power)
+    }
+}
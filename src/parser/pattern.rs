@@ -1,4 +1,4 @@
-use crate::{ids::VariableId, lexer::Token, spanned::{Span, Spanned, SpannedParser}};
+use crate::{ids::VariableId, lexer::Token, parser::Extra, spanned::{Span, Spanned, SpannedParser}};
 
 use super::literal::Literal;
 use chumsky::{input::ValueInput, prelude::*};
@@ -28,7 +28,7 @@ pub enum Pattern<'db> {
     Tuple(Vec<Spanned<Pattern<'db>>>),
 }
 impl<'db> Pattern<'db> {
-    pub fn parser<'src, I>(db: &'db dyn salsa::Database) -> impl Parser<'src, I, Self> + Clone
+    pub fn parser<'src, I>(db: &'db dyn salsa::Database) -> impl Parser<'src, I, Self, Extra<'src>> + Clone
     where 
     'db: 'src,
     I: ValueInput<'src, Span = Span, Token = Token>
@@ -37,9 +37,9 @@ impl<'db> Pattern<'db> {
             let wildcard = just(Token::Wildcard)
                 .ignored()
                 .spanned()
-                .map(|x| Self::Wildcard(x));
-            let variable = VariableId::parser(db).spanned().map(|x| Self::Variable(x));
-            let literal = Literal::parser().spanned().map(|x| Self::Literal(x));
+                .map(Self::Wildcard);
+            let variable = VariableId::parser(db).spanned().map(Self::Variable);
+            let literal = Literal::parser().spanned().map(Self::Literal);
 
             let tuple = atom.clone()
                 .spanned()
@@ -48,7 +48,7 @@ impl<'db> Pattern<'db> {
                 .allow_trailing()
                 .collect::<Vec<_>>()
                 .delimited_by(just(Token::LParen), just(Token::RParen))
-                .map(|x| Self::Tuple(x));
+                .map(Self::Tuple);
 
 
             choice((
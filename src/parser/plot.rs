@@ -0,0 +1,253 @@
+use chumsky::{input::ValueInput, prelude::*};
+
+use crate::{ids::VariableId, lexer::Token, parser::Extra, spanned::{Span, Spanned, SpannedParser}};
+
+use super::{expression::Expression, statement::Statement};
+
+/// the values from start up to end, written start..end
+#[derive(PartialEq, Eq, Debug, Hash, salsa::Update, Clone)]
+pub struct PlotRange<'db> {
+    pub start: Spanned<Expression<'db>>,
+    pub end: Spanned<Expression<'db>>,
+}
+impl<'db> PlotRange<'db> {
+    pub fn parser<'src, I: ValueInput<'src, Span = Span, Token = Token>>(
+        db: &'db dyn salsa::Database,
+        statement_parser: impl Parser<'src, I, Statement<'db>, Extra<'src>> + 'src + Clone
+    ) -> impl Parser<'src, I, Self, Extra<'src>> + Clone
+    where 'db: 'src
+    {
+        let expression = Expression::parser(db, statement_parser).spanned();
+        expression.clone()
+            .then_ignore(just(Token::DotDot))
+            .then(expression)
+            .map(|(start, end)| Self { start, end })
+    }
+    pub fn span(&self) -> Span {
+        self.start.span().merge(self.end.span())
+    }
+}
+
+/// the value given to an option of a plot
+#[derive(PartialEq, Eq, Debug, Hash, salsa::Update, Clone)]
+pub enum OptionValue<'db> {
+    String(Spanned<String>),
+    Range(PlotRange<'db>),
+    Expression(Spanned<Expression<'db>>),
+}
+impl OptionValue<'_> {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::String(x) => x.span(),
+            Self::Range(x) => x.span(),
+            Self::Expression(x) => x.span(),
+        }
+    }
+}
+
+/// a plotted expression, a number for every value of the variable or an (x, y) tuple for a parametric curve
+#[derive(PartialEq, Eq, Debug, Hash, salsa::Update, Clone)]
+pub struct PlotSeries<'db> {
+    /// the keyword of a polar curve, polar r is the distance from the origin at the angle given by the variable
+    pub polar: Option<Span>,
+    pub expression: Spanned<Expression<'db>>,
+}
+impl PlotSeries<'_> {
+    pub fn span(&self) -> Span {
+        self.polar.map_or(self.expression.span(), |x| x.merge(self.expression.span()))
+    }
+}
+
+/// the second variable of an implicit plot with its range, written after the first like for x in -2..2, y in -2..2
+#[derive(PartialEq, Eq, Debug, Hash, salsa::Update, Clone)]
+pub struct PlotVariable<'db> {
+    pub variable: Spanned<VariableId<'db>>,
+    pub range: PlotRange<'db>,
+}
+
+/// a setting of a plot written after `with`, a name optionally followed by a value like `title "demo"` or `grid`
+#[derive(PartialEq, Eq, Debug, Hash, salsa::Update, Clone)]
+pub struct PlotOption<'db> {
+    pub name: Spanned<String>,
+    pub value: Option<OptionValue<'db>>,
+}
+
+/// draws expressions of a variable over a range, plot sin(x), x^2 for x in -5..5 with title "demo";
+///
+/// the variable is only bound inside of the plotted expressions, the range and the options are evaluated in the
+/// enclosing scope. plot (cos(t), sin(t)) for t in 0..tau; draws a parametric curve and plot polar 1 for t in 0..tau;
+/// a polar one. with a second variable the plotted expressions are equations of both which are drawn where they hold,
+/// plot x^2 + y^2 == 1 for x in -2..2, y in -2..2; draws a circle. plot3d f for x in -2..2, y in -2..2; draws the
+/// height given by a function or an expression of both variables. a plot ending in `to "out.svg"` is written to the
+/// file instead of being drawn
+#[salsa::tracked(debug)]
+pub struct Plot<'db> {
+    /// the source from the plot keyword up to the end of the output file
+    pub span: Span,
+    /// written plot3d, the single series is a height over both variables drawn as a heatmap or a wireframe
+    pub surface: bool,
+    #[returns(ref)]
+    pub series: Vec<PlotSeries<'db>>,
+    pub variable: Spanned<VariableId<'db>>,
+    #[returns(ref)]
+    pub range: PlotRange<'db>,
+    /// the vertical variable of an implicit plot
+    #[returns(ref)]
+    pub second: Option<PlotVariable<'db>>,
+    #[returns(ref)]
+    pub options: Vec<PlotOption<'db>>,
+    /// the file the plot is written to
+    #[returns(ref)]
+    pub output: Option<Spanned<String>>,
+}
+impl<'db> Plot<'db> {
+    pub fn parser<'src, I: ValueInput<'src, Span = Span, Token = Token>>(
+        db: &'db dyn salsa::Database,
+        statement_parser: impl Parser<'src, I, Statement<'db>, Extra<'src>> + 'src + Clone
+    ) -> impl Parser<'src, I, Self, Extra<'src>> + Clone
+    where 'db: 'src
+    {
+        let expression = Expression::parser(db, statement_parser.clone()).spanned();
+        let range = PlotRange::parser(db, statement_parser);
+        let second = just(Token::Comma)
+            .ignore_then(VariableId::parser(db).spanned())
+            .then_ignore(just(Token::In))
+            .then(range.clone())
+            .map(|(variable, range)| PlotVariable { variable, range });
+
+        let series = just(Token::Polar)
+            .map_with(|_, e| e.span())
+            .or_not()
+            .then(expression.clone())
+            .map(|(polar, expression)| PlotSeries { polar, expression });
+        let string = select! { Token::String(x) => x }.spanned();
+        let value = choice((
+            string.clone().map(OptionValue::String),
+            range.clone().map(OptionValue::Range),
+            expression.clone().map(OptionValue::Expression),
+        ));
+        let option = select! { Token::Identifier(x) => x }
+            .spanned()
+            .then(value.or_not())
+            .map(|(name, value)| PlotOption { name, value });
+        let options = just(Token::With)
+            .ignore_then(option.separated_by(just(Token::Comma)).at_least(1).collect::<Vec<_>>())
+            .or_not()
+            .map(Option::unwrap_or_default);
+
+        choice((just(Token::Plot).to(false), just(Token::Plot3d).to(true)))
+            .then(series.separated_by(just(Token::Comma)).at_least(1).collect::<Vec<_>>())
+            .then_ignore(just(Token::For))
+            .then(VariableId::parser(db).spanned())
+            .then_ignore(just(Token::In))
+            .then(range)
+            .then(second.or_not())
+            .then(options)
+            .then(just(Token::To).ignore_then(string).or_not())
+            .map_with(move |((((((surface, series), variable), range), second), options), output), e| {
+                Self::new(db, e.span(), surface, series, variable, range, second, options, output)
+            })
+            .then_ignore(just(Token::Semicolon))
+    }
+    /// the plotted expressions, evaluated in the scope of the plot
+    pub fn expressions(&self, db: &'db dyn salsa::Database) -> impl Iterator<Item = &'db Spanned<Expression<'db>>> {
+        self.series(db).iter().map(|x| &x.expression)
+    }
+    /// the variables bound inside of the plotted expressions, the second one only for implicit plots
+    pub fn variables(&self, db: &'db dyn salsa::Database) -> impl Iterator<Item = Spanned<VariableId<'db>>> {
+        std::iter::once(self.variable(db)).chain(self.second(db).as_ref().map(|x| x.variable.clone()))
+    }
+    /// the expressions evaluated in the enclosing scope, the ends of the ranges and the values of the options
+    pub fn settings(&self, db: &'db dyn salsa::Database) -> impl Iterator<Item = &'db Spanned<Expression<'db>>> {
+        let range = self.range(db);
+        let second = self.second(db).iter().flat_map(|x| [&x.range.start, &x.range.end]);
+        let options = self.options(db).iter()
+            .flat_map(|x| match &x.value {
+                Some(OptionValue::Range(x)) => vec![&x.start, &x.end],
+                Some(OptionValue::Expression(x)) => vec![x],
+                Some(OptionValue::String(_)) | None => vec![],
+            });
+        [&range.start, &range.end].into_iter().chain(second).chain(options)
+    }
+    /// the option with the name, the last one when it is given more than once
+    pub fn option(&self, db: &'db dyn salsa::Database, name: &str) -> Option<&'db PlotOption<'db>> {
+        self.options(db).iter().rfind(|x| *x.name == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{lexer::{lex_source, LexedSource}, stream::Stream, GraphingDatabase, ProgramSource};
+
+    use super::*;
+
+    #[salsa::tracked]
+    fn test_compile_plot<'db>(db: &'db dyn salsa::Database, ls: LexedSource<'db>) -> Option<Plot<'db>> {
+        let tokenstream = ls.tokens(db);
+        let stream = Stream::from_iter(tokenstream.iter().cloned());
+        let sp = Statement::parser(db);
+        Plot::parser(db, sp).parse(stream).into_output()
+    }
+
+    #[test]
+    fn plot(){
+        let dbs = GraphingDatabase::default();
+        let code = ProgramSource::new(&dbs, r#"plot sin(x), x^2 for x in -5..5 with title "demo", grid, y -1..1;"#.to_owned());
+        let plot = test_compile_plot(&dbs, lex_source(&dbs, code)).unwrap();
+        assert_eq!(plot.series(&dbs).len(), 2);
+        assert_eq!(plot.variable(&dbs).text(&dbs), "x");
+        assert_eq!(plot.span(&dbs), Span::new(0, 64));
+        assert!(matches!(plot.option(&dbs, "title").unwrap().value, Some(OptionValue::String(ref x)) if **x == "demo"));
+        assert_eq!(plot.option(&dbs, "grid").unwrap().value, None);
+        assert!(matches!(plot.option(&dbs, "y").unwrap().value, Some(OptionValue::Range(_))));
+        assert_eq!(plot.output(&dbs), &None);
+    }
+    #[test]
+    fn output(){
+        let dbs = GraphingDatabase::default();
+        let code = ProgramSource::new(&dbs, r#"plot x for x in 0..1 to "out.svg";"#.to_owned());
+        let plot = test_compile_plot(&dbs, lex_source(&dbs, code)).unwrap();
+        assert_eq!(plot.output(&dbs), &Some(Spanned::new("out.svg".to_owned(), Span::new(24, 33))));
+        assert_eq!(plot.span(&dbs), Span::new(0, 33));
+    }
+    #[test]
+    fn curves(){
+        let dbs = GraphingDatabase::default();
+        let code = ProgramSource::new(&dbs, "plot (cos(t), sin(t)), polar 1 + t for t in 0..tau;".to_owned());
+        let plot = test_compile_plot(&dbs, lex_source(&dbs, code)).unwrap();
+        let series = plot.series(&dbs);
+        assert!(matches!(*series[0].expression, Expression::Tuple(_)) && series[0].polar.is_none());
+        assert_eq!(series[1].polar, Some(Span::new(23, 28)));
+        assert_eq!(series[1].span(), Span::new(23, 34));
+    }
+    #[test]
+    fn implicit(){
+        let dbs = GraphingDatabase::default();
+        let code = ProgramSource::new(&dbs, "plot x^2 + y^2 == 1 for x in -2..2, y in -1..a with equal;".to_owned());
+        let plot = test_compile_plot(&dbs, lex_source(&dbs, code)).unwrap();
+        let second = plot.second(&dbs).as_ref().unwrap();
+        assert_eq!(second.variable.text(&dbs), "y");
+        assert_eq!(second.range.span(), Span::new(41, 46));
+        assert_eq!(plot.variables(&dbs).count(), 2);
+        // both ends of both ranges
+        assert_eq!(plot.settings(&dbs).count(), 4);
+        assert!(plot.option(&dbs, "equal").is_some());
+    }
+    #[test]
+    fn surface(){
+        let dbs = GraphingDatabase::default();
+        let code = ProgramSource::new(&dbs, "plot3d f for x in -2..2, y in -2..2 with wireframe;".to_owned());
+        let plot = test_compile_plot(&dbs, lex_source(&dbs, code)).unwrap();
+        assert!(plot.surface(&dbs));
+        assert_eq!(plot.variables(&dbs).count(), 2);
+        assert_eq!(plot.span(&dbs), Span::new(0, 50));
+        let code = ProgramSource::new(&dbs, "plot x for x in 0..1;".to_owned());
+        assert!(!test_compile_plot(&dbs, lex_source(&dbs, code)).unwrap().surface(&dbs));
+    }
+    #[test]
+    fn missing_range(){
+        let dbs = GraphingDatabase::default();
+        let code = ProgramSource::new(&dbs, "plot x for x;".to_owned());
+        assert_eq!(test_compile_plot(&dbs, lex_source(&dbs, code)), None);
+    }
+}
@@ -0,0 +1,313 @@
+//! owned mirrors of the syntax tree which can be serialized without a database
+//!
+//! the tracked structs only hold ids into the database so they are converted into these nodes first,
+//! interned names are written as their text
+
+use ordered_float::OrderedFloat;
+use serde::Serialize;
+
+use crate::spanned::Spanned;
+
+use super::{expression::Expression, literal::Literal, operations::BinaryOp, pattern::Pattern, plot::{OptionValue, PlotRange, PlotVariable}, statement::Statement, ty::Type, Program};
+
+pub(crate) fn ordered_float<S: serde::Serializer>(x: &OrderedFloat<f64>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(x.0)
+}
+
+/// the mirror of a [`Pattern`]
+#[derive(PartialEq, Debug, Clone, Serialize, salsa::Update)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PatternNode {
+    Wildcard(Spanned<()>),
+    Variable(Spanned<String>),
+    Literal(Spanned<Literal>),
+    Tuple { parts: Vec<Spanned<PatternNode>> },
+}
+impl PatternNode {
+    pub fn new(db: &dyn salsa::Database, pattern: &Pattern<'_>) -> Self {
+        match pattern {
+            Pattern::Wildcard(x) => Self::Wildcard(x.clone()),
+            Pattern::Variable(x) => Self::Variable(Spanned::new(x.text(db).clone(), x.span())),
+            Pattern::Literal(x) => Self::Literal(x.clone()),
+            Pattern::Tuple(x) => Self::Tuple { parts: x.iter().map(|ell| spanned(ell, |x| Self::new(db, x))).collect() },
+        }
+    }
+}
+
+/// the mirror of a [`Type`]
+#[derive(PartialEq, Debug, Clone, Serialize, salsa::Update)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TypeNode {
+    Inferred(Spanned<()>),
+    Type(Spanned<String>),
+    Tuple { parts: Vec<Spanned<TypeNode>> },
+}
+impl TypeNode {
+    pub fn new(db: &dyn salsa::Database, ty: &Type<'_>) -> Self {
+        match ty {
+            Type::Inferred(x) => Self::Inferred(x.clone()),
+            Type::Type(x) => Self::Type(Spanned::new(x.text(db).clone(), x.span())),
+            Type::Tuple(x) => Self::Tuple { parts: x.iter().map(|ell| spanned(ell, |x| Self::new(db, x))).collect() },
+        }
+    }
+}
+
+/// the mirror of an [`Expression`]
+#[derive(PartialEq, Debug, Clone, Serialize, salsa::Update)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ExpressionNode {
+    Todo(Spanned<()>),
+    Literal(Spanned<Literal>),
+    Variable(Spanned<String>),
+    Negate { operand: Box<Spanned<ExpressionNode>> },
+    Tuple { parts: Vec<Spanned<ExpressionNode>> },
+    Binary {
+        left: Box<Spanned<ExpressionNode>>,
+        op: Spanned<BinaryOp>,
+        right: Box<Spanned<ExpressionNode>>,
+    },
+    FunctionCall {
+        function: Spanned<String>,
+        args: Vec<Spanned<ExpressionNode>>,
+    },
+    Block {
+        statements: Vec<Spanned<StatementNode>>,
+        return_expr: Option<Box<Spanned<ExpressionNode>>>,
+    },
+    Slider {
+        range: Box<RangeNode>,
+        step: Option<Box<Spanned<ExpressionNode>>>,
+    },
+    If,
+}
+impl ExpressionNode {
+    pub fn new(db: &dyn salsa::Database, expression: &Expression<'_>) -> Self {
+        let node = |x: &Spanned<Expression<'_>>| spanned(x, |x| Self::new(db, x));
+        match expression {
+            Expression::Todo(x) => Self::Todo(x.clone()),
+            Expression::Literal(x) => Self::Literal(x.clone()),
+            Expression::Variable(x) => Self::Variable(Spanned::new(x.text(db).clone(), x.span())),
+            Expression::Negate(x) => Self::Negate { operand: Box::new(node(x)) },
+            Expression::Tuple(x) => Self::Tuple { parts: x.iter().map(node).collect() },
+            Expression::Binary(left, op, right) => Self::Binary {
+                left: Box::new(node(left)),
+                op: op.clone(),
+                right: Box::new(node(right)),
+            },
+            Expression::FunctionCall(function, args) => Self::FunctionCall {
+                function: Spanned::new(function.text(db).clone(), function.span()),
+                args: args.iter().map(node).collect(),
+            },
+            Expression::Block(x) => Self::Block {
+                statements: x.statements(db).iter().map(|x| spanned(x, |x| StatementNode::new(db, x))).collect(),
+                return_expr: x.return_expr(db).as_ref().map(|x| Box::new(node(x))),
+            },
+            Expression::Slider(x) => Self::Slider {
+                range: Box::new(RangeNode::new(db, &x.range)),
+                step: x.step.as_ref().map(|x| Box::new(node(x))),
+            },
+            Expression::If => Self::If,
+        }
+    }
+}
+
+/// an argument of a function with its optional type annotation
+#[derive(PartialEq, Debug, Clone, Serialize, salsa::Update)]
+pub struct ArgumentNode {
+    pub pattern: Spanned<PatternNode>,
+    pub ty: Option<Spanned<TypeNode>>,
+}
+
+/// the mirror of a [`PlotRange`]
+#[derive(PartialEq, Debug, Clone, Serialize, salsa::Update)]
+pub struct RangeNode {
+    pub start: Spanned<ExpressionNode>,
+    pub end: Spanned<ExpressionNode>,
+}
+impl RangeNode {
+    pub fn new(db: &dyn salsa::Database, range: &PlotRange<'_>) -> Self {
+        Self {
+            start: spanned(&range.start, |x| ExpressionNode::new(db, x)),
+            end: spanned(&range.end, |x| ExpressionNode::new(db, x)),
+        }
+    }
+}
+
+/// the mirror of a [`PlotVariable`]
+#[derive(PartialEq, Debug, Clone, Serialize, salsa::Update)]
+pub struct VariableNode {
+    pub variable: Spanned<String>,
+    pub range: RangeNode,
+}
+impl VariableNode {
+    pub fn new(db: &dyn salsa::Database, variable: &PlotVariable<'_>) -> Self {
+        Self {
+            variable: Spanned::new(variable.variable.text(db).clone(), variable.variable.span()),
+            range: RangeNode::new(db, &variable.range),
+        }
+    }
+}
+
+/// the mirror of an [`OptionValue`]
+#[derive(PartialEq, Debug, Clone, Serialize, salsa::Update)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum OptionValueNode {
+    String(Spanned<String>),
+    Range(RangeNode),
+    Expression(Spanned<ExpressionNode>),
+}
+
+/// a plotted expression and whether it is a polar curve
+#[derive(PartialEq, Debug, Clone, Serialize, salsa::Update)]
+pub struct SeriesNode {
+    pub polar: bool,
+    pub expression: Spanned<ExpressionNode>,
+}
+
+/// an option of a plot with its optional value
+#[derive(PartialEq, Debug, Clone, Serialize, salsa::Update)]
+pub struct OptionNode {
+    pub name: Spanned<String>,
+    pub value: Option<OptionValueNode>,
+}
+
+/// the mirror of a [`Statement`]
+#[derive(PartialEq, Debug, Clone, Serialize, salsa::Update)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StatementNode {
+    Function {
+        name: Spanned<String>,
+        args: Vec<ArgumentNode>,
+        return_type: Option<Spanned<TypeNode>>,
+        body: Spanned<ExpressionNode>,
+    },
+    Variable {
+        pattern: Spanned<PatternNode>,
+        ty: Option<Spanned<TypeNode>>,
+        body: Spanned<ExpressionNode>,
+    },
+    Plot {
+        /// written plot3d, the series is the height over both variables
+        surface: bool,
+        series: Vec<SeriesNode>,
+        variable: Spanned<String>,
+        range: RangeNode,
+        second: Option<Box<VariableNode>>,
+        options: Vec<OptionNode>,
+        output: Option<Spanned<String>>,
+    },
+}
+impl StatementNode {
+    pub fn new(db: &dyn salsa::Database, statement: &Statement<'_>) -> Self {
+        let ty = |x: &Spanned<Type<'_>>| spanned(x, |x| TypeNode::new(db, x));
+        match statement {
+            Statement::Function(x) => {
+                let name = x.name(db);
+                Self::Function {
+                    name: Spanned::new(name.text(db).clone(), name.span()),
+                    args: x.args(db).iter()
+                        .map(|(pattern, annotation)| ArgumentNode {
+                            pattern: spanned(pattern, |x| PatternNode::new(db, x)),
+                            ty: annotation.as_ref().map(ty),
+                        })
+                        .collect(),
+                    return_type: x.return_type(db).as_ref().map(ty),
+                    body: spanned(x.body(db), |x| ExpressionNode::new(db, x)),
+                }
+            },
+            Statement::Variable(x) => Self::Variable {
+                pattern: spanned(x.name(db), |x| PatternNode::new(db, x)),
+                ty: x.ty(db).as_ref().map(ty),
+                body: spanned(&x.body(db), |x| ExpressionNode::new(db, x)),
+            },
+            Statement::Plot(x) => {
+                let variable = x.variable(db);
+                Self::Plot {
+                    surface: x.surface(db),
+                    series: x.series(db).iter()
+                        .map(|series| SeriesNode { polar: series.polar.is_some(), expression: spanned(&series.expression, |x| ExpressionNode::new(db, x)) })
+                        .collect(),
+                    variable: Spanned::new(variable.text(db).clone(), variable.span()),
+                    range: RangeNode::new(db, x.range(db)),
+                    second: x.second(db).as_ref().map(|x| Box::new(VariableNode::new(db, x))),
+                    options: x.options(db).iter()
+                        .map(|option| OptionNode {
+                            name: option.name.clone(),
+                            value: option.value.as_ref().map(|value| match value {
+                                OptionValue::String(x) => OptionValueNode::String(x.clone()),
+                                OptionValue::Range(x) => OptionValueNode::Range(RangeNode::new(db, x)),
+                                OptionValue::Expression(x) => OptionValueNode::Expression(spanned(x, |x| ExpressionNode::new(db, x))),
+                            }),
+                        })
+                        .collect(),
+                    output: x.output(db).clone(),
+                }
+            },
+        }
+    }
+}
+
+/// the mirror of a [`Program`], every statement is spanned like [`Statement::span`]
+#[derive(PartialEq, Debug, Clone, Serialize, salsa::Update)]
+pub struct ProgramNode {
+    pub statements: Vec<Spanned<StatementNode>>,
+}
+impl ProgramNode {
+    pub fn new(db: &dyn salsa::Database, program: Program<'_>) -> Self {
+        Self {
+            statements: program.statements(db).iter()
+                .map(|x| Spanned::new(StatementNode::new(db, x), x.span(db)))
+                .collect(),
+        }
+    }
+}
+
+/// converts the node keeping its span
+fn spanned<T: PartialEq + salsa::Update, U: PartialEq + salsa::Update>(x: &Spanned<T>, f: impl FnOnce(&T) -> U) -> Spanned<U> {
+    Spanned::new(f(x), x.span())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{lexer::lex_source, parser::compile_source, GraphingDatabase, ProgramSource};
+
+    use super::*;
+
+    #[test]
+    fn program(){
+        let db = GraphingDatabase::default();
+        let code = ProgramSource::new(&db, "fn f(x: u8) = x * 2.5;".to_owned());
+        let node = ProgramNode::new(&db, compile_source(&db, code));
+        let span = |start, end| json!({"start": start, "end": end});
+        let expected = json!({"statements": [{
+            "span": span(3, 21),
+            "node": {
+                "kind": "function",
+                "name": {"span": span(3, 4), "node": "f"},
+                "args": [{
+                    "pattern": {"span": span(5, 6), "node": {"kind": "variable", "span": span(5, 6), "node": "x"}},
+                    "ty": {"span": span(8, 10), "node": {"kind": "type", "span": span(8, 10), "node": "u8"}},
+                }],
+                "return_type": null,
+                "body": {"span": span(14, 21), "node": {
+                    "kind": "binary",
+                    "left": {"span": span(14, 15), "node": {"kind": "variable", "span": span(14, 15), "node": "x"}},
+                    "op": {"span": span(16, 17), "node": "Multiply"},
+                    "right": {"span": span(18, 21), "node": {"kind": "literal", "span": span(18, 21), "node": {"AbstractFloat": 2.5}}},
+                }},
+            },
+        }]});
+        assert_eq!(serde_json::to_value(&node).unwrap(), expected);
+    }
+    #[test]
+    fn tokens(){
+        let db = GraphingDatabase::default();
+        let code = ProgramSource::new(&db, "let a = 1.5;".to_owned());
+        let tokens = serde_json::to_value(lex_source(&db, code).tokens(&db)).unwrap();
+        assert_eq!(tokens[1], json!([{"Identifier": "a"}, {"start": 4, "end": 5}]));
+        assert_eq!(tokens[3], json!([{"Float": 1.5}, {"start": 8, "end": 11}]));
+        assert_eq!(tokens[0][0], json!("Let"));
+    }
+}
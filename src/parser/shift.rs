@@ -0,0 +1,167 @@
+use crate::{ids::{FunctionId, TypeId, VariableId}, spanned::{Span, Spanned}};
+
+use super::{block::BlockExpression, expression::Expression, function::Function, literal::Literal, operations::BinaryOp, pattern::Pattern, plot::{OptionValue, Plot, PlotOption, PlotRange, PlotSeries, PlotVariable}, slider::Slider, statement::Statement, ty::Type, variable::Variable};
+
+/// moves every span of a syntax tree by the offset, statements are parsed with spans from the start of their own text
+/// so they can be reused wherever the statement moves in the program
+pub trait Shift<'db> {
+    fn shifted(&self, db: &'db dyn salsa::Database, offset: usize) -> Self;
+}
+
+impl<'db> Shift<'db> for Span {
+    fn shifted(&self, _db: &'db dyn salsa::Database, offset: usize) -> Self {
+        self.shift(offset)
+    }
+}
+impl<'db, T: Shift<'db> + PartialEq + salsa::Update> Shift<'db> for Spanned<T> {
+    fn shifted(&self, db: &'db dyn salsa::Database, offset: usize) -> Self {
+        Spanned::new((**self).shifted(db, offset), self.span().shift(offset))
+    }
+}
+impl<'db, T: Shift<'db>> Shift<'db> for Vec<T> {
+    fn shifted(&self, db: &'db dyn salsa::Database, offset: usize) -> Self {
+        self.iter().map(|x| x.shifted(db, offset)).collect()
+    }
+}
+impl<'db, T: Shift<'db>> Shift<'db> for Option<T> {
+    fn shifted(&self, db: &'db dyn salsa::Database, offset: usize) -> Self {
+        self.as_ref().map(|x| x.shifted(db, offset))
+    }
+}
+impl<'db, T: Shift<'db>> Shift<'db> for Box<T> {
+    fn shifted(&self, db: &'db dyn salsa::Database, offset: usize) -> Self {
+        Box::new((**self).shifted(db, offset))
+    }
+}
+impl<'db, A: Shift<'db>, B: Shift<'db>> Shift<'db> for (A, B) {
+    fn shifted(&self, db: &'db dyn salsa::Database, offset: usize) -> Self {
+        (self.0.shifted(db, offset), self.1.shifted(db, offset))
+    }
+}
+
+/// the nodes holding no spans of their own
+macro_rules! unspanned {
+    ($($ty:ty),*) => {
+        $(impl<'db> Shift<'db> for $ty {
+            fn shifted(&self, _db: &'db dyn salsa::Database, _offset: usize) -> Self {
+                self.clone()
+            }
+        })*
+    };
+}
+unspanned!((), String, Literal, BinaryOp, VariableId<'db>, FunctionId<'db>, TypeId<'db>);
+
+impl<'db> Shift<'db> for Statement<'db> {
+    fn shifted(&self, db: &'db dyn salsa::Database, offset: usize) -> Self {
+        match self {
+            Self::Function(x) => Self::Function(x.shifted(db, offset)),
+            Self::Variable(x) => Self::Variable(x.shifted(db, offset)),
+            Self::Plot(x) => Self::Plot(x.shifted(db, offset)),
+        }
+    }
+}
+impl<'db> Shift<'db> for Function<'db> {
+    fn shifted(&self, db: &'db dyn salsa::Database, offset: usize) -> Self {
+        Function::new(
+            db,
+            self.name(db).shifted(db, offset),
+            self.args(db).shifted(db, offset),
+            self.return_type(db).shifted(db, offset),
+            self.body(db).shifted(db, offset),
+        )
+    }
+}
+impl<'db> Shift<'db> for Variable<'db> {
+    fn shifted(&self, db: &'db dyn salsa::Database, offset: usize) -> Self {
+        Variable::new(db, self.name(db).shifted(db, offset), self.ty(db).shifted(db, offset), self.body(db).shifted(db, offset))
+    }
+}
+impl<'db> Shift<'db> for Plot<'db> {
+    fn shifted(&self, db: &'db dyn salsa::Database, offset: usize) -> Self {
+        Plot::new(
+            db,
+            self.span(db).shift(offset),
+            self.surface(db),
+            self.series(db).shifted(db, offset),
+            self.variable(db).shifted(db, offset),
+            self.range(db).shifted(db, offset),
+            self.second(db).shifted(db, offset),
+            self.options(db).shifted(db, offset),
+            self.output(db).shifted(db, offset),
+        )
+    }
+}
+impl<'db> Shift<'db> for BlockExpression<'db> {
+    fn shifted(&self, db: &'db dyn salsa::Database, offset: usize) -> Self {
+        BlockExpression::new(db, self.statements(db).shifted(db, offset), self.return_expr(db).shifted(db, offset))
+    }
+}
+impl<'db> Shift<'db> for Expression<'db> {
+    fn shifted(&self, db: &'db dyn salsa::Database, offset: usize) -> Self {
+        match self {
+            Self::Todo(x) => Self::Todo(x.shifted(db, offset)),
+            Self::Literal(x) => Self::Literal(x.shifted(db, offset)),
+            Self::Variable(x) => Self::Variable(x.shifted(db, offset)),
+            Self::Negate(x) => Self::Negate(x.shifted(db, offset)),
+            Self::Binary(a, op, b) => Self::Binary(a.shifted(db, offset), op.shifted(db, offset), b.shifted(db, offset)),
+            Self::Tuple(x) => Self::Tuple(x.shifted(db, offset)),
+            Self::FunctionCall(name, args) => Self::FunctionCall(name.shifted(db, offset), args.shifted(db, offset)),
+            Self::Block(x) => Self::Block(x.shifted(db, offset)),
+            Self::Slider(x) => Self::Slider(x.shifted(db, offset)),
+            Self::If => Self::If,
+        }
+    }
+}
+impl<'db> Shift<'db> for Pattern<'db> {
+    fn shifted(&self, db: &'db dyn salsa::Database, offset: usize) -> Self {
+        match self {
+            Self::Wildcard(x) => Self::Wildcard(x.shifted(db, offset)),
+            Self::Variable(x) => Self::Variable(x.shifted(db, offset)),
+            Self::Literal(x) => Self::Literal(x.shifted(db, offset)),
+            Self::Tuple(x) => Self::Tuple(x.shifted(db, offset)),
+        }
+    }
+}
+impl<'db> Shift<'db> for Type<'db> {
+    fn shifted(&self, db: &'db dyn salsa::Database, offset: usize) -> Self {
+        match self {
+            Self::Inferred(x) => Self::Inferred(x.shifted(db, offset)),
+            Self::Type(x) => Self::Type(x.shifted(db, offset)),
+            Self::Tuple(x) => Self::Tuple(x.shifted(db, offset)),
+        }
+    }
+}
+impl<'db> Shift<'db> for PlotRange<'db> {
+    fn shifted(&self, db: &'db dyn salsa::Database, offset: usize) -> Self {
+        Self { start: self.start.shifted(db, offset), end: self.end.shifted(db, offset) }
+    }
+}
+impl<'db> Shift<'db> for OptionValue<'db> {
+    fn shifted(&self, db: &'db dyn salsa::Database, offset: usize) -> Self {
+        match self {
+            Self::String(x) => Self::String(x.shifted(db, offset)),
+            Self::Range(x) => Self::Range(x.shifted(db, offset)),
+            Self::Expression(x) => Self::Expression(x.shifted(db, offset)),
+        }
+    }
+}
+impl<'db> Shift<'db> for PlotSeries<'db> {
+    fn shifted(&self, db: &'db dyn salsa::Database, offset: usize) -> Self {
+        Self { polar: self.polar.shifted(db, offset), expression: self.expression.shifted(db, offset) }
+    }
+}
+impl<'db> Shift<'db> for PlotVariable<'db> {
+    fn shifted(&self, db: &'db dyn salsa::Database, offset: usize) -> Self {
+        Self { variable: self.variable.shifted(db, offset), range: self.range.shifted(db, offset) }
+    }
+}
+impl<'db> Shift<'db> for PlotOption<'db> {
+    fn shifted(&self, db: &'db dyn salsa::Database, offset: usize) -> Self {
+        Self { name: self.name.shifted(db, offset), value: self.value.shifted(db, offset) }
+    }
+}
+impl<'db> Shift<'db> for Slider<'db> {
+    fn shifted(&self, db: &'db dyn salsa::Database, offset: usize) -> Self {
+        Self { range: self.range.shifted(db, offset), step: self.step.shifted(db, offset) }
+    }
+}
@@ -0,0 +1,38 @@
+use chumsky::{input::ValueInput, prelude::*};
+
+use crate::{lexer::Token, parser::Extra, spanned::{Span, Spanned, SpannedParser}};
+
+use super::{expression::Expression, plot::PlotRange, statement::Statement};
+
+/// a number which can be changed while the program runs, let a = slider(0..10, step 0.1);
+///
+/// it starts at the start of its range and is changed by steps, a hundredth of the range without a step. in the
+/// repl the plot view changes it with keys
+#[derive(PartialEq, Eq, Debug, Hash, salsa::Update, Clone)]
+pub struct Slider<'db> {
+    pub range: PlotRange<'db>,
+    pub step: Option<Spanned<Expression<'db>>>,
+}
+impl<'db> Slider<'db> {
+    pub fn parser<'src, I: ValueInput<'src, Span = Span, Token = Token>>(
+        db: &'db dyn salsa::Database,
+        statement_parser: impl Parser<'src, I, Statement<'db>, Extra<'src>> + 'src + Clone
+    ) -> impl Parser<'src, I, Self, Extra<'src>> + Clone
+    where 'db: 'src
+    {
+        let step = just(Token::Comma)
+            .ignore_then(select! { Token::Identifier(x) if x == "step" => () })
+            .ignore_then(Expression::parser(db, statement_parser.clone()).spanned());
+        just(Token::Slider)
+            .ignore_then(
+                PlotRange::parser(db, statement_parser)
+                    .then(step.or_not())
+                    .delimited_by(just(Token::LParen), just(Token::RParen))
+            )
+            .map(|(range, step)| Self { range, step })
+    }
+    /// the expressions evaluated to find the range and the step
+    pub fn settings(&self) -> impl Iterator<Item = &Spanned<Expression<'db>>> {
+        [&self.range.start, &self.range.end].into_iter().chain(&self.step)
+    }
+}
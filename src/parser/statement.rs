@@ -2,9 +2,9 @@ use std::hash::{Hash, Hasher};
 
 use chumsky::{input::ValueInput, prelude::*};
 
-use crate::{lexer::Token, spanned::Span};
+use crate::{lexer::Token, parser::Extra, spanned::Span};
 
-use super::{function::Function, variable::Variable};
+use super::{function::Function, plot::Plot, variable::Variable};
 
 
 
@@ -12,26 +12,40 @@ use super::{function::Function, variable::Variable};
 #[derive(PartialEq, Eq, Debug, Hash, salsa::Update, Clone)]
 pub enum Statement<'db>{
     Function(Function<'db>),
-    Variable(Variable<'db>)
+    Variable(Variable<'db>),
+    Plot(Plot<'db>),
 }
 impl<'db> Statement<'db> {
     pub fn parser<'src, I: ValueInput<'src, Span = Span, Token = Token>>(
         db: &'db dyn salsa::Database,
-    ) -> impl Parser<'src, I, Self> + Clone
+    ) -> impl Parser<'src, I, Self, Extra<'src>> + Clone
     where 'db: 'src
     {
         recursive(|p| {
             choice((
-                Function::parser(db, p.clone()).map(|x| Self::Function(x)),
-                Variable::parser(db, p).map(|x| Self::Variable(x))
+                Function::parser(db, p.clone()).map(Self::Function),
+                Variable::parser(db, p.clone()).map(Self::Variable),
+                Plot::parser(db, p).map(Self::Plot),
             ))
         })
     }
 
+    /// the source covered by the statement, from its name up to the end of its body
+    ///
+    /// plots have no name, they are covered from the plot keyword up to the end of the last option
+    pub fn span(&self, db: &'db dyn salsa::Database) -> Span {
+        match self {
+            Self::Function(x) => x.name(db).span().merge(x.body(db).span()),
+            Self::Variable(x) => x.name(db).span().merge(x.body(db).span()),
+            Self::Plot(x) => x.span(db),
+        }
+    }
+
     pub fn hash_id<H: Hasher>(&self, db: &'db dyn salsa::Database, hasher: &mut H) {
         match self {
             Self::Function(x) => (*x.name(db)).hash(hasher),
-            Self::Variable(x) => (*x.name(db)).hash(hasher)
+            Self::Variable(x) => (*x.name(db)).hash(hasher),
+            Self::Plot(x) => x.span(db).hash(hasher),
         }
     }
 }
\ No newline at end of file
@@ -1,8 +1,9 @@
-use crate::{ids::TypeId, lexer::Token, spanned::{Span, Spanned, SpannedParser}};
+use crate::{ids::TypeId, lexer::Token, parser::Extra, spanned::{Span, Spanned, SpannedParser}};
 use chumsky::{input::ValueInput, prelude::*};
 
 
 #[derive(PartialEq, Eq, Debug, Hash, salsa::Update, Clone)]
+#[allow(clippy::enum_variant_names)]
 pub enum Type<'db>{
     /// An inferred type _
     Inferred(Spanned<()>),
@@ -13,7 +14,7 @@ pub enum Type<'db>{
     // TODO: array [u8; 5]
 }
 impl<'db> Type<'db> {
-    pub fn parser<'src, I>(db: &'db dyn salsa::Database) -> impl Parser<'src, I, Self> + Clone
+    pub fn parser<'src, I>(db: &'db dyn salsa::Database) -> impl Parser<'src, I, Self, Extra<'src>> + Clone
     where 
     'db: 'src,
     I: ValueInput<'src, Span = Span, Token = Token>
@@ -22,11 +23,11 @@ impl<'db> Type<'db> {
             let inferred = just(Token::Wildcard)
                 .ignored()
                 .spanned()
-                .map(|x| Self::Inferred(x));
+                .map(Self::Inferred);
 
             let ty = TypeId::parser(db)
                 .spanned()
-                .map(|x| Self::Type(x));
+                .map(Self::Type);
 
             let tuple = atom
                 .spanned()
@@ -34,7 +35,7 @@ impl<'db> Type<'db> {
                 .at_least(1)
                 .collect::<Vec<_>>()
                 .delimited_by(just(Token::LParen), just(Token::RParen))
-                .map(|x| Type::Tuple(x));
+                .map(Type::Tuple);
 
             choice((
                 inferred,
@@ -1,8 +1,8 @@
 use chumsky::{input::ValueInput, prelude::*};
 
-use crate::{lexer::Token, spanned::{Span, Spanned, SpannedParser}};
+use crate::{lexer::Token, parser::Extra, spanned::{Span, Spanned, SpannedParser}};
 
-use super::{expression::Expression, pattern::Pattern, statement::Statement, ty::Type};
+use super::{expression::Expression, pattern::Pattern, slider::Slider, statement::Statement, ty::Type};
 
 #[salsa::tracked(debug)]
 pub struct Variable<'db> {
@@ -14,8 +14,8 @@ pub struct Variable<'db> {
 impl<'db> Variable<'db> {
     pub fn parser<'src, I: ValueInput<'src, Span = Span, Token = Token>>(
         db: &'db dyn salsa::Database,
-        statement_parser: impl Parser<'src, I, Statement<'db>> + 'src + Clone
-    ) -> impl Parser<'src, I, Self> + Clone
+        statement_parser: impl Parser<'src, I, Statement<'db>, Extra<'src>> + 'src + Clone
+    ) -> impl Parser<'src, I, Self, Extra<'src>> + Clone
     where 'db: 'src
     {
         let type_annotation = just(Token::DPoint)
@@ -25,7 +25,10 @@ impl<'db> Variable<'db> {
             .ignore_then(Pattern::parser(db).spanned())
             .then(type_annotation)
             .then_ignore(just(Token::Equals))
-            .then(Expression::parser(db, statement_parser).spanned())
+            .then(choice((
+                Slider::parser(db, statement_parser.clone()).map(|x| Expression::Slider(Box::new(x))),
+                Expression::parser(db, statement_parser),
+            )).spanned())
             .then_ignore(just(Token::Semicolon))
             .map(|((name, ty), body)| Self::new(db, name, ty, body))
     }
@@ -39,7 +42,7 @@ mod tests {
     #[salsa::tracked]
     fn test_compile_variable<'db>(db: &'db dyn salsa::Database, ls: LexedSource<'db>) -> Variable<'db> {
         let tokenstream = ls.tokens(db);
-        let stream = Stream::from_iter(tokenstream.to_owned().into_iter());
+        let stream = Stream::from_iter(tokenstream.iter().cloned());
         let sp = Statement::parser(db);
         Variable::parser(db, sp).parse(stream).unwrap()
     }
@@ -61,6 +64,15 @@ mod tests {
         let _ = test_compile_variable(&dbs, lexed);
     }
     #[test]
+    fn slider(){
+        let dbs = GraphingDatabase::default();
+        let code = ProgramSource::new(&dbs, "let a = slider(0..10, step 0.1);".to_owned());
+        let variable = test_compile_variable(&dbs, lex_source(&dbs, code));
+        let Expression::Slider(slider) = &*variable.body(&dbs) else { panic!("the value is a slider") };
+        assert_eq!(slider.range.span(), Span::new(15, 20));
+        assert_eq!(slider.step.as_ref().map(|x| x.span()), Some(Span::new(27, 30)));
+    }
+    #[test]
     fn typed_variable(){
         let dbs = GraphingDatabase::default();
         let code = "let a: u8 = a * 2 ;";
@@ -0,0 +1,234 @@
+//! the scales, labels and ticks of the axes of a plot, shared by the terminal, svg and png renderers
+//!
+//! renderers place everything at its position along the axes, which is the value itself on a linear scale and its
+//! base 10 logarithm on a log scale, and label the ticks with the values
+
+use serde::Serialize;
+
+use super::{format_decimals, format_tick, tick_step, ticks, Polyline};
+
+/// the prefixes of the powers of a thousand from 10^-24 to 10^24
+const PREFIXES: [&str; 17] = ["y", "z", "a", "f", "p", "n", "µ", "m", "", "k", "M", "G", "T", "P", "E", "Z", "Y"];
+
+/// how values are placed along an axis
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scale {
+    #[default]
+    Linear,
+    /// every power of ten is the same distance from the next one, only positive values can be shown
+    Log,
+}
+impl Scale {
+    /// the scale by the name it is given in plot options and on the command line
+    pub fn of(name: &str) -> Option<Self> {
+        match name {
+            "linear" => Some(Self::Linear),
+            "log" => Some(Self::Log),
+            _ => None,
+        }
+    }
+    /// the position of the value along the axis, None for values a log scale can not show
+    pub fn position(self, value: f64) -> Option<f64> {
+        match self {
+            Self::Linear => Some(value),
+            Self::Log => (value > 0.0).then(|| value.log10()),
+        }
+    }
+    /// the value at the position along the axis
+    pub fn value(self, position: f64) -> f64 {
+        match self {
+            Self::Linear => position,
+            Self::Log => 10f64.powf(position),
+        }
+    }
+    /// the positions of both ends of the range, None when a log scale can not show all of it
+    pub fn range(self, (min, max): (f64, f64)) -> Option<(f64, f64)> {
+        Some((self.position(min)?, self.position(max)?))
+    }
+}
+
+/// how the numbers of tick labels are written
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NumberFormat {
+    /// with as many decimals as the distance between the ticks needs, like 2500 and 0.25
+    #[default]
+    Plain,
+    /// with the prefix of a power of a thousand, like 2.5k and 250m
+    Si,
+}
+impl NumberFormat {
+    /// the format by the name it is given in plot options and on the command line
+    pub fn of(name: &str) -> Option<Self> {
+        match name {
+            "plain" => Some(Self::Plain),
+            "si" => Some(Self::Si),
+            _ => None,
+        }
+    }
+    /// the value with as many decimals as the step between it and the next value needs, values too large or too
+    /// small for a prefix are written with an exponent
+    pub fn format(self, value: f64, step: f64) -> String {
+        let group = (value.abs().log10() / 3.0).floor() as i32;
+        match self {
+            Self::Si if value != 0.0 && value.is_finite() && (-8..=8).contains(&group) => {
+                let unit = 10f64.powi(group * 3);
+                format!("{}{}", format_decimals(value / unit, step / unit), PREFIXES[(group + 8) as usize])
+            },
+            Self::Plain | Self::Si => format_tick(value, step),
+        }
+    }
+}
+
+/// the scales, labels and grid of a plot, the parts it does not give are taken from elsewhere
+#[derive(PartialEq, Debug, Clone, Default, Serialize)]
+pub struct Axes {
+    pub x_scale: Option<Scale>,
+    pub y_scale: Option<Scale>,
+    /// written below the x axis
+    pub x_label: Option<String>,
+    /// written next to the y axis
+    pub y_label: Option<String>,
+    /// whether lines are drawn across the plot at the ticks, images have them and the terminal does not unless given
+    pub grid: Option<bool>,
+    pub format: Option<NumberFormat>,
+}
+impl Axes {
+    /// the parts given by these axes with the missing ones taken from the other
+    pub fn or(self, other: &Self) -> Self {
+        Self {
+            x_scale: self.x_scale.or(other.x_scale),
+            y_scale: self.y_scale.or(other.y_scale),
+            x_label: self.x_label.or_else(|| other.x_label.clone()),
+            y_label: self.y_label.or_else(|| other.y_label.clone()),
+            grid: self.grid.or(other.grid),
+            format: self.format.or(other.format),
+        }
+    }
+    /// the scales of the x and the y axis, linear unless given
+    pub fn scales(&self) -> (Scale, Scale) {
+        (self.x_scale.unwrap_or_default(), self.y_scale.unwrap_or_default())
+    }
+}
+
+/// a mark on an axis with the value it shows
+#[derive(PartialEq, Debug, Clone)]
+pub struct Tick {
+    /// the position along the axis
+    pub position: f64,
+    pub label: String,
+}
+
+/// about `count` ticks at round values between the positions, spaced evenly on a linear scale and at powers of ten on a
+/// log scale
+///
+/// a log scale with less than three powers of ten in it also has ticks at 2 and 5 times a power of ten, or linear ticks
+/// when it covers less than one
+pub fn axis_ticks(scale: Scale, format: NumberFormat, (min, max): (f64, f64), count: usize) -> Vec<Tick> {
+    let linear = |(min, max): (f64, f64)| {
+        let step = tick_step(min, max, count);
+        ticks(min, max, count).into_iter().map(move |x| (x, format.format(x, step)))
+    };
+    if !min.is_finite() || !max.is_finite() {
+        return vec![];
+    }
+    if scale == Scale::Linear {
+        return linear((min, max)).map(|(position, label)| Tick { position, label }).collect();
+    }
+    // powers of ten, skipping some of them when there are too many
+    let stride = ((max - min) / count.max(1) as f64).ceil().max(1.0) as i64;
+    let decades = (min.ceil() as i64..=max.floor() as i64).filter(|x| x.rem_euclid(stride) == 0).collect::<Vec<_>>();
+    let values = match decades.len() {
+        0..=2 => {
+            let mantissas = (min.floor() as i64..=max.ceil() as i64)
+                .flat_map(|x| [1.0, 2.0, 5.0].map(|m| (m * 10f64.powi(x as i32), 10f64.powi(x as i32))))
+                .filter(|(x, _)| (min..=max).contains(&x.log10()))
+                .collect::<Vec<_>>();
+            if mantissas.len() >= 2 {
+                mantissas.into_iter().map(|(x, step)| (x, format.format(x, step))).collect()
+            } else {
+                linear((scale.value(min), scale.value(max))).collect()
+            }
+        },
+        _ => decades.into_iter().map(|x| 10f64.powi(x as i32)).map(|x| (x, format.format(x, x))).collect::<Vec<_>>(),
+    };
+    values.into_iter().filter_map(|(x, label)| Some(Tick { position: scale.position(x)?, label })).collect()
+}
+
+/// the lines with their points at their positions along the axes, broken where a point can not be shown
+pub fn project(lines: &[Polyline], (x_scale, y_scale): (Scale, Scale)) -> Vec<Polyline> {
+    if (x_scale, y_scale) == (Scale::Linear, Scale::Linear) {
+        return lines.to_vec();
+    }
+    let mut out = vec![];
+    for line in lines {
+        let mut current = vec![];
+        for (x, y) in line {
+            match (x_scale.position(*x), y_scale.position(*y)) {
+                (Some(x), Some(y)) => current.push((x, y)),
+                _ if !current.is_empty() => out.push(std::mem::take(&mut current)),
+                _ => {},
+            }
+        }
+        if !current.is_empty() {
+            out.push(current);
+        }
+    }
+    out
+}
+
+/// the positions of the ends of the x range, fitted to the projected lines when the scale can not show the range
+pub fn x_positions(range: (f64, f64), scale: Scale, lines: &[Polyline]) -> (f64, f64) {
+    scale.range(range).unwrap_or_else(|| match super::x_extent(lines) {
+        (min, max) if min < max => (min, max),
+        (min, max) if min == max => (min - 1.0, max + 1.0),
+        _ => (0.0, 1.0),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scales(){
+        assert_eq!(Scale::Log.position(1000.0), Some(3.0));
+        assert_eq!(Scale::Log.position(0.0), None);
+        assert_eq!(Scale::Log.value(-2.0), 0.01);
+        assert_eq!(Scale::Log.range((-1.0, 10.0)), None);
+        assert_eq!(Scale::Linear.range((-1.0, 10.0)), Some((-1.0, 10.0)));
+        let lines = project(&[vec![(1.0, 1.0), (0.0, 2.0), (10.0, 100.0), (100.0, 1000.0)]], (Scale::Log, Scale::Log));
+        assert_eq!(lines, vec![vec![(0.0, 0.0)], vec![(1.0, 2.0), (2.0, 3.0)]]);
+    }
+    #[test]
+    fn si(){
+        let si = |x: f64, step: f64| NumberFormat::Si.format(x, step);
+        assert_eq!(si(2500.0, 500.0), "2.5k");
+        assert_eq!(si(500.0, 500.0), "500");
+        assert_eq!(si(0.25, 0.05), "250m");
+        assert_eq!(si(-3e-6, 1e-6), "-3µ");
+        assert_eq!(si(4e9, 1e9), "4G");
+        assert_eq!(si(0.0, 1000.0), "0");
+        assert_eq!(si(1.00005e3, 0.05), "1.00005k");
+        // outside of the prefixes from y to Y
+        assert_eq!(si(1e40, 1e40), "1e40");
+        assert_eq!(si(-2.5e30, 5e29), "-2.5e30");
+        assert_eq!(si(3e-30, 1e-30), "3e-30");
+        assert_eq!(NumberFormat::Plain.format(2500.0, 500.0), "2500");
+        assert_eq!(NumberFormat::Plain.format(1e40, 1e40), "1e40");
+        assert_eq!(NumberFormat::Plain.format(3e6, 1e6), "3e6");
+    }
+    #[test]
+    fn log_ticks(){
+        let labels = |range: (f64, f64), count: usize| axis_ticks(Scale::Log, NumberFormat::Plain, range, count).into_iter().map(|x| x.label).collect::<Vec<_>>();
+        assert_eq!(labels((-2.0, 3.0), 6), ["0.01", "0.1", "1", "10", "100", "1000"]);
+        // every other power of ten
+        assert_eq!(labels((0.0, 8.0), 4), ["1", "100", "10000", "1e6", "1e8"]);
+        assert_eq!(labels((0.0, 1.0), 4), ["1", "2", "5", "10"]);
+        // less than a power of ten
+        assert_eq!(labels((2.0f64.log10(), 4.0f64.log10()), 4), ["2.0", "2.5", "3.0", "3.5", "4.0"]);
+        let ticks = axis_ticks(Scale::Log, NumberFormat::Si, (-3.0, 3.0), 3);
+        assert_eq!(ticks.iter().map(|x| (x.position, x.label.as_str())).collect::<Vec<_>>(), [(-2.0, "10m"), (0.0, "1"), (2.0, "100")]);
+    }
+}
@@ -0,0 +1,88 @@
+/// the bit of each dot of a braille character, by column and then row
+const DOTS: [[u8; 4]; 2] = [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+
+/// a grid of braille characters where every character holds 2 by 4 dots
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct BrailleCanvas {
+    width: usize,
+    height: usize,
+    cells: Vec<u8>,
+}
+impl BrailleCanvas {
+    /// a canvas of the given size in characters
+    pub fn new(width: usize, height: usize) -> Self {
+        Self { width, height, cells: vec![0; width * height] }
+    }
+    /// the width in dots
+    pub fn dot_width(&self) -> usize {
+        self.width * 2
+    }
+    /// the height in dots
+    pub fn dot_height(&self) -> usize {
+        self.height * 4
+    }
+    /// sets the dot, dots outside of the canvas are ignored
+    pub fn set(&mut self, x: i64, y: i64) {
+        if x < 0 || y < 0 || x as usize >= self.dot_width() || y as usize >= self.dot_height() {
+            return;
+        }
+        let (x, y) = (x as usize, y as usize);
+        self.cells[y / 4 * self.width + x / 2] |= DOTS[x % 2][y % 4];
+    }
+    /// sets every dot on the line between the two dots
+    pub fn line(&mut self, from: (i64, i64), to: (i64, i64)) {
+        for (x, y) in line_dots(from, to) {
+            self.set(x, y);
+        }
+    }
+    /// the character at the position, None when none of its dots are set
+    pub fn cell(&self, column: usize, row: usize) -> Option<char> {
+        match self.cells[row * self.width + column] {
+            0 => None,
+            bits => char::from_u32(0x2800 + bits as u32),
+        }
+    }
+}
+
+/// the dots on the line between the two dots, both included
+pub fn line_dots(from: (i64, i64), to: (i64, i64)) -> Vec<(i64, i64)> {
+    let (dx, dy) = ((to.0 - from.0).abs(), -(to.1 - from.1).abs());
+    let (sx, sy) = ((to.0 - from.0).signum(), (to.1 - from.1).signum());
+    let (mut x, mut y) = from;
+    let mut err = dx + dy;
+    let mut out = vec![];
+    loop {
+        out.push((x, y));
+        if (x, y) == to {
+            break;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dots(){
+        let mut canvas = BrailleCanvas::new(2, 1);
+        canvas.set(0, 0);
+        canvas.set(1, 3);
+        canvas.set(4, 0);
+        assert_eq!(canvas.cell(0, 0), Some('⢁'));
+        assert_eq!(canvas.cell(1, 0), None);
+        canvas.line((0, 3), (3, 0));
+        assert_eq!(canvas.cell(0, 0), Some('⣡'));
+        assert_eq!(canvas.cell(1, 0), Some('⠊'));
+    }
+}
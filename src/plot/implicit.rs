@@ -0,0 +1,184 @@
+//! traces where a function of two arguments is zero with marching squares, used for implicit plots like
+//! plot x^2 + y^2 == 1 for x in -2..2, y in -2..2;
+//!
+//! the region is split into a grid of cells and every cell whose corners are not all on the same side of zero is
+//! halved in both directions [`MAX_DEPTH`] times. the corners of the smallest cells are on a lattice, the value at each
+//! point of it is only computed once and the segments of neighbouring cells meet at the same points so they can be
+//! joined into lines
+
+use std::collections::HashMap;
+
+use super::Polyline;
+
+/// the number of times a cell of the grid is halved at most
+pub const MAX_DEPTH: u32 = 3;
+
+/// a point of the lattice by its column and row
+type Node = (usize, usize);
+/// the side of a cell of the lattice, the lower node first
+type Edge = (Node, Node);
+
+/// the lines where the function is zero, sampled on a grid of `count` by `count` points over both ranges
+///
+/// cells where the function fails to evaluate are left out, like cells the function jumps across zero in without
+/// passing through it
+pub fn contour(x_range: (f64, f64), y_range: (f64, f64), count: usize, f: impl FnMut(f64, f64) -> Option<f64>) -> Vec<Polyline> {
+    let step = 1 << MAX_DEPTH;
+    let cells = count.max(2) - 1;
+    let mut contour = Contour { f, x_range, y_range, size: cells * step, values: HashMap::new(), segments: vec![] };
+    for column in 0..cells {
+        for row in 0..cells {
+            contour.cell((column * step, row * step), step);
+        }
+    }
+    let lines = join(&contour.segments);
+    lines.iter().map(|x| x.iter().map(|edge| contour.crossing(*edge)).collect()).collect()
+}
+
+struct Contour<F> {
+    f: F,
+    x_range: (f64, f64),
+    y_range: (f64, f64),
+    /// the number of the smallest cells along each axis
+    size: usize,
+    values: HashMap<Node, Option<f64>>,
+    /// pairs of edges the curve passes through in one of the smallest cells
+    segments: Vec<(Edge, Edge)>,
+}
+impl<F: FnMut(f64, f64) -> Option<f64>> Contour<F> {
+    fn point(&self, (column, row): (f64, f64)) -> (f64, f64) {
+        let at = |(min, max): (f64, f64), i: f64| min + (max - min) * i / self.size as f64;
+        (at(self.x_range, column), at(self.y_range, row))
+    }
+    fn eval(&mut self, node: (f64, f64)) -> Option<f64> {
+        let (x, y) = self.point(node);
+        (self.f)(x, y).filter(|x| x.is_finite())
+    }
+    fn value(&mut self, node: Node) -> Option<f64> {
+        if let Some(value) = self.values.get(&node) {
+            return *value;
+        }
+        let value = self.eval((node.0 as f64, node.1 as f64));
+        self.values.insert(node, value);
+        value
+    }
+    /// where the curve crosses the edge, between its nodes in proportion to their values
+    fn crossing(&self, (a, b): Edge) -> (f64, f64) {
+        let (Some(Some(va)), Some(Some(vb))) = (self.values.get(&a), self.values.get(&b)) else {
+            unreachable!("segments only pass through edges with values at both ends")
+        };
+        let t = va / (va - vb);
+        self.point((a.0 as f64 + t * (b.0 as f64 - a.0 as f64), a.1 as f64 + t * (b.1 as f64 - a.1 as f64)))
+    }
+    /// looks for the curve in the cell with the lower left node and the size in steps of the lattice
+    fn cell(&mut self, (column, row): Node, size: usize) {
+        // counterclockwise from the lower left
+        let corners = [(column, row), (column + size, row), (column + size, row + size), (column, row + size)];
+        let values = corners.map(|x| self.value(x));
+        let crosses = values.iter().any(|x| x.is_some_and(|x| x > 0.0)) && values.iter().any(|x| x.is_some_and(|x| x <= 0.0));
+        let partial = values.iter().any(Option::is_none) && values.iter().any(Option::is_some);
+        if !crosses && !partial {
+            return;
+        }
+        if size > 1 {
+            let half = size / 2;
+            for (dx, dy) in [(0, 0), (half, 0), (0, half), (half, half)] {
+                self.cell((column + dx, row + dy), half);
+            }
+            return;
+        }
+        let Some(values) = values.into_iter().collect::<Option<Vec<_>>>() else { return };
+        let Some(center) = self.eval((column as f64 + 0.5, row as f64 + 0.5)) else { return };
+        // the value grows towards the middle of the cell instead of passing through zero, like at a pole of 1 / x
+        if center.abs() > 2.0 * values.iter().fold(0.0f64, |max, x| max.max(x.abs())) {
+            return;
+        }
+        let above = values.iter().map(|x| *x > 0.0).collect::<Vec<_>>();
+        let edge = |i: usize| {
+            let (a, b) = (corners[i], corners[(i + 1) % 4]);
+            if a < b { (a, b) } else { (b, a) }
+        };
+        let crossed = (0..4).filter(|i| above[*i] != above[(i + 1) % 4]).collect::<Vec<_>>();
+        match crossed[..] {
+            [a, b] => self.segments.push((edge(a), edge(b))),
+            // opposite corners are on the same side, the middle decides which of them are connected
+            [_, _, _, _] if (center > 0.0) == above[0] => {
+                self.segments.push((edge(0), edge(1)));
+                self.segments.push((edge(2), edge(3)));
+            },
+            [_, _, _, _] => {
+                self.segments.push((edge(3), edge(0)));
+                self.segments.push((edge(1), edge(2)));
+            },
+            _ => unreachable!("a cell with corners on both sides has two or four crossed edges"),
+        }
+    }
+}
+
+/// the segments joined into lines where they share an edge, every line as the edges it passes through
+fn join(segments: &[(Edge, Edge)]) -> Vec<Vec<Edge>> {
+    let mut at = HashMap::<Edge, Vec<usize>>::new();
+    for (i, (a, b)) in segments.iter().enumerate() {
+        at.entry(*a).or_default().push(i);
+        at.entry(*b).or_default().push(i);
+    }
+    let mut used = vec![false; segments.len()];
+    let mut lines = vec![];
+    for start in 0..segments.len() {
+        if used[start] {
+            continue;
+        }
+        used[start] = true;
+        let mut line = vec![segments[start].0, segments[start].1];
+        // follows the line forwards and then backwards from the first segment
+        for backwards in [false, true] {
+            if backwards {
+                line.reverse();
+            }
+            while let Some(&next) = at[line.last().expect("a line has two edges")].iter().find(|x| !used[**x]) {
+                used[next] = true;
+                let (a, b) = segments[next];
+                line.push(if a == *line.last().expect("a line has two edges") { b } else { a });
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn circle(){
+        let lines = contour((-2.0, 2.0), (-2.0, 2.0), 9, |x, y| Some(x * x + y * y - 1.0));
+        // one closed line close to the circle
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].first(), lines[0].last());
+        assert!(lines[0].len() > 50);
+        assert!(lines[0].iter().all(|(x, y)| (x.hypot(*y) - 1.0).abs() < 1e-2));
+    }
+    #[test]
+    fn lines(){
+        // exact on straight lines, crossing the whole region
+        let lines = contour((0.0, 1.0), (0.0, 1.0), 3, |x, y| Some(y - 0.3 - x / 2.0));
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].iter().all(|(x, y)| (y - 0.3 - x / 2.0).abs() < 1e-12));
+        let (xs, _): (Vec<f64>, Vec<f64>) = lines[0].iter().copied().unzip();
+        assert_eq!(xs.iter().fold(f64::INFINITY, |a, b| a.min(*b)), 0.0);
+        assert_eq!(xs.iter().fold(f64::NEG_INFINITY, |a, b| a.max(*b)), 1.0);
+        // two lines through a saddle
+        let lines = contour((-1.0, 1.0), (-1.0, 1.0), 4, |x, y| Some(x * y));
+        assert!(lines.iter().flatten().all(|(x, y)| x.abs() < 1e-12 || y.abs() < 1e-12));
+    }
+    #[test]
+    fn poles(){
+        assert_eq!(contour((-1.0, 1.0), (-1.0, 1.0), 6, |x, _| Some(1.0 / x)), Vec::<Polyline>::new());
+        assert_eq!(contour((-1.0, 1.0), (-1.0, 1.0), 5, |_, _| None), Vec::<Polyline>::new());
+        // the line stops close to where the function stops having a value
+        let lines = contour((-1.0, 1.0), (-1.0, 1.0), 5, |x, y| (x > 0.0).then_some(y));
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].iter().all(|(x, y)| *x > 0.0 && *y == 0.0));
+    }
+}
@@ -0,0 +1,117 @@
+//! where the parts of a plot go in an image, shared by the svg and raster renderers
+
+use super::{axes::{axis_ticks, project, x_positions, Scale, Tick}, equal_aspect, surface::{extent, Face, Surface}, y_range, Polyline, SampledPlot};
+
+/// the space in pixels at 96 dpi between the edges of the image and the plot area, the title is above the top margin
+pub const LEFT: f64 = 56.0;
+pub const RIGHT: f64 = 16.0;
+pub const TOP: f64 = 16.0;
+pub const BOTTOM: f64 = 36.0;
+pub const TITLE_HEIGHT: f64 = 28.0;
+/// the space taken by the label of an axis, below the bottom margin for x and left of the left margin for y
+pub const LABEL_SPACE: f64 = 20.0;
+/// the space right of the plot area of a plot3d taken by its colour scale and the labels of the scale
+pub const SCALE_SPACE: f64 = 72.0;
+/// the space between the plot area and the colour scale, and the width of the scale
+pub const SCALE_GAP: f64 = 16.0;
+pub const SCALE_WIDTH: f64 = 12.0;
+
+/// the plot area of an image and the ticks on its axes
+///
+/// the ranges, ticks and lines are at their positions along the axes, see [`super::axes`]
+pub struct Layout {
+    pub left: f64,
+    pub right: f64,
+    pub top: f64,
+    pub bottom: f64,
+    /// widened to keep the aspect of plots with equal aspect
+    pub x_range: (f64, f64),
+    /// the one given by the plot or chosen to fit every point of it
+    pub y_range: (f64, f64),
+    pub x_ticks: Vec<Tick>,
+    pub y_ticks: Vec<Tick>,
+    /// the lines of every series, broken where a scale can not show a point
+    pub lines: Vec<Vec<Polyline>>,
+    /// whether lines are drawn across the plot area at the ticks
+    pub grid: bool,
+    /// the colour scale of a plot3d
+    pub color_scale: Option<ColorScale>,
+}
+impl Layout {
+    /// the layout of the plot in an image of the size in pixels, `scale` is the number of pixels per pixel at 96 dpi
+    pub fn new(plot: &SampledPlot, width: f64, height: f64, scale: f64) -> Self {
+        let (scales, format) = (plot.axes.scales(), plot.axes.format.unwrap_or_default());
+        let series = plot.series.iter().map(|x| project(&x.lines, scales)).collect::<Vec<_>>();
+        let lines = series.iter().flatten().cloned().collect::<Vec<_>>();
+        let top = if plot.title.is_some() { TOP + TITLE_HEIGHT } else { TOP } * scale;
+        let left = if plot.axes.y_label.is_some() { LEFT + LABEL_SPACE } else { LEFT } * scale;
+        let right = (width - if plot.surface.is_some() { RIGHT + SCALE_SPACE } else { RIGHT } * scale).max(left + 1.0);
+        let bottom = (height - if plot.axes.x_label.is_some() { BOTTOM + LABEL_SPACE } else { BOTTOM } * scale).max(top + 1.0);
+        // about a tick every 80 pixels horizontally and every 50 pixels vertically
+        let x_count = (((right - left) / (80.0 * scale)) as usize).max(2);
+        let y_count = (((bottom - top) / (50.0 * scale)) as usize).max(2);
+        let x_range = x_positions(plot.x_range, scales.0, &lines);
+        let fitted = plot.y_range.and_then(|x| scales.1.range(x)).unwrap_or_else(|| y_range(&lines));
+        let (x_range, y_range) = match plot.equal_aspect {
+            true => equal_aspect(x_range, fitted, right - left, bottom - top),
+            false => (x_range, fitted),
+        };
+        let color_scale = plot.surface.as_ref().map(|surface| {
+            let z_range = surface.z_range();
+            ColorScale {
+                left: right + SCALE_GAP * scale,
+                right: right + (SCALE_GAP + SCALE_WIDTH) * scale,
+                top, bottom,
+                z_range,
+                ticks: axis_ticks(Scale::Linear, format, z_range, y_count),
+            }
+        });
+        Self {
+            left, right, top, bottom,
+            x_range,
+            y_range,
+            x_ticks: axis_ticks(scales.0, format, x_range, x_count),
+            y_ticks: axis_ticks(scales.1, format, y_range, y_count),
+            lines: series,
+            grid: plot.axes.grid.unwrap_or(true),
+            color_scale,
+        }
+    }
+    /// the horizontal pixel of the x coordinate
+    pub fn x(&self, x: f64) -> f64 {
+        self.left + (x - self.x_range.0) / (self.x_range.1 - self.x_range.0) * (self.right - self.left)
+    }
+    /// the vertical pixel of the y coordinate, larger y are higher up
+    pub fn y(&self, y: f64) -> f64 {
+        self.bottom - (y - self.y_range.0) / (self.y_range.1 - self.y_range.0) * (self.bottom - self.top)
+    }
+    /// the cells of the wireframe from the back to the front with their corners in pixels, as large as fits into the
+    /// plot area without stretching it
+    pub fn wireframe(&self, surface: &Surface) -> Vec<Face> {
+        let mut faces = surface.faces();
+        let ((x_min, x_max), (y_min, y_max)) = extent(&faces);
+        let size = ((self.right - self.left) / (x_max - x_min)).min((self.bottom - self.top) / (y_max - y_min));
+        let (x_offset, y_offset) = ((self.left + self.right - (x_max - x_min) * size) / 2.0, (self.top + self.bottom - (y_max - y_min) * size) / 2.0);
+        for face in &mut faces {
+            face.corners = face.corners.map(|(x, y)| (x_offset + (x - x_min) * size, y_offset + (y_max - y) * size));
+        }
+        faces
+    }
+}
+
+/// the bar right of the plot area of a plot3d showing the colours of the heights, as high as the plot area
+pub struct ColorScale {
+    pub left: f64,
+    pub right: f64,
+    pub top: f64,
+    pub bottom: f64,
+    /// the heights at the bottom and the top of the bar
+    pub z_range: (f64, f64),
+    pub ticks: Vec<Tick>,
+}
+impl ColorScale {
+    /// the vertical pixel of the height, larger heights are higher up
+    pub fn y(&self, z: f64) -> f64 {
+        self.bottom - (z - self.z_range.0) / (self.z_range.1 - self.z_range.0) * (self.bottom - self.top)
+    }
+}
@@ -0,0 +1,729 @@
+//! sampling functions and drawing them as graphs
+
+use std::{fs, path::Path};
+
+use axes::{Axes, NumberFormat, Scale};
+use implicit::contour;
+use sampler::{sample, sample_curve};
+use serde::Serialize;
+use surface::Surface;
+
+use crate::{eval::{evaluate_source, EvalError, Evaluator, RunError, Value}, parser::{compile_source, expression::Expression, plot::{OptionValue, Plot, PlotRange, PlotSeries}, statement::Statement}, spanned::{Span, Spanned}, ProgramSource};
+
+pub mod axes;
+pub mod braille;
+pub mod font;
+pub mod implicit;
+pub mod layout;
+pub mod raster;
+pub mod sampler;
+pub mod surface;
+pub mod svg;
+pub mod terminal;
+
+/// a connected part of a graph, the points are sorted by x unless it is a parametric or polar curve
+pub type Polyline = Vec<(f64, f64)>;
+
+/// the x range used when a plot does not give one
+pub const DEFAULT_RANGE: (f64, f64) = (-10.0, 10.0);
+
+/// the number of evenly spaced samples of a plot without a `samples` option, two for every column of braille dots in
+/// the repl, more are taken between them where the graph bends
+pub const DEFAULT_SAMPLES: usize = terminal::DEFAULT_SIZE.0 * 4;
+/// the number of points along each axis of the grid of an implicit plot without a `samples` option, the cells the
+/// curve passes through are halved until they are as small as the distance between the samples of other plots
+pub const DEFAULT_GRID: usize = DEFAULT_SAMPLES >> implicit::MAX_DEPTH;
+/// the most points along each axis of the grid of an implicit plot, it is evaluated at every point of the grid and
+/// more often in the cells the curve passes through
+pub const MAX_GRID: usize = 400;
+/// the most points along each axis of a plot3d, every cell between them is drawn on its own
+pub const MAX_SURFACE: usize = 200;
+/// the number of points along each axis of a plot3d heatmap without a `samples` option
+pub const DEFAULT_SURFACE: usize = 32;
+/// the number of points along each axis of a plot3d wireframe without a `samples` option, fewer than for a heatmap so
+/// the cells stay apart in the repl
+pub const DEFAULT_WIREFRAME: usize = 16;
+
+/// the size of images of plots in pixels at 96 dpi and their resolution, used when neither the plot nor the command
+/// line give one
+pub const DEFAULT_IMAGE: (f64, f64, f64) = (640.0, 400.0, 96.0);
+/// the smallest and largest width and height of images
+pub const PIXEL_LIMITS: (f64, f64) = (16.0, 4096.0);
+/// the smallest and largest resolution of raster images
+pub const DPI_LIMITS: (f64, f64) = (24.0, 384.0);
+/// the fewest and most evenly spaced samples a plot can ask for
+pub const SAMPLE_LIMITS: (f64, f64) = (2.0, 100_000.0);
+
+/// a plotted expression sampled over the range of its plot
+#[derive(PartialEq, Debug, Clone, Serialize)]
+pub struct Series {
+    /// the expression as written in the source
+    pub label: String,
+    pub lines: Vec<Polyline>,
+}
+
+/// a plot statement evaluated at evenly spaced points of its range
+#[derive(PartialEq, Debug, Clone, Serialize)]
+pub struct SampledPlot {
+    pub title: Option<String>,
+    pub x_range: (f64, f64),
+    /// the y range given by an implicit plot, fitted to the points when there is none
+    pub y_range: Option<(f64, f64)>,
+    pub series: Vec<Series>,
+    /// the file given by the `to` clause of the plot
+    pub output: Option<String>,
+    /// the size given by the options of the plot
+    pub size: ImageSize,
+    /// whether a unit is as long along x as along y, so circles look round
+    pub equal_aspect: bool,
+    /// the heights drawn by a plot3d, which has no series
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub surface: Option<Surface>,
+    pub axes: Axes,
+}
+impl SampledPlot {
+    /// the plot with the parts of its axes it does not give taken from the defaults, like the ones given on the command
+    /// line. a plot3d keeps linear scales
+    pub fn with_axes(self, defaults: &Axes) -> Self {
+        let axes = match self.surface {
+            Some(_) => Axes { x_scale: None, y_scale: None, ..defaults.clone() },
+            None => defaults.clone(),
+        };
+        Self { axes: self.axes.or(&axes), ..self }
+    }
+}
+
+/// the size of the image a plot is written to, parts which are not given are taken from elsewhere
+#[derive(PartialEq, Debug, Clone, Copy, Default, Serialize)]
+pub struct ImageSize {
+    /// in pixels at 96 dpi
+    pub width: Option<f64>,
+    pub height: Option<f64>,
+    /// raster images have dpi / 96 times as many pixels in each direction
+    pub dpi: Option<f64>,
+}
+impl ImageSize {
+    /// the parts given by this size with the missing ones taken from the other
+    pub fn or(self, other: Self) -> Self {
+        Self { width: self.width.or(other.width), height: self.height.or(other.height), dpi: self.dpi.or(other.dpi) }
+    }
+    /// the width, height and dpi with the defaults for the missing ones
+    pub fn resolve(self) -> (f64, f64, f64) {
+        (self.width.unwrap_or(DEFAULT_IMAGE.0), self.height.unwrap_or(DEFAULT_IMAGE.1), self.dpi.unwrap_or(DEFAULT_IMAGE.2))
+    }
+}
+
+/// the formats plots can be written to a file as
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum FileFormat {
+    Svg,
+    Png,
+}
+impl FileFormat {
+    /// the format of the file by its extension
+    pub fn of(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "svg" => Some(Self::Svg),
+            "png" => Some(Self::Png),
+            _ => None,
+        }
+    }
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Svg => "svg",
+            Self::Png => "png",
+        }
+    }
+    /// the plot in this format at its own size, the parts of the size it does not give are taken from the defaults
+    pub fn render(self, plot: &SampledPlot, defaults: ImageSize) -> Vec<u8> {
+        let (width, height, dpi) = plot.size.or(defaults).resolve();
+        match self {
+            Self::Svg => svg::render(plot, width, height).into_bytes(),
+            Self::Png => raster::render(plot, width, height, dpi),
+        }
+    }
+}
+
+/// writes the plot to the file in the format of its extension
+pub fn write_file(plot: &SampledPlot, path: &Path, defaults: ImageSize) -> Result<(), String> {
+    let format = FileFormat::of(path)
+        .ok_or_else(|| format!("cannot write a plot to {}, the file has to end in .svg or .png", path.display()))?;
+    fs::write(path, format.render(plot, defaults)).map_err(|err| format!("unable to write {}: {err}", path.display()))
+}
+
+/// the ranges plots are sampled and drawn over instead of their own, used to pan and zoom
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub struct Window {
+    pub x_range: Option<(f64, f64)>,
+    pub y_range: Option<(f64, f64)>,
+    /// the scale of the x axis of plots which do not give one, which functions are sampled evenly along
+    pub x_scale: Option<Scale>,
+}
+
+/// the top level bindings with their values and the sampled top level plots
+pub type Plotted = (Vec<(String, Value)>, Vec<SampledPlot>);
+
+/// evaluates the source like [`evaluate_source`] and samples every top level plot statement
+pub fn plot_source(db: &dyn salsa::Database, source: ProgramSource) -> Result<Plotted, RunError> {
+    plot_source_in(db, source, Window::default())
+}
+
+/// samples the plots of the source like [`plot_source`] over the ranges of the window where it gives them
+pub fn plot_source_in(db: &dyn salsa::Database, source: ProgramSource, window: Window) -> Result<Plotted, RunError> {
+    let (mut evaluator, values) = evaluate_source(db, source)?;
+    let mut plots = vec![];
+    for statement in compile_source(db, source).statements(db) {
+        let Statement::Plot(plot) = statement else { continue };
+        match sample_plot(db, &mut evaluator, source.raw_text(db), *plot, window) {
+            Ok(plot) => plots.push(plot),
+            Err(err) => return Err(RunError::Failed(values, err)),
+        }
+    }
+    Ok((values, plots))
+}
+
+/// evaluates the range and the options of the plot and samples every plotted expression
+///
+/// points where evaluating fails are left out, errors in the range or the options stop the plot. functions are sampled
+/// over the x range of the window when it has one, parametric and polar curves always over the range of the plot
+pub fn sample_plot<'db>(db: &'db dyn salsa::Database, evaluator: &mut Evaluator<'db>, source: &str, plot: Plot<'db>, window: Window) -> Result<SampledPlot, EvalError> {
+    let parts = (0..plot.series(db).len())
+        .map(|i| sample_plot_series(db, evaluator, source, plot, window, i))
+        .collect::<Result<Vec<_>, _>>()?;
+    combine_plot(db, evaluator, plot, window, parts)
+}
+
+/// a plotted expression sampled on its own, see [`sample_plot_series`]
+#[derive(PartialEq, Debug, Clone)]
+pub struct SampledSeries {
+    pub series: Series,
+    /// the x range of a parametric or polar curve, functions are drawn over the range of the plot
+    pub extent: Option<(f64, f64)>,
+    /// the heights of a plot3d, its series has no lines
+    pub surface: Option<Surface>,
+}
+
+/// samples the plotted expression with the index like [`sample_plot`], the series are joined by [`combine_plot`]
+///
+/// sampling every series on its own lets the repl keep the ones which did not change. functions on a log x scale are
+/// sampled at points evenly spaced along the axis
+pub fn sample_plot_series<'db>(db: &'db dyn salsa::Database, evaluator: &mut Evaluator<'db>, source: &str, plot: Plot<'db>, window: Window, index: usize) -> Result<SampledSeries, EvalError> {
+    let settings = settings(db, evaluator, plot)?;
+    let window = Window { x_range: window.x_range.or(settings.x_view), y_range: window.y_range.or(settings.y_view), ..window };
+    let series = &plot.series(db)[index];
+    let (min, max) = settings.range;
+    if let (Some(second), Some(y_range)) = (plot.second(db), settings.second) {
+        let ranges = (window.x_range.unwrap_or((min, max)), window.y_range.unwrap_or(y_range));
+        let sites = (plot.variable(db).span(), second.variable.span());
+        if plot.surface(db) {
+            let count = settings.count.unwrap_or(if settings.wireframe { DEFAULT_WIREFRAME } else { DEFAULT_SURFACE });
+            let surface = surface_series(evaluator, source, series, sites, ranges, count, settings.wireframe)?;
+            let series = Series { label: surface.label.clone(), lines: vec![] };
+            return Ok(SampledSeries { series, extent: None, surface: Some(surface) });
+        }
+        let series = implicit_series(evaluator, source, series, sites, ranges, settings.count.unwrap_or(DEFAULT_GRID))?;
+        return Ok(SampledSeries { series, extent: None, surface: None });
+    }
+    let count = settings.count.unwrap_or(DEFAULT_SAMPLES);
+    let variable = plot.variable(db).span();
+    let range = window.x_range.unwrap_or((min, max));
+    let log = settings.axes.x_scale.or(window.x_scale) == Some(Scale::Log) && range.0 > 0.0;
+    let (mut lines, curve) = sample_series(evaluator, variable, series, range, count, log);
+    // parametric and polar curves keep the range of their parameter and its even spacing
+    if curve && (window.x_range.is_some() || log) {
+        lines = sample_series(evaluator, variable, series, (min, max), count, false).0;
+    }
+    let extent = curve.then(|| x_extent(&lines));
+    Ok(SampledSeries { series: Series { label: source[series.span().start..series.span().end].to_owned(), lines }, extent, surface: None })
+}
+
+/// the plot of the sampled series, which are in the order of the plotted expressions
+pub fn combine_plot<'db>(db: &'db dyn salsa::Database, evaluator: &mut Evaluator<'db>, plot: Plot<'db>, window: Window, parts: Vec<SampledSeries>) -> Result<SampledPlot, EvalError> {
+    let Settings { range: (min, max), second, title, count: _, size, equal_aspect, wireframe: _, x_view, y_view, axes, output } = settings(db, evaluator, plot)?;
+    let window = Window { x_range: window.x_range.or(x_view), y_range: window.y_range.or(y_view), ..window };
+    if let Some(y_range) = second {
+        let (x_range, y_range) = (window.x_range.unwrap_or((min, max)), window.y_range.unwrap_or(y_range));
+        let surface = parts.iter().find_map(|x| x.surface.clone());
+        let series = parts.into_iter().filter(|x| x.surface.is_none()).map(|x| x.series).collect();
+        return Ok(SampledPlot { title, x_range, y_range: Some(y_range), series, output, size, equal_aspect, surface, axes });
+    }
+    let x_range = parts.iter()
+        .map(|x| x.extent.unwrap_or(window.x_range.unwrap_or((min, max))))
+        .reduce(|(a, b), (low, high)| (a.min(low), b.max(high)));
+    let x_range = match (window.x_range, x_range) {
+        (Some(range), _) => range,
+        (None, Some((low, high))) if low < high => (low, high),
+        (None, Some((low, _))) if low.is_finite() => (low - 1.0, low + 1.0),
+        _ => (min, max),
+    };
+    let series = parts.into_iter().map(|x| x.series).collect();
+    Ok(SampledPlot { title, x_range, y_range: window.y_range, series, output, size, equal_aspect, surface: None, axes })
+}
+
+/// the range and the options of a plot statement
+struct Settings {
+    range: (f64, f64),
+    /// the range of the second variable of an implicit plot
+    second: Option<(f64, f64)>,
+    title: Option<String>,
+    count: Option<usize>,
+    size: ImageSize,
+    equal_aspect: bool,
+    /// whether a plot3d is drawn as a wireframe instead of a heatmap
+    wireframe: bool,
+    /// the ranges given by the `x` and `y` options, used like a window
+    x_view: Option<(f64, f64)>,
+    y_view: Option<(f64, f64)>,
+    axes: Axes,
+    output: Option<String>,
+}
+
+fn settings<'db>(db: &'db dyn salsa::Database, evaluator: &mut Evaluator<'db>, plot: Plot<'db>) -> Result<Settings, EvalError> {
+    let range = bounds(evaluator, plot.range(db))?;
+    let mut title = None;
+    let mut count = None;
+    let mut size = ImageSize::default();
+    let mut equal_aspect = false;
+    let mut wireframe = false;
+    let (mut x_view, mut y_view) = (None, None);
+    let mut axes = Axes::default();
+    for option in plot.options(db) {
+        match (option.name.as_str(), &option.value) {
+            ("title", Some(OptionValue::String(x))) => title = Some((**x).clone()),
+            ("samples", Some(OptionValue::Expression(x))) => count = match within(evaluator, x, "number of samples", SAMPLE_LIMITS)? {
+                count if count.fract() == 0.0 => Some(count as usize),
+                other => return Err(EvalError { span: x.span(), message: format!("the number of samples has to be whole, found {other}") }),
+            },
+            ("width", Some(OptionValue::Expression(x))) => size.width = Some(within(evaluator, x, "width", PIXEL_LIMITS)?),
+            ("height", Some(OptionValue::Expression(x))) => size.height = Some(within(evaluator, x, "height", PIXEL_LIMITS)?),
+            ("dpi", Some(OptionValue::Expression(x))) => size.dpi = Some(within(evaluator, x, "dpi", DPI_LIMITS)?),
+            ("equal", None) => equal_aspect = true,
+            ("wireframe", None) if plot.surface(db) => wireframe = true,
+            ("x", Some(OptionValue::Range(x))) => x_view = Some(bounds(evaluator, x)?),
+            ("y", Some(OptionValue::Range(x))) => y_view = Some(bounds(evaluator, x)?),
+            (name @ ("xscale" | "yscale"), Some(OptionValue::String(x))) => {
+                let scale = Scale::of(x).ok_or_else(|| option_error(x.span(), &format!("`{name}` is \"linear\" or \"log\", found \"{}\"", **x)))?;
+                if plot.surface(db) && scale == Scale::Log {
+                    return Err(option_error(x.span(), "plot3d is always drawn on linear scales"));
+                }
+                *if name == "xscale" { &mut axes.x_scale } else { &mut axes.y_scale } = Some(scale);
+            },
+            ("xlabel", Some(OptionValue::String(x))) => axes.x_label = Some((**x).clone()),
+            ("ylabel", Some(OptionValue::String(x))) => axes.y_label = Some((**x).clone()),
+            ("grid", None) => axes.grid = Some(true),
+            ("grid", Some(OptionValue::String(x))) => axes.grid = Some(match x.as_str() {
+                "on" => true,
+                "off" => false,
+                other => return Err(option_error(x.span(), &format!("`grid` is \"on\" or \"off\", found \"{other}\""))),
+            }),
+            ("format", Some(OptionValue::String(x))) => axes.format = Some(NumberFormat::of(x)
+                .ok_or_else(|| option_error(x.span(), &format!("`format` is \"plain\" or \"si\", found \"{}\"", **x)))?),
+            ("title", _) => return Err(option_error(option.name.span(), "`title` takes a string like title \"sine\"")),
+            ("samples", _) => return Err(option_error(option.name.span(), "`samples` takes a number like samples 200")),
+            (name @ ("width" | "height" | "dpi"), _) => return Err(option_error(option.name.span(), &format!("`{name}` takes a number like {name} 200"))),
+            ("equal", _) => return Err(option_error(option.name.span(), "`equal` takes no value, it is written as with equal")),
+            ("wireframe", None) => return Err(option_error(option.name.span(), "`wireframe` only applies to plot3d")),
+            ("wireframe", _) => return Err(option_error(option.name.span(), "`wireframe` takes no value, it is written as with wireframe")),
+            (name @ ("x" | "y"), _) => return Err(option_error(option.name.span(), &format!("`{name}` takes a range like {name} 0..10"))),
+            (name @ ("xscale" | "yscale"), _) => return Err(option_error(option.name.span(), &format!("`{name}` takes a string like {name} \"log\""))),
+            (name @ ("xlabel" | "ylabel"), _) => return Err(option_error(option.name.span(), &format!("`{name}` takes a string like {name} \"time\""))),
+            ("grid", _) => return Err(option_error(option.name.span(), "`grid` takes \"on\" or \"off\", it is on when written without a value")),
+            ("format", _) => return Err(option_error(option.name.span(), "`format` takes a string like format \"si\"")),
+            (name, _) => return Err(option_error(
+                option.name.span(),
+                &format!("unknown plot option `{name}`, expected title, samples, width, height, dpi, equal, wireframe, x, y, xscale, yscale, xlabel, ylabel, grid or format"),
+            )),
+        }
+    }
+    for (name, view, scale) in [("x", x_view, axes.x_scale), ("y", y_view, axes.y_scale)] {
+        if let (Some((min, max)), Some(Scale::Log)) = (view, scale) && min <= 0.0 {
+            let span = plot.option(db, name).and_then(|x| x.value.as_ref()).map_or(plot.span(db), OptionValue::span);
+            return Err(option_error(span, &format!("a log scale only shows positive numbers, the range {min}..{max} is not")));
+        }
+    }
+    if plot.surface(db) {
+        if plot.second(db).is_none() {
+            return Err(option_error(plot.range(db).span(), "a `plot3d` draws a height over two variables, like plot3d f for x in -2..2, y in -2..2"));
+        }
+        if let [_, extra, ..] = &plot.series(db)[..] {
+            return Err(option_error(extra.span(), "a `plot3d` draws a single surface"));
+        }
+    }
+    let (limit, kind) = if plot.surface(db) { (MAX_SURFACE, "a `plot3d`") } else { (MAX_GRID, "a plot of two variables") };
+    if let (Some(count), Some(_)) = (count, plot.second(db)) && count > limit {
+        let span = plot.option(db, "samples").and_then(|x| x.value.as_ref()).map_or(plot.span(db), OptionValue::span);
+        return Err(option_error(span, &format!("{kind} samples a grid of at most {limit} by {limit} points, found {count}")));
+    }
+    let second = plot.second(db).as_ref().map(|x| bounds(evaluator, &x.range)).transpose()?;
+    let output = plot.output(db).as_ref().map(|x| (**x).clone());
+    Ok(Settings { range, second, title, count, size, equal_aspect, wireframe, x_view, y_view, axes, output })
+}
+
+/// samples the series over the range of its variable, also returning whether it is a curve, which it is when it is
+/// polar or any of its values is a pair of numbers
+///
+/// with `log` the values of the variable are evenly spaced on a log scale, the range has to be positive
+fn sample_series<'db>(evaluator: &mut Evaluator<'db>, variable: Span, series: &PlotSeries<'db>, (min, max): (f64, f64), count: usize, log: bool) -> (Vec<Polyline>, bool) {
+    let mut curve = series.polar.is_some();
+    let scale = if log { Scale::Log } else { Scale::Linear };
+    let (start, end) = scale.range((min, max)).unwrap_or((min, max));
+    let lines = sample_curve(start, end, count, |position| {
+        let t = scale.value(position);
+        match evaluator.expression_with(&[(variable, Value::Float(t))], &series.expression).ok()? {
+            Value::Tuple(parts) => match &parts[..] {
+                [a, b] => {
+                    curve = true;
+                    Some((a.as_float()?, b.as_float()?))
+                },
+                _ => None,
+            },
+            value if series.polar.is_some() => value.as_float().map(|r| (r * t.cos(), r * t.sin())),
+            value => Some((t, value.as_float()?)),
+        }
+    });
+    (lines, curve)
+}
+
+/// traces where the comparison of the series holds with equality, the difference of both sides is zero
+fn implicit_series<'db>(
+    evaluator: &mut Evaluator<'db>,
+    source: &str,
+    series: &PlotSeries<'db>,
+    (x, y): (Span, Span),
+    ranges: ((f64, f64), (f64, f64)),
+    count: usize,
+) -> Result<Series, EvalError> {
+    let (left, right) = match (&*series.expression, series.polar) {
+        (_, Some(span)) => return Err(EvalError { span, message: "`polar` curves have one variable, the angle".to_owned() }),
+        (Expression::Binary(left, op, right), None) if op.is_comparison() => (left, right),
+        _ => return Err(EvalError { span: series.span(), message: "a plot of two variables draws equations like x^2 + y^2 == 1".to_owned() }),
+    };
+    let lines = contour(ranges.0, ranges.1, count, |a, b| {
+        let bindings = [(x, Value::Float(a)), (y, Value::Float(b))];
+        let left = evaluator.expression_with(&bindings, left).ok()?.as_float()?;
+        let right = evaluator.expression_with(&bindings, right).ok()?.as_float()?;
+        Some(left - right)
+    });
+    Ok(Series { label: source[series.span().start..series.span().end].to_owned(), lines })
+}
+
+/// samples the height given by the series at the points of both ranges, a function of two arguments is called with
+/// both variables
+fn surface_series<'db>(
+    evaluator: &mut Evaluator<'db>,
+    source: &str,
+    series: &PlotSeries<'db>,
+    (x, y): (Span, Span),
+    ranges: ((f64, f64), (f64, f64)),
+    count: usize,
+    wireframe: bool,
+) -> Result<Surface, EvalError> {
+    if let Some(span) = series.polar {
+        return Err(EvalError { span, message: "`polar` curves have one variable, the angle".to_owned() });
+    }
+    let label = source[series.span().start..series.span().end].to_owned();
+    let bindings = |a: f64, b: f64| [(x, Value::Float(a)), (y, Value::Float(b))];
+    let function = match evaluator.expression_with(&bindings(ranges.0.0, ranges.1.0), &series.expression) {
+        Ok(value @ (Value::Function(_) | Value::Builtin(_))) => match evaluator.arity(&value) {
+            Some(2) => Some(value),
+            Some(arity) => return Err(EvalError { span: series.span(), message: format!("`{label}` takes {arity} arguments, a plot3d draws functions of two arguments") }),
+            None => None,
+        },
+        _ => None,
+    };
+    let span = series.span();
+    Ok(Surface::sample(label, ranges, count, wireframe, |a, b| match &function {
+        Some(function) => evaluator.call(function, &[Value::Float(a), Value::Float(b)], span).ok()?.as_float(),
+        None => evaluator.expression_with(&bindings(a, b), &series.expression).ok()?.as_float(),
+    }))
+}
+
+/// the smallest and largest x of the lines, empty when there are no points
+fn x_extent(lines: &[Polyline]) -> (f64, f64) {
+    lines.iter().flatten().fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), (x, _)| (min.min(*x), max.max(*x)))
+}
+
+/// evaluates both ends of the range, which have to be finite numbers with the start below the end
+fn bounds<'db>(evaluator: &mut Evaluator<'db>, range: &PlotRange<'db>) -> Result<(f64, f64), EvalError> {
+    let (min, max) = (number(evaluator, &range.start)?, number(evaluator, &range.end)?);
+    if !min.is_finite() || !max.is_finite() {
+        return Err(EvalError { span: range.span(), message: format!("the range {min}..{max} has to have finite ends") });
+    }
+    if min >= max {
+        return Err(EvalError { span: range.span(), message: format!("the range {min}..{max} is empty, the start has to be below the end") });
+    }
+    Ok((min, max))
+}
+
+/// evaluates the expression, which has to be a number
+fn number<'db>(evaluator: &mut Evaluator<'db>, expression: &Spanned<Expression<'db>>) -> Result<f64, EvalError> {
+    let value = evaluator.expression(expression)?;
+    value.as_float().ok_or_else(|| EvalError { span: expression.span(), message: format!("expected a number, found {value}") })
+}
+
+/// evaluates the expression, which has to be a number between the limits
+fn within<'db>(evaluator: &mut Evaluator<'db>, expression: &Spanned<Expression<'db>>, name: &str, (min, max): (f64, f64)) -> Result<f64, EvalError> {
+    match number(evaluator, expression)? {
+        x if (min..=max).contains(&x) => Ok(x),
+        other => Err(EvalError { span: expression.span(), message: format!("the {name} has to be between {min} and {max}, found {other}") }),
+    }
+}
+
+fn option_error(span: Span, message: &str) -> EvalError {
+    EvalError { span, message: message.to_owned() }
+}
+
+/// samples the function of one argument with the name, defined by the source or builtin
+///
+/// points where evaluating fails are left out, like points without a finite value
+pub fn sample_function(db: &dyn salsa::Database, source: ProgramSource, name: &str, range: (f64, f64), count: usize) -> Result<Vec<Polyline>, String> {
+    let mut evaluator = match evaluate_source(db, source) {
+        Ok((evaluator, _)) => evaluator,
+        Err(RunError::Invalid(diagnostics)) => return Err(format!("the definitions have errors: {}", diagnostics[0].message)),
+        Err(RunError::Failed(_, err)) => return Err(format!("evaluating the definitions failed: {}", err.message)),
+    };
+    let function = evaluator.global(name).ok_or_else(|| format!("cannot find `{name}`"))?;
+    match evaluator.arity(&function) {
+        Some(1) => {},
+        Some(arity) => return Err(format!("`{name}` takes {arity} arguments, only functions of one argument can be plotted")),
+        None => return Err(format!("`{name}` is not a function")),
+    }
+    Ok(sample(range.0, range.1, count, |x| {
+        evaluator.call(&function, &[Value::Float(x)], Span::new(0, 0)).ok()?.as_float()
+    }))
+}
+
+/// the smallest and largest y of the lines, widened when every point has the same y
+pub fn y_range(lines: &[Polyline]) -> (f64, f64) {
+    let (min, max) = lines.iter().flatten()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), (_, y)| (min.min(*y), max.max(*y)));
+    if min > max {
+        (-1.0, 1.0)
+    } else if min == max {
+        (min - 1.0, max + 1.0)
+    } else {
+        (min, max)
+    }
+}
+
+/// the ranges widened around their middles so that a unit is as long along x as along y on an area of the size
+pub fn equal_aspect(x_range: (f64, f64), y_range: (f64, f64), width: f64, height: f64) -> ((f64, f64), (f64, f64)) {
+    if width <= 0.0 || height <= 0.0 {
+        return (x_range, y_range);
+    }
+    // the units per pixel of the range which needs the most
+    let scale = ((x_range.1 - x_range.0) / width).max((y_range.1 - y_range.0) / height);
+    let widen = |(min, max): (f64, f64), size: f64| ((min + max - scale * size) / 2.0, (min + max + scale * size) / 2.0);
+    (widen(x_range, width), widen(y_range, height))
+}
+
+/// round values inside of the range, about `count` of them spaced by 1, 2 or 5 times a power of ten
+///
+/// ranges too wide for the step to be found, like ones with infinite ends, have no ticks
+pub fn ticks(min: f64, max: f64, count: usize) -> Vec<f64> {
+    let step = tick_step(min, max, count);
+    if !min.is_finite() || !max.is_finite() || !step.is_finite() {
+        return vec![];
+    }
+    let first = (min / step).ceil() as i64;
+    let last = (max / step).floor() as i64;
+    if last.saturating_sub(first) > 4 * count.max(1) as i64 {
+        return vec![];
+    }
+    (first..=last).map(|i| i as f64 * step).collect()
+}
+
+/// the distance between the ticks of the range
+pub fn tick_step(min: f64, max: f64, count: usize) -> f64 {
+    let rough = (max - min) / count.max(1) as f64;
+    let magnitude = 10f64.powf(rough.log10().floor());
+    // the candidate closest to the rough step when comparing ratios
+    let step = [1.0, 2.0, 5.0, 10.0].into_iter()
+        .map(|x| x * magnitude)
+        .min_by(|a, b| (a / rough).ln().abs().total_cmp(&(b / rough).ln().abs()))
+        .unwrap_or(magnitude);
+    if step > 0.0 && step.is_finite() { step } else { 1.0 }
+}
+
+/// the tick with as many decimals as the step needs, values of a million and more and ticks closer than a ten
+/// thousandth are written with an exponent like 2.5e7
+pub fn format_tick(value: f64, step: f64) -> String {
+    if value != 0.0 && value.is_finite() && (value.abs() >= 1e6 || step < 1e-4) {
+        let exponent = value.abs().log10().floor();
+        let decimals = (exponent - step.log10().floor()).clamp(0.0, 16.0) as usize;
+        let out = format!("{value:.decimals$e}");
+        let (mantissa, exponent) = out.split_once('e').expect("exponent notation has an e");
+        let mantissa = if mantissa.contains('.') { mantissa.trim_end_matches('0').trim_end_matches('.') } else { mantissa };
+        return format!("{mantissa}e{exponent}");
+    }
+    format_decimals(value, step)
+}
+
+/// the value with as many decimals as the step needs and without an exponent
+fn format_decimals(value: f64, step: f64) -> String {
+    let decimals = (-step.log10().floor()).max(0.0) as usize;
+    let out = format!("{value:.decimals$}");
+    // -0.0 is printed with its sign
+    if out.trim_start_matches('-').chars().all(|c| c == '0' || c == '.') { out.trim_start_matches('-').to_owned() } else { out }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges(){
+        assert_eq!(y_range(&[vec![(-2.0, -0.5), (-1.0, -1.0)], vec![(1.0, 1.0), (2.0, 0.5)]]), (-1.0, 1.0));
+        assert_eq!(y_range(&[vec![(0.0, 3.0)]]), (2.0, 4.0));
+        assert_eq!(y_range(&[]), (-1.0, 1.0));
+    }
+    #[test]
+    fn functions(){
+        let db = crate::GraphingDatabase::default();
+        let code = ProgramSource::new(&db, "fn inv(x) = 1 / x; fn add(a, b) = a + b; let c = 2;".to_owned());
+        assert_eq!(sample_function(&db, code, "inv", (-1.0, 1.0), 3).unwrap().len(), 2);
+        assert_eq!(sample_function(&db, code, "abs", (0.0, 4.0), 2), Ok(vec![vec![(0.0, 0.0), (4.0, 4.0)]]));
+        // a line for every step
+        assert_eq!(sample_function(&db, code, "floor", (0.0, 2.5), 3).unwrap().len(), 3);
+        assert!(sample_function(&db, code, "add", (0.0, 1.0), 2).unwrap_err().contains("takes 2 arguments"));
+        assert_eq!(sample_function(&db, code, "c", (0.0, 1.0), 2), Err("`c` is not a function".to_owned()));
+        assert_eq!(sample_function(&db, code, "d", (0.0, 1.0), 2), Err("cannot find `d`".to_owned()));
+    }
+    #[test]
+    fn plots(){
+        let db = crate::GraphingDatabase::default();
+        let code = ProgramSource::new(&db, "let a = 2; plot a * x, 1 / x for x in -1..a - 1 with title \"lines\", samples 3;".to_owned());
+        let (values, plots) = plot_source(&db, code).unwrap();
+        assert_eq!(values, vec![("a".to_owned(), Value::Int(2))]);
+        assert_eq!(plots[0].title.as_deref(), Some("lines"));
+        assert_eq!(plots[0].x_range, (-1.0, 1.0));
+        assert_eq!(plots[0].series[0], Series { label: "a * x".to_owned(), lines: vec![vec![(-1.0, -2.0), (0.0, 0.0), (1.0, 2.0)]] });
+        // broken at the division by zero
+        assert_eq!(plots[0].series[1].label, "1 / x");
+        assert_eq!(plots[0].series[1].lines.len(), 2);
+        let error = |src: &str| match plot_source(&db, ProgramSource::new(&db, src.to_owned())) {
+            Err(RunError::Failed(_, err)) => err.message,
+            other => panic!("{other:?}"),
+        };
+        assert_eq!(error("plot x for x in 1..0;"), "the range 1..0 is empty, the start has to be below the end");
+        assert_eq!(error("plot x for x in 0.0 / 0.0..1;"), "the range NaN..1 has to have finite ends");
+        assert_eq!(error("plot x for x in -1.0 / 0.0..1;"), "the range -inf..1 has to have finite ends");
+        assert_eq!(error("plot x for x in 0..1.0 / 0.0 with y 0..1;"), "the range 0..inf has to have finite ends");
+        assert_eq!(error("plot x for x in 0..1 with y 0..1.0 / 0.0;"), "the range 0..inf has to have finite ends");
+        assert_eq!(error("plot x for x in 0..1 with color 2;"), "unknown plot option `color`, expected title, samples, width, height, dpi, equal, wireframe, x, y, xscale, yscale, xlabel, ylabel, grid or format");
+        assert_eq!(error("plot x for x in 0..1 with equal 1;"), "`equal` takes no value, it is written as with equal");
+        assert_eq!(error("plot x for x in 0..1 with samples 1;"), "the number of samples has to be between 2 and 100000, found 1");
+        assert_eq!(error("plot x for x in 0..1 with samples 100000000000;"), "the number of samples has to be between 2 and 100000, found 100000000000");
+        assert_eq!(error("plot x for x in 0..1 with samples 2.5;"), "the number of samples has to be whole, found 2.5");
+        assert_eq!(error("plot x for x in 0..1 with dpi 1000;"), "the dpi has to be between 24 and 384, found 1000");
+        assert_eq!(error("plot x for x in 0..1 with width \"wide\";"), "`width` takes a number like width 200");
+        let (_, plots) = plot_source(&db, ProgramSource::new(&db, "plot x for x in 0..1 with width 300, dpi 192;".to_owned())).unwrap();
+        assert_eq!(plots[0].size, ImageSize { width: Some(300.0), height: None, dpi: Some(192.0) });
+        assert_eq!(plots[0].size.or(ImageSize { width: Some(100.0), height: Some(100.0), dpi: None }).resolve(), (300.0, 100.0, 192.0));
+    }
+    #[test]
+    fn curves(){
+        let db = crate::GraphingDatabase::default();
+        let code = ProgramSource::new(&db, "plot (cos(t), sin(t)), polar 2, t for t in 0..tau with equal, samples 9;".to_owned());
+        let (_, plots) = plot_source(&db, code).unwrap();
+        assert!(plots[0].equal_aspect);
+        let radius = |series: &Series| series.lines.iter().flatten().map(|(x, y)| x.hypot(*y)).collect::<Vec<_>>();
+        assert!(radius(&plots[0].series[0]).iter().all(|x| (x - 1.0).abs() < 1e-9));
+        assert!(radius(&plots[0].series[1]).iter().all(|x| (x - 2.0).abs() < 1e-9));
+        assert_eq!(plots[0].series[1].label, "polar 2");
+        // the range fits the curves and the function over the range of t
+        assert_eq!(plots[0].x_range.0, -2.0);
+        assert!((plots[0].x_range.1 - std::f64::consts::TAU).abs() < 1e-12);
+    }
+    #[test]
+    fn implicit(){
+        let db = crate::GraphingDatabase::default();
+        let code = ProgramSource::new(&db, "let r = 2; plot x^2 + y^2 == 1 for x in -3..3, y in -r..r with samples 5;".to_owned());
+        let (_, plots) = plot_source(&db, code).unwrap();
+        assert_eq!((plots[0].x_range, plots[0].y_range), ((-3.0, 3.0), Some((-2.0, 2.0))));
+        assert_eq!(plots[0].series[0].lines.len(), 1);
+        assert!(plots[0].series[0].lines[0].iter().all(|(x, y)| (x.hypot(*y) - 1.0).abs() < 0.05));
+        let error = |src: &str| match plot_source(&db, ProgramSource::new(&db, src.to_owned())) {
+            Err(RunError::Failed(_, err)) => err.message,
+            other => panic!("{other:?}"),
+        };
+        assert_eq!(error("plot x + y for x in 0..1, y in 0..1;"), "a plot of two variables draws equations like x^2 + y^2 == 1");
+        assert_eq!(error("plot polar x == y for x in 0..1, y in 0..1;"), "`polar` curves have one variable, the angle");
+        assert_eq!(error("plot x == y for x in 0..1, y in 1..1;"), "the range 1..1 is empty, the start has to be below the end");
+        assert_eq!(error("plot x == y for x in 0..1, y in 0..1.0 / 0.0;"), "the range 0..inf has to have finite ends");
+        assert_eq!(error("plot x == y for x in 0..1, y in 0..1 with samples 1000000;"), "the number of samples has to be between 2 and 100000, found 1000000");
+        assert_eq!(error("plot x == y for x in 0..1, y in 0..1 with samples 1000;"), "a plot of two variables samples a grid of at most 400 by 400 points, found 1000");
+    }
+    #[test]
+    fn surfaces(){
+        let db = crate::GraphingDatabase::default();
+        let code = ProgramSource::new(&db, "fn f(x, y) = x * y; plot3d f for x in 0..2, y in 0..1 with samples 3; plot3d a - b for a in 0..1, b in 0..1 with wireframe;".to_owned());
+        let (_, plots) = plot_source(&db, code).unwrap();
+        assert!(plots[0].series.is_empty());
+        assert_eq!((plots[0].x_range, plots[0].y_range), ((0.0, 2.0), Some((0.0, 1.0))));
+        let surface = plots[0].surface.as_ref().unwrap();
+        assert_eq!(surface.heights[2], vec![Some(0.0), Some(1.0), Some(2.0)]);
+        assert!(!surface.wireframe);
+        let surface = plots[1].surface.as_ref().unwrap();
+        assert_eq!((surface.label.as_str(), surface.heights.len(), surface.wireframe), ("a - b", DEFAULT_WIREFRAME, true));
+        assert_eq!(surface.heights[0][DEFAULT_WIREFRAME - 1], Some(1.0));
+        let error = |src: &str| match plot_source(&db, ProgramSource::new(&db, src.to_owned())) {
+            Err(RunError::Failed(_, err)) => err.message,
+            other => panic!("{other:?}"),
+        };
+        assert_eq!(error("plot3d x for x in 0..1;"), "a `plot3d` draws a height over two variables, like plot3d f for x in -2..2, y in -2..2");
+        assert_eq!(error("plot3d x, y for x in 0..1, y in 0..1;"), "a `plot3d` draws a single surface");
+        assert_eq!(error("fn g(x) = x; plot3d g for x in 0..1, y in 0..1;"), "`g` takes 1 arguments, a plot3d draws functions of two arguments");
+        assert_eq!(error("plot x == y for x in 0..1, y in 0..1 with wireframe;"), "`wireframe` only applies to plot3d");
+        assert_eq!(error("plot3d x * y for x in 0..1, y in 0..1 with samples 1000;"), "a `plot3d` samples a grid of at most 200 by 200 points, found 1000");
+    }
+    #[test]
+    fn axes(){
+        let db = crate::GraphingDatabase::default();
+        let code = ProgramSource::new(&db, "plot x for x in 1..1000 with xscale \"log\", samples 4, xlabel \"f\", grid \"off\", format \"si\"; plot x for x in 0..10 with x 2..4, y -1..1, grid;".to_owned());
+        let (_, plots) = plot_source(&db, code).unwrap();
+        // evenly spaced along the log axis, with more points where the line bends on it
+        let xs = plots[0].series[0].lines[0].iter().map(|(x, _)| x.round()).collect::<Vec<_>>();
+        assert!([1.0, 10.0, 100.0, 1000.0].iter().all(|x| xs.contains(x)));
+        assert_eq!(plots[0].axes, Axes { x_scale: Some(Scale::Log), x_label: Some("f".to_owned()), grid: Some(false), format: Some(NumberFormat::Si), ..Default::default() });
+        assert_eq!((plots[1].x_range, plots[1].y_range, plots[1].axes.grid), ((2.0, 4.0), Some((-1.0, 1.0)), Some(true)));
+        assert_eq!(plots[1].series[0].lines[0][0], (2.0, 2.0));
+        // the options of the plot win over the defaults
+        let plot = plots[0].clone().with_axes(&Axes { x_scale: Some(Scale::Linear), y_scale: Some(Scale::Log), ..Default::default() });
+        assert_eq!(plot.axes.scales(), (Scale::Log, Scale::Log));
+        let error = |src: &str| match plot_source(&db, ProgramSource::new(&db, src.to_owned())) {
+            Err(RunError::Failed(_, err)) => err.message,
+            other => panic!("{other:?}"),
+        };
+        assert_eq!(error("plot x for x in 0..1 with yscale \"ln\";"), "`yscale` is \"linear\" or \"log\", found \"ln\"");
+        assert_eq!(error("plot x for x in 0..1 with x -1..1, xscale \"log\";"), "a log scale only shows positive numbers, the range -1..1 is not");
+        assert_eq!(error("plot x for x in 0..1 with grid 1;"), "`grid` takes \"on\" or \"off\", it is on when written without a value");
+        assert_eq!(error("plot x for x in 0..1 with format \"hex\";"), "`format` is \"plain\" or \"si\", found \"hex\"");
+        assert_eq!(error("plot x for x in 0..1 with xlabel;"), "`xlabel` takes a string like xlabel \"time\"");
+        assert_eq!(error("plot3d x * y for x in 1..2, y in 1..2 with yscale \"log\";"), "plot3d is always drawn on linear scales");
+    }
+    #[test]
+    fn aspect(){
+        assert_eq!(equal_aspect((0.0, 2.0), (0.0, 1.0), 100.0, 100.0), ((0.0, 2.0), (-0.5, 1.5)));
+        assert_eq!(equal_aspect((0.0, 1.0), (0.0, 1.0), 200.0, 100.0), ((-0.5, 1.5), (0.0, 1.0)));
+    }
+    #[test]
+    fn nice_ticks(){
+        assert_eq!(ticks(-10.0, 10.0, 4), vec![-10.0, -5.0, 0.0, 5.0, 10.0]);
+        assert_eq!(ticks(0.13, 0.87, 3), vec![0.2, 0.4, 0.6000000000000001, 0.8]);
+        // the width of the range overflows
+        assert!(ticks(-1.0e308 * 2.0, 1.0e308 * 2.0, 4).is_empty());
+        assert!(ticks(-1.0e308, 1.0e308, 4).is_empty());
+        assert!(ticks(f64::NEG_INFINITY, 1.0, 4).is_empty());
+        assert_eq!(ticks(0.0, 1.0, 10), ticks(0.0, 1.0, 9));
+        assert_eq!(format_tick(0.6000000000000001, 0.2), "0.6");
+        assert_eq!(format_tick(-0.0, 1.0), "0");
+        assert_eq!(format_tick(2500.0, 500.0), "2500");
+        assert_eq!(format_tick(5.0e129, 1.0e129), "5e129");
+        assert_eq!(format_tick(2.0 * 1.0e129 + 1.0e129, 1.0e129), "3e129");
+        assert_eq!(format_tick(-2.5e7, 5.0e6), "-2.5e7");
+        assert_eq!(format_tick(0.00005, 0.00001), "5e-5");
+        assert_eq!(format_tick(1.00005, 0.00005), "1.00005e0");
+        assert_eq!(format_tick(0.0, 1.0e129), "0");
+    }
+}
@@ -0,0 +1,394 @@
+//! draws plots into pixels on the cpu and encodes them as png images
+
+use super::{font::{self, ADVANCE, GLYPH_HEIGHT, GLYPH_WIDTH}, layout::{Layout, LEFT, TOP}, surface::{inside, level, scale_color}, SampledPlot};
+
+pub type Rgb = [u8; 3];
+
+/// the colours of the series, the same as in svg images
+const COLORS: [Rgb; 6] = [[0x1f, 0x77, 0xb4], [0xff, 0x7f, 0x0e], [0x2c, 0xa0, 0x2c], [0xd6, 0x27, 0x28], [0x94, 0x67, 0xbd], [0x8c, 0x56, 0x4b]];
+const WHITE: Rgb = [0xff, 0xff, 0xff];
+const BLACK: Rgb = [0x00, 0x00, 0x00];
+const GRID: Rgb = [0xe0, 0xe0, 0xe0];
+/// black at 30% opacity on white, the border of the legend
+const LEGEND_BORDER: Rgb = [0xb3, 0xb3, 0xb3];
+
+/// a rectangle of pixel coordinates, left, top, right and bottom
+type Rect = (f64, f64, f64, f64);
+
+/// an image with a colour for every pixel, pixel (x, y) covers the square from (x, y) to (x + 1, y + 1)
+pub struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<Rgb>,
+    /// pixels with their centre outside of the rectangle are not drawn
+    clip: Option<Rect>,
+}
+impl Canvas {
+    pub fn new(width: usize, height: usize, background: Rgb) -> Self {
+        Self { width, height, pixels: vec![background; width * height], clip: None }
+    }
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    pub fn height(&self) -> usize {
+        self.height
+    }
+    pub fn get(&self, x: usize, y: usize) -> Rgb {
+        self.pixels[y * self.width + x]
+    }
+    /// limits drawing to the rectangle, None draws on the whole image
+    pub fn set_clip(&mut self, clip: Option<Rect>) {
+        self.clip = clip;
+    }
+    /// mixes the colour into the pixel, a coverage of 1 replaces it
+    fn blend(&mut self, x: usize, y: usize, color: Rgb, coverage: f64) {
+        let (cx, cy) = (x as f64 + 0.5, y as f64 + 0.5);
+        if self.clip.is_some_and(|(left, top, right, bottom)| cx < left || cx > right || cy < top || cy > bottom) {
+            return;
+        }
+        let pixel = &mut self.pixels[y * self.width + x];
+        for (channel, target) in pixel.iter_mut().zip(color) {
+            *channel = (*channel as f64 + (target as f64 - *channel as f64) * coverage).round() as u8;
+        }
+    }
+    /// the pixels inside of the image and the rectangle, as columns and rows
+    fn pixels_in(&self, (left, top, right, bottom): Rect) -> (std::ops::Range<usize>, std::ops::Range<usize>) {
+        let clamp = |value: f64, max: usize| (value.max(0.0) as usize).min(max);
+        (clamp(left.floor(), self.width)..clamp(right.ceil(), self.width), clamp(top.floor(), self.height)..clamp(bottom.ceil(), self.height))
+    }
+    /// fills the pixels with their centre inside of the rectangle
+    pub fn fill(&mut self, (left, top, right, bottom): Rect, color: Rgb) {
+        let (columns, rows) = self.pixels_in(((left - 0.5).ceil(), (top - 0.5).ceil(), (right - 0.5).ceil(), (bottom - 0.5).ceil()));
+        for y in rows {
+            for x in columns.clone() {
+                self.blend(x, y, color, 1.0);
+            }
+        }
+    }
+    /// fills the pixels with their centre inside of the polygon, without anti aliasing so neighbouring polygons leave no
+    /// gaps between them
+    pub fn polygon(&mut self, points: &[(f64, f64)], color: Rgb) {
+        let (left, top, right, bottom) = points.iter()
+            .fold((f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY), |(a, b, c, d), (x, y)| (a.min(*x), b.min(*y), c.max(*x), d.max(*y)));
+        let (columns, rows) = self.pixels_in((left, top, right, bottom));
+        for y in rows {
+            for x in columns.clone() {
+                if inside((x as f64 + 0.5, y as f64 + 0.5), points) {
+                    self.blend(x, y, color, 1.0);
+                }
+            }
+        }
+    }
+    /// draws lines with round ends and joins between the points, anti aliased by the distance of every pixel to the line
+    ///
+    /// a single point is drawn as a dot with the width as its diameter
+    pub fn polyline(&mut self, points: &[(f64, f64)], width: f64, color: Rgb) {
+        let Some(first) = points.first() else { return };
+        let radius = width / 2.0;
+        let (min_x, min_y, max_x, max_y) = points.iter()
+            .fold((first.0, first.1, first.0, first.1), |(a, b, c, d), (x, y)| (a.min(*x), b.min(*y), c.max(*x), d.max(*y)));
+        let (columns, rows) = self.pixels_in((min_x - radius - 1.0, min_y - radius - 1.0, max_x + radius + 1.0, max_y + radius + 1.0));
+        if columns.is_empty() || rows.is_empty() {
+            return;
+        }
+        // the coverage of every pixel is the largest of all segments so joins are not drawn twice
+        let mut coverage = vec![0.0f64; columns.len() * rows.len()];
+        let segments = points.windows(2).map(|x| (x[0], x[1])).chain((points.len() == 1).then_some((*first, *first)));
+        for (a, b) in segments {
+            let (xs, ys) = self.pixels_in((a.0.min(b.0) - radius - 1.0, a.1.min(b.1) - radius - 1.0, a.0.max(b.0) + radius + 1.0, a.1.max(b.1) + radius + 1.0));
+            for y in ys {
+                for x in xs.clone() {
+                    let distance = segment_distance((x as f64 + 0.5, y as f64 + 0.5), a, b);
+                    let covered = (radius + 0.5 - distance).clamp(0.0, 1.0);
+                    let cell = &mut coverage[(y - rows.start) * columns.len() + x - columns.start];
+                    *cell = cell.max(covered);
+                }
+            }
+        }
+        for y in rows.clone() {
+            for x in columns.clone() {
+                let covered = coverage[(y - rows.start) * columns.len() + x - columns.start];
+                if covered > 0.0 {
+                    self.blend(x, y, color, covered);
+                }
+            }
+        }
+    }
+    /// draws the text in the embedded font with its top left corner at the position, `scale` pixels for every font pixel
+    pub fn text(&mut self, (x, y): (f64, f64), text: &str, scale: usize, color: Rgb) {
+        let size = scale as f64;
+        for (i, c) in text.chars().enumerate() {
+            let glyph = font::glyph(c);
+            for row in 0..GLYPH_HEIGHT {
+                for column in (0..GLYPH_WIDTH).filter(|column| font::pixel(glyph, *column, row)) {
+                    let left = (x + ((i * ADVANCE + column) * scale) as f64).round();
+                    let top = (y + (row * scale) as f64).round();
+                    self.fill((left, top, left + size, top + size), color);
+                }
+            }
+        }
+    }
+    /// draws the text like [`Self::text`] turned a quarter anticlockwise, so it is read from the bottom up, with the top
+    /// left corner of the turned text at the position
+    pub fn vertical_text(&mut self, (x, y): (f64, f64), text: &str, scale: usize, color: Rgb) {
+        let (size, height) = (scale as f64, (font::text_width(text) * scale) as f64);
+        for (i, c) in text.chars().enumerate() {
+            let glyph = font::glyph(c);
+            for row in 0..GLYPH_HEIGHT {
+                for column in (0..GLYPH_WIDTH).filter(|column| font::pixel(glyph, *column, row)) {
+                    let left = (x + (row * scale) as f64).round();
+                    let top = (y + height - ((i * ADVANCE + column + 1) * scale) as f64).round();
+                    self.fill((left, top, left + size, top + size), color);
+                }
+            }
+        }
+    }
+    /// the image as a png file which states the resolution in dots per inch
+    pub fn encode_png(&self, dpi: f64) -> Vec<u8> {
+        let mut out = vec![];
+        let mut encoder = png::Encoder::new(&mut out, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let per_meter = (dpi / 0.0254).round() as u32;
+        encoder.set_pixel_dims(Some(png::PixelDimensions { xppu: per_meter, yppu: per_meter, unit: png::Unit::Meter }));
+        let data = self.pixels.iter().flatten().copied().collect::<Vec<_>>();
+        let mut writer = encoder.write_header().expect("the size of the image is valid");
+        writer.write_image_data(&data).expect("writing to memory does not fail");
+        writer.finish().expect("writing to memory does not fail");
+        out
+    }
+}
+
+/// the distance of the point to the line segment from a to b
+fn segment_distance(point: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length = dx * dx + dy * dy;
+    let t = if length == 0.0 { 0.0 } else { (((point.0 - a.0) * dx + (point.1 - a.1) * dy) / length).clamp(0.0, 1.0) };
+    (point.0 - a.0 - t * dx).hypot(point.1 - a.1 - t * dy)
+}
+
+/// the plot as a png image of the size in pixels at 96 dpi, with dpi / 96 times as many pixels in each direction
+///
+/// it has the same parts as the svg image, the labels use the embedded font
+pub fn render(plot: &SampledPlot, width: f64, height: f64, dpi: f64) -> Vec<u8> {
+    draw(plot, width, height, dpi).encode_png(dpi)
+}
+
+/// draws the plot like [`render`] without encoding it
+pub fn draw(plot: &SampledPlot, width: f64, height: f64, dpi: f64) -> Canvas {
+    let scale = dpi / 96.0;
+    let (pixel_width, pixel_height) = ((width * scale).round().max(1.0), (height * scale).round().max(1.0));
+    let layout = Layout::new(plot, pixel_width, pixel_height, scale);
+    let Layout { left, right, top, bottom, .. } = layout;
+    // the 7 pixel high glyphs are about the size of 12 pixel text
+    let text_scale = (scale.round() as usize).max(1);
+    let thin = scale.round().max(1.0);
+    // thin lines are centred on pixels so they stay sharp
+    let snap = |x: f64| if thin as usize % 2 == 1 { x.floor() + 0.5 } else { x.round() };
+    let label = |text: &str, scale: usize| (font::text_width(text) * scale) as f64;
+    let mut canvas = Canvas::new(pixel_width as usize, pixel_height as usize, WHITE);
+
+    if let Some(title) = &plot.title {
+        let baseline = (TOP + 16.0) * scale;
+        canvas.text((pixel_width / 2.0 - label(title, text_scale * 2) / 2.0, baseline - (GLYPH_HEIGHT * text_scale * 2) as f64), title, text_scale * 2, BLACK);
+    }
+
+    match &plot.surface {
+        Some(surface) if surface.wireframe => {
+            for face in layout.wireframe(surface) {
+                canvas.polygon(&face.corners, WHITE);
+                let outline = face.corners.iter().chain(&face.corners[..1]).copied().collect::<Vec<_>>();
+                canvas.polyline(&outline, thin, scale_color(face.level));
+            }
+        },
+        Some(surface) => {
+            // the height at the centre of every pixel of the plot area
+            let z_range = surface.z_range();
+            let ((x_min, x_max), (y_min, y_max)) = (layout.x_range, layout.y_range);
+            let (columns, rows) = canvas.pixels_in((left, top, right, bottom));
+            for row in rows {
+                let y = y_max - (row as f64 + 0.5 - top) / (bottom - top) * (y_max - y_min);
+                for column in columns.clone() {
+                    let x = x_min + (column as f64 + 0.5 - left) / (right - left) * (x_max - x_min);
+                    if let Some(z) = surface.at(x, y) {
+                        canvas.blend(column, row, scale_color(level(z, z_range)), 1.0);
+                    }
+                }
+            }
+        },
+        None if layout.grid => {
+            for x in &layout.x_ticks {
+                canvas.polyline(&[(snap(layout.x(x.position)), top), (snap(layout.x(x.position)), bottom)], thin, GRID);
+            }
+            for y in &layout.y_ticks {
+                canvas.polyline(&[(left, snap(layout.y(y.position))), (right, snap(layout.y(y.position)))], thin, GRID);
+            }
+        },
+        None => {},
+    }
+    let glyph_height = (GLYPH_HEIGHT * text_scale) as f64;
+    if let Some(color_scale) = &layout.color_scale {
+        let (columns, rows) = canvas.pixels_in((color_scale.left, color_scale.top, color_scale.right, color_scale.bottom));
+        for row in rows {
+            let level = (color_scale.bottom - row as f64 - 0.5) / (color_scale.bottom - color_scale.top);
+            for column in columns.clone() {
+                canvas.blend(column, row, scale_color(level), 1.0);
+            }
+        }
+        let (bar_left, bar_top, bar_right, bar_bottom) = (snap(color_scale.left), snap(color_scale.top), snap(color_scale.right), snap(color_scale.bottom));
+        canvas.polyline(&[(bar_left, bar_top), (bar_right, bar_top), (bar_right, bar_bottom), (bar_left, bar_bottom), (bar_left, bar_top)], thin, BLACK);
+        for z in &color_scale.ticks {
+            let position = snap(color_scale.y(z.position));
+            canvas.polyline(&[(color_scale.right, position), (color_scale.right + 4.0 * scale, position)], thin, BLACK);
+            canvas.text((color_scale.right + 8.0 * scale, position - glyph_height / 2.0), &z.label, text_scale, BLACK);
+        }
+    }
+    // a wireframe has no axes, its x and y are not along the edges of the image
+    if !plot.surface.as_ref().is_some_and(|x| x.wireframe) {
+        let (frame_left, frame_top, frame_right, frame_bottom) = (snap(left), snap(top), snap(right), snap(bottom));
+        canvas.polyline(&[(frame_left, frame_top), (frame_right, frame_top), (frame_right, frame_bottom), (frame_left, frame_bottom), (frame_left, frame_top)], thin, BLACK);
+        for x in &layout.x_ticks {
+            let position = snap(layout.x(x.position));
+            canvas.polyline(&[(position, bottom), (position, bottom + 4.0 * scale)], thin, BLACK);
+            canvas.text((position - label(&x.label, text_scale) / 2.0, bottom + 8.0 * scale), &x.label, text_scale, BLACK);
+        }
+        for y in &layout.y_ticks {
+            let position = snap(layout.y(y.position));
+            canvas.polyline(&[(left - 4.0 * scale, position), (left, position)], thin, BLACK);
+            canvas.text((left - 8.0 * scale - label(&y.label, text_scale), position - glyph_height / 2.0), &y.label, text_scale, BLACK);
+        }
+        if let Some(text) = &plot.axes.x_label {
+            canvas.text(((left + right) / 2.0 - label(text, text_scale) / 2.0, bottom + 28.0 * scale), text, text_scale, BLACK);
+        }
+        if let Some(text) = &plot.axes.y_label {
+            canvas.vertical_text((left - (LEFT + 6.0) * scale - glyph_height, (top + bottom) / 2.0 - label(text, text_scale) / 2.0), text, text_scale, BLACK);
+        }
+    }
+
+    canvas.set_clip(Some((left, top, right, bottom)));
+    for (i, lines) in layout.lines.iter().enumerate() {
+        let color = COLORS[i % COLORS.len()];
+        for line in lines {
+            let points = line.iter().map(|(x, y)| (layout.x(*x), layout.y(*y))).collect::<Vec<_>>();
+            let width = if points.len() == 1 { 4.0 } else { 2.0 };
+            canvas.polyline(&points, width * scale, color);
+        }
+    }
+    canvas.set_clip(None);
+
+    if plot.series.len() > 1 {
+        let longest = plot.series.iter().map(|x| label(&x.label, text_scale)).fold(0.0, f64::max);
+        let (legend_width, legend_height) = (36.0 * scale + longest, (8.0 + plot.series.len() as f64 * 18.0) * scale);
+        let (x, y) = (snap(right - legend_width - 8.0 * scale), snap(top + 8.0 * scale));
+        canvas.fill((x, y, x + legend_width, y + legend_height), WHITE);
+        canvas.polyline(&[(x, y), (x + legend_width, y), (x + legend_width, y + legend_height), (x, y + legend_height), (x, y)], thin, LEGEND_BORDER);
+        for (i, series) in plot.series.iter().enumerate() {
+            let row = y + (12.0 + i as f64 * 18.0) * scale;
+            canvas.polyline(&[(x + 6.0 * scale, row), (x + 26.0 * scale, row)], 2.0 * scale, COLORS[i % COLORS.len()]);
+            canvas.text((x + 30.0 * scale, row - glyph_height / 2.0), &series.label, text_scale, BLACK);
+        }
+    }
+    canvas
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::plot::{surface::Surface, Series};
+
+    use super::*;
+
+    #[test]
+    fn lines(){
+        let mut canvas = Canvas::new(8, 4, WHITE);
+        // centred on the pixels of row 1
+        canvas.polyline(&[(0.0, 1.5), (8.0, 1.5)], 1.0, BLACK);
+        assert_eq!((0..4).map(|y| canvas.get(3, y)).collect::<Vec<_>>(), [WHITE, BLACK, WHITE, WHITE]);
+        // on the edge between rows 2 and 3, half of both is covered
+        canvas.polyline(&[(0.0, 3.0), (8.0, 3.0)], 1.0, BLACK);
+        assert_eq!(canvas.get(3, 2), [0x80; 3]);
+        assert_eq!(canvas.get(3, 3), [0x80; 3]);
+        let mut canvas = Canvas::new(8, 8, WHITE);
+        canvas.set_clip(Some((0.0, 0.0, 4.0, 8.0)));
+        canvas.polyline(&[(0.0, 0.0), (8.0, 8.0)], 2.0, BLACK);
+        assert_eq!(canvas.get(2, 2), BLACK);
+        assert_eq!(canvas.get(6, 6), WHITE);
+        assert!(canvas.get(2, 4)[0] > 0 && canvas.get(2, 4)[0] < 0xff);
+    }
+    #[test]
+    fn text(){
+        let mut canvas = Canvas::new(12, 16, WHITE);
+        canvas.text((0.0, 1.0), "-", 2, BLACK);
+        let row = |y| (0..12).map(|x| if canvas.get(x, y) == BLACK { '#' } else { '.' }).collect::<String>();
+        assert_eq!(row(6), "............");
+        assert_eq!(row(7), "##########..");
+        assert_eq!(row(8), "##########..");
+        // turned, the dash is a bar from the top down
+        let mut canvas = Canvas::new(12, 16, WHITE);
+        canvas.vertical_text((0.0, 1.0), "-", 2, BLACK);
+        let column = |x| (0..16).map(|y| if canvas.get(x, y) == BLACK { '#' } else { '.' }).collect::<String>();
+        assert_eq!(column(5), "................");
+        assert_eq!(column(6), ".##########.....");
+        assert_eq!(column(7), ".##########.....");
+    }
+    #[test]
+    fn surfaces(){
+        let surface = Surface::sample("x".to_owned(), ((0.0, 1.0), (0.0, 1.0)), 3, false, |x, _| Some(x));
+        let mut plot = SampledPlot {
+            title: None,
+            x_range: (0.0, 1.0),
+            y_range: Some((0.0, 1.0)),
+            series: vec![],
+            output: None,
+            size: Default::default(),
+            equal_aspect: false,
+            surface: Some(surface),
+            axes: Default::default(),
+        };
+        let layout = Layout::new(&plot, 320.0, 200.0, 1.0);
+        let (middle, bar) = (((layout.top + layout.bottom) / 2.0) as usize, layout.color_scale.as_ref().unwrap());
+        let canvas = draw(&plot, 320.0, 200.0, 96.0);
+        // low on the left in purple, high on the right in yellow, like the top of the colour scale
+        let (low, high) = (canvas.get(layout.left as usize + 1, middle), canvas.get(layout.right as usize - 2, middle));
+        assert!(low[0] < 0x50 && low[2] > 0x50 && high[0] > 0xf0 && high[2] < 0x40);
+        assert!(canvas.get(((bar.left + bar.right) / 2.0) as usize, bar.top as usize + 2)[0] > 0xf0);
+        plot.surface.as_mut().unwrap().wireframe = true;
+        let canvas = draw(&plot, 320.0, 200.0, 96.0);
+        // the outlines of the cells in the colours of the scale without a frame around them
+        let colored = (layout.top as usize..layout.bottom as usize)
+            .flat_map(|y| (layout.left as usize..layout.right as usize).map(move |x| (x, y)))
+            .filter(|(x, y)| canvas.get(*x, *y)[0] != canvas.get(*x, *y)[1])
+            .count();
+        assert!(colored > 100);
+        assert_eq!(canvas.get(layout.left as usize, layout.top as usize), WHITE);
+    }
+    #[test]
+    fn png(){
+        let plot = SampledPlot {
+            title: Some("line".to_owned()),
+            x_range: (0.0, 1.0),
+            y_range: None,
+            series: vec![
+                Series { label: "x".to_owned(), lines: vec![vec![(0.0, 0.0), (1.0, 1.0)]] },
+                Series { label: "1 - x".to_owned(), lines: vec![vec![(0.0, 1.0), (1.0, 0.0)]] },
+            ],
+            output: None,
+            size: Default::default(),
+            equal_aspect: false,
+            surface: None,
+            axes: Default::default(),
+        };
+        let canvas = draw(&plot, 320.0, 200.0, 192.0);
+        assert_eq!((canvas.width(), canvas.height()), (640, 400));
+        assert_eq!(canvas.get(0, 0), WHITE);
+        assert!(canvas.pixels.contains(&COLORS[0]) && canvas.pixels.contains(&COLORS[1]));
+
+        let mut decoder = png::Decoder::new(std::io::Cursor::new(render(&plot, 320.0, 200.0, 192.0))).read_info().unwrap();
+        let info = decoder.info();
+        assert_eq!((info.width, info.height), (640, 400));
+        let dims = info.pixel_dims.unwrap();
+        assert_eq!((dims.xppu, dims.yppu, dims.unit), (7559, 7559, png::Unit::Meter));
+        let mut data = vec![0; decoder.output_buffer_size().unwrap()];
+        decoder.next_frame(&mut data).unwrap();
+        assert_eq!(data, canvas.pixels.concat());
+    }
+}
@@ -0,0 +1,235 @@
+//! samples functions and curves of one argument into polylines, shared by every renderer
+//!
+//! the range is first sampled at evenly spaced points, then every interval is halved where the curve bends or
+//! stops having a value. lines are broken where the curve has no finite point and at jumps, so asymptotes like
+//! the one of tan(x) are not connected across. lines are cut where they leave the box around the evenly spaced
+//! samples, which keeps the range of graphs with asymptotes useful
+
+use std::cmp::Ordering;
+
+use super::Polyline;
+
+/// the number of times an interval between two evenly spaced samples is halved at most
+pub const MAX_DEPTH: usize = 10;
+/// how far the middle of an interval may be from the straight line between its ends before it is halved, relative to
+/// the size of the curve along each axis
+const TOLERANCE: f64 = 1e-3;
+/// the change over an interval which is still bent after halving it [`MAX_DEPTH`] times above which it is a jump,
+/// relative to the size of the curve along each axis
+const JUMP: f64 = 0.05;
+/// how far the box around the evenly spaced samples reaches past them, relative to their size
+const MARGIN: f64 = 1.0;
+
+/// the argument of a curve and its point, None where it has no finite point
+type Sample = (f64, Option<(f64, f64)>);
+
+/// evaluates the function at `count` evenly spaced points of the range and where it needs more points between them
+///
+/// the line is broken where the function has no finite value, like where it fails to evaluate, and where it jumps
+pub fn sample(min: f64, max: f64, count: usize, mut f: impl FnMut(f64) -> Option<f64>) -> Vec<Polyline> {
+    sample_curve(min, max, count, |x| Some((x, f(x)?)))
+}
+
+/// samples a curve with a point for every argument like [`sample`], for parametric and polar curves
+pub fn sample_curve(min: f64, max: f64, count: usize, f: impl FnMut(f64) -> Option<(f64, f64)>) -> Vec<Polyline> {
+    let mut sampler = Sampler { f, tolerance: (0.0, 0.0), jump: (0.0, 0.0), bounds: [(0.0, 0.0); 2], lines: vec![], line: vec![] };
+    let samples = (0..count)
+        .map(|i| if count == 1 { min } else { min + (max - min) * i as f64 / (count - 1) as f64 })
+        .map(|t| (t, sampler.eval(t)))
+        .collect::<Vec<_>>();
+    let extent = |coordinate: fn((f64, f64)) -> f64| {
+        let (low, high) = samples.iter()
+            .filter_map(|(_, point)| point.map(coordinate))
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), x| (low.min(x), high.max(x)));
+        // a constant still has a size once it is drawn
+        let size = if high > low { high - low } else { high.abs().max(1.0) };
+        ((low - MARGIN * size, high + MARGIN * size), size)
+    };
+    let (x_bounds, width) = extent(|(x, _)| x);
+    let (y_bounds, height) = extent(|(_, y)| y);
+    sampler.tolerance = (TOLERANCE * width, TOLERANCE * height);
+    sampler.jump = (JUMP * width, JUMP * height);
+    sampler.bounds = [x_bounds, y_bounds];
+    for (i, sample) in samples.iter().enumerate() {
+        if i > 0 {
+            sampler.interval(samples[i - 1], *sample, MAX_DEPTH);
+        }
+        sampler.point(sample.1);
+    }
+    sampler.split();
+    let bounds = sampler.bounds;
+    sampler.lines.into_iter().flat_map(|x| clip(&x, bounds)).collect()
+}
+
+/// the parts of the line inside of the box with the lowest and highest x and y, cut where the line crosses its edges
+pub fn clip(line: &Polyline, [(x_low, x_high), (y_low, y_high)]: [(f64, f64); 2]) -> Vec<Polyline> {
+    let inside = |(x, y): (f64, f64)| (x_low..=x_high).contains(&x) && (y_low..=y_high).contains(&y);
+    let mut out = vec![];
+    let mut current = vec![];
+    for (i, &point) in line.iter().enumerate() {
+        if let Some(&previous) = i.checked_sub(1).map(|i| &line[i]) {
+            let (dx, dy) = (point.0 - previous.0, point.1 - previous.1);
+            let at = |t: f64| (previous.0 + t * dx, previous.1 + t * dy);
+            // the fractions of the segment where it enters and leaves the box, after liang and barsky
+            let part = [(-dx, previous.0 - x_low), (dx, x_high - previous.0), (-dy, previous.1 - y_low), (dy, y_high - previous.1)]
+                .into_iter()
+                .try_fold((0.0f64, 1.0f64), |(enter, leave), (p, q)| {
+                    let (enter, leave) = if p == 0.0 {
+                        return (q >= 0.0).then_some((enter, leave));
+                    } else if p < 0.0 {
+                        (enter.max(q / p), leave)
+                    } else {
+                        (enter, leave.min(q / p))
+                    };
+                    (enter <= leave).then_some((enter, leave))
+                });
+            match (inside(previous), inside(point), part) {
+                (true, false, Some((_, leave))) => {
+                    current.push(at(leave));
+                    out.push(std::mem::take(&mut current));
+                },
+                (false, true, Some((enter, _))) => current.push(at(enter)),
+                // the segment passes through the box without an end inside
+                (false, false, Some((enter, leave))) if enter < leave => out.push(vec![at(enter), at(leave)]),
+                _ => {},
+            }
+        }
+        if inside(point) {
+            current.push(point);
+        }
+    }
+    if !current.is_empty() {
+        out.push(current);
+    }
+    out
+}
+
+struct Sampler<F> {
+    f: F,
+    tolerance: (f64, f64),
+    jump: (f64, f64),
+    /// the lowest and highest x and y worth following the line to
+    bounds: [(f64, f64); 2],
+    lines: Vec<Polyline>,
+    line: Polyline,
+}
+impl<F: FnMut(f64) -> Option<(f64, f64)>> Sampler<F> {
+    fn eval(&mut self, t: f64) -> Option<(f64, f64)> {
+        (self.f)(t).filter(|(x, y)| x.is_finite() && y.is_finite())
+    }
+    fn point(&mut self, point: Option<(f64, f64)>) {
+        match point {
+            Some(point) => self.line.push(point),
+            None => self.split(),
+        }
+    }
+    /// ends the current line
+    fn split(&mut self) {
+        if !self.line.is_empty() {
+            self.lines.push(std::mem::take(&mut self.line));
+        }
+    }
+    /// which side of the bounds the point is on along each axis
+    fn sides(&self, (x, y): (f64, f64)) -> [Ordering; 2] {
+        let side = |x: f64, (low, high): (f64, f64)| if x < low { Ordering::Less } else if x > high { Ordering::Greater } else { Ordering::Equal };
+        [side(x, self.bounds[0]), side(y, self.bounds[1])]
+    }
+    /// adds the points which are needed strictly between both ends
+    fn interval(&mut self, (a, pa): Sample, (b, pb): Sample, depth: usize) {
+        match (pa.map(|x| self.sides(x)), pb.map(|x| self.sides(x))) {
+            // the line leaves the bounds on one side and comes back on the other, like at the asymptote of tan(x)
+            (Some(sa), Some(sb)) if sa.iter().zip(sb).any(|(a, b)| a.is_ne() && b.is_ne() && *a != b) => return self.split(),
+            // the line is outside of the bounds
+            (Some(sa), Some(sb)) if sa.iter().zip(sb).any(|(a, b)| a.is_ne() && *a == b) => return,
+            // the line leaves the bounds before it stops having a value
+            (Some(sides), None) | (None, Some(sides)) if sides.iter().any(|x| x.is_ne()) => return,
+            _ => {},
+        }
+        if depth == 0 {
+            if let (Some(pa), Some(pb)) = (pa, pb) && ((pb.0 - pa.0).abs() > self.jump.0 || (pb.1 - pa.1).abs() > self.jump.1) {
+                self.split();
+            }
+            return;
+        }
+        let middle = (a + b) / 2.0;
+        let pm = self.eval(middle);
+        let halve = match (pa, pm, pb) {
+            (Some(pa), Some(pm), Some(pb)) =>
+                (pm.0 - (pa.0 + pb.0) / 2.0).abs() > self.tolerance.0 || (pm.1 - (pa.1 + pb.1) / 2.0).abs() > self.tolerance.1,
+            (None, None, None) => false,
+            // look for where the curve stops having a point
+            _ => true,
+        };
+        if halve {
+            self.interval((a, pa), (middle, pm), depth - 1);
+            self.point(pm);
+            self.interval((middle, pm), (b, pb), depth - 1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn straight_lines(){
+        assert_eq!(sample(0.0, 1.0, 3, Some), vec![vec![(0.0, 0.0), (0.5, 0.5), (1.0, 1.0)]]);
+        assert_eq!(sample(0.0, 1.0, 2, |_| Some(2.0)), vec![vec![(0.0, 2.0), (1.0, 2.0)]]);
+        assert_eq!(sample(0.0, 1.0, 3, |_| None), Vec::<Polyline>::new());
+    }
+    #[test]
+    fn curves(){
+        let lines = sample(-1.0, 1.0, 5, |x| Some(x * x));
+        assert_eq!(lines.len(), 1);
+        // more points where the parabola bends, every point is on the curve
+        assert!(lines[0].len() > 5);
+        assert!(lines[0].windows(2).all(|x| x[0].0 < x[1].0));
+        assert!(lines[0].iter().all(|(x, y)| *y == x * x));
+    }
+    #[test]
+    fn asymptotes(){
+        // the sample at 0 has no finite value
+        let lines = sample(-2.0, 2.0, 5, |x| Some(1.0 / x));
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].iter().all(|(x, _)| *x < 0.0) && lines[1].iter().all(|(x, _)| *x > 0.0));
+        // cut where it leaves the band from -3 to 3 around the evenly spaced samples
+        assert_eq!(lines[0].last().unwrap().1, -3.0);
+        // no sample is at the asymptote, the jump between both sides breaks the line
+        let lines = sample(0.0, 3.0, 7, |x| Some(x.tan()));
+        assert_eq!(lines.len(), 2);
+        let pi = std::f64::consts::FRAC_PI_2;
+        assert!(lines[0].iter().all(|(x, _)| *x < pi) && lines[1].iter().all(|(x, _)| *x > pi));
+        // steep but continuous functions are not broken
+        assert_eq!(sample(-1.0, 1.0, 4, |x| Some((x * 20.0).atan())).len(), 1);
+    }
+    #[test]
+    fn parametric(){
+        let lines = sample_curve(0.0, std::f64::consts::TAU, 9, |t| Some((t.cos(), t.sin())));
+        assert_eq!(lines.len(), 1);
+        // more points than the evenly spaced ones, every point is on the circle
+        assert!(lines[0].len() > 9);
+        assert!(lines[0].iter().all(|(x, y)| (x.hypot(*y) - 1.0).abs() < 1e-12));
+        // jumps along x break the line like jumps along y
+        let lines = sample_curve(0.0, 1.0, 4, |t| Some((if t < 0.4 { t } else { t + 1.0 }, 0.0)));
+        assert_eq!(lines.len(), 2);
+    }
+    #[test]
+    fn clipping(){
+        let bounds = [(-10.0, 10.0), (-2.0, 2.0)];
+        let line = vec![(0.0, 0.0), (1.0, 4.0), (2.0, 6.0), (3.0, -2.0)];
+        assert_eq!(clip(&line, bounds), vec![vec![(0.0, 0.0), (0.5, 2.0)], vec![(2.5, 2.0), (3.0, -2.0)]]);
+        assert_eq!(clip(&line, [(-10.0, 10.0); 2]), vec![line]);
+        assert_eq!(clip(&vec![(0.0, 4.0), (4.0, -4.0)], bounds), vec![vec![(1.0, 2.0), (3.0, -2.0)]]);
+        assert_eq!(clip(&vec![(-12.0, 0.0), (-11.0, 1.0)], bounds), Vec::<Polyline>::new());
+    }
+    #[test]
+    fn domains(){
+        // the line gets close to where the function stops having a value
+        let lines = sample(-1.0, 1.0, 3, |x| (x >= 0.3).then(|| x.sqrt()));
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0][0].0 - 0.3 < 1e-3);
+        let lines = sample(-1.0, 1.0, 9, |x| (x.abs() > 0.5).then_some(x));
+        assert_eq!(lines.len(), 2);
+    }
+}
@@ -0,0 +1,185 @@
+//! heights over two variables sampled on a grid, drawn as a heatmap or as a wireframe seen from above a corner
+//!
+//! the wireframe is an isometric projection of the cells of the grid. the cells are sorted by their distance to the
+//! viewer and every renderer draws them from the back to the front, covering what is behind them
+
+use serde::Serialize;
+
+use super::raster::Rgb;
+
+/// the colours of the scale from the lowest to the highest height, after the viridis colour map
+const SCALE: [Rgb; 5] = [[0x44, 0x01, 0x54], [0x3b, 0x52, 0x8b], [0x21, 0x91, 0x8c], [0x5e, 0xc9, 0x62], [0xfd, 0xe7, 0x25]];
+/// how far the highest point of a wireframe is above the lowest one, relative to the length of a side of the grid
+const RELIEF: f64 = 0.5;
+
+/// a plotted expression of two variables sampled at evenly spaced points of both ranges
+#[derive(PartialEq, Debug, Clone, Serialize)]
+pub struct Surface {
+    /// the expression as written in the source
+    pub label: String,
+    pub x_range: (f64, f64),
+    pub y_range: (f64, f64),
+    /// a row of heights for every y from the lowest up, None where the expression has no finite value
+    pub heights: Vec<Vec<Option<f64>>>,
+    /// drawn as a wireframe instead of a heatmap
+    pub wireframe: bool,
+}
+impl Surface {
+    /// evaluates the function at `count` by `count` points covering both ranges
+    pub fn sample(
+        label: String,
+        (x_range, y_range): ((f64, f64), (f64, f64)),
+        count: usize,
+        wireframe: bool,
+        mut f: impl FnMut(f64, f64) -> Option<f64>,
+    ) -> Self {
+        let at = |(min, max): (f64, f64), i: usize| min + (max - min) * i as f64 / (count - 1).max(1) as f64;
+        let heights = (0..count)
+            .map(|row| (0..count).map(|column| f(at(x_range, column), at(y_range, row)).filter(|x| x.is_finite())).collect())
+            .collect();
+        Self { label, x_range, y_range, heights, wireframe }
+    }
+    /// the lowest and highest height, widened when every height is the same
+    pub fn z_range(&self) -> (f64, f64) {
+        let (min, max) = self.heights.iter().flatten().flatten()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), z| (min.min(*z), max.max(*z)));
+        if min > max {
+            (0.0, 1.0)
+        } else if min == max {
+            (min - 1.0, max + 1.0)
+        } else {
+            (min, max)
+        }
+    }
+    /// the height at the point interpolated between the four samples around it, None outside of the ranges and next
+    /// to a sample without a height
+    pub fn at(&self, x: f64, y: f64) -> Option<f64> {
+        let count = self.heights.len();
+        let grid = |value: f64, (min, max): (f64, f64)| {
+            let position = (value - min) / (max - min) * (count - 1) as f64;
+            (0.0..=(count - 1) as f64).contains(&position).then_some(position)
+        };
+        let (column, row) = (grid(x, self.x_range)?, grid(y, self.y_range)?);
+        // the last row and column are interpolated from the cell before them
+        let (i, j) = ((column as usize).min(count.saturating_sub(2)), (row as usize).min(count.saturating_sub(2)));
+        let (s, t) = (column - i as f64, row - j as f64);
+        let height = |i: usize, j: usize| self.heights.get(j)?.get(i).copied().flatten();
+        let (a, b, c, d) = (height(i, j)?, height(i + 1, j)?, height(i, j + 1)?, height(i + 1, j + 1)?);
+        Some((a * (1.0 - s) + b * s) * (1.0 - t) + (c * (1.0 - s) + d * s) * t)
+    }
+    /// the cells of the grid between four samples with a height, projected isometrically and sorted from the back to
+    /// the front
+    pub fn faces(&self) -> Vec<Face> {
+        let count = self.heights.len();
+        let last = (count - 1).max(1) as f64;
+        let z_range = self.z_range();
+        let project = |column: usize, row: usize, z: f64| {
+            let (u, v, w) = (column as f64 / last, row as f64 / last, level(z, z_range) * RELIEF);
+            // seen from above the corner with the lowest x and y, x goes up to the right and y up to the left
+            ((u - v) * 30f64.to_radians().cos(), (u + v) * 30f64.to_radians().sin() + w)
+        };
+        let mut faces = vec![];
+        for row in 0..count.saturating_sub(1) {
+            for column in 0..count.saturating_sub(1) {
+                let corners = [(column, row), (column + 1, row), (column + 1, row + 1), (column, row + 1)];
+                let Some(heights) = corners.iter().map(|(i, j)| self.heights[*j][*i]).collect::<Option<Vec<_>>>() else { continue };
+                faces.push(Face {
+                    corners: [0, 1, 2, 3].map(|k| project(corners[k].0, corners[k].1, heights[k])),
+                    level: level(heights.iter().sum::<f64>() / 4.0, z_range),
+                    depth: (column + row) as f64,
+                });
+            }
+        }
+        // the cells furthest from the corner the grid is seen from are drawn first
+        faces.sort_by(|a, b| b.depth.total_cmp(&a.depth));
+        faces
+    }
+}
+
+/// a cell of a wireframe with its corners projected so x grows to the right and y grows upwards
+#[derive(PartialEq, Debug, Clone)]
+pub struct Face {
+    pub corners: [(f64, f64); 4],
+    /// the mean height of the corners relative to the z range, from 0 to 1
+    pub level: f64,
+    /// the distance from the corner the grid is seen from, in cells
+    depth: f64,
+}
+
+/// the smallest and largest projected x and y of the faces
+pub fn extent(faces: &[Face]) -> ((f64, f64), (f64, f64)) {
+    let fold = |coordinate: fn(&(f64, f64)) -> f64| faces.iter()
+        .flat_map(|x| x.corners.iter().map(coordinate))
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), x| (min.min(x), max.max(x)));
+    (fold(|x| x.0), fold(|x| x.1))
+}
+
+/// whether the point is inside of the polygon, by the number of its edges a ray to the right crosses
+pub fn inside((x, y): (f64, f64), corners: &[(f64, f64)]) -> bool {
+    let edges = corners.iter().zip(corners.iter().cycle().skip(1));
+    edges.filter(|((x1, y1), (x2, y2))| (*y1 > y) != (*y2 > y) && x < x1 + (y - y1) / (y2 - y1) * (x2 - x1)).count() % 2 == 1
+}
+
+/// the height relative to the z range, 0 at the lowest and 1 at the highest
+pub fn level(z: f64, (min, max): (f64, f64)) -> f64 {
+    ((z - min) / (max - min)).clamp(0.0, 1.0)
+}
+
+/// the colour of the scale at the level from 0 to 1
+pub fn scale_color(level: f64) -> Rgb {
+    let position = level.clamp(0.0, 1.0) * (SCALE.len() - 1) as f64;
+    let i = (position as usize).min(SCALE.len() - 2);
+    let t = position - i as f64;
+    [0, 1, 2].map(|channel| (SCALE[i][channel] as f64 + (SCALE[i + 1][channel] as f64 - SCALE[i][channel] as f64) * t).round() as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heights(){
+        let surface = Surface::sample("x + y".to_owned(), ((0.0, 2.0), (0.0, 4.0)), 3, false, |x, y| Some(x + y));
+        assert_eq!(surface.heights[0], vec![Some(0.0), Some(1.0), Some(2.0)]);
+        assert_eq!(surface.heights[2][2], Some(6.0));
+        assert_eq!(surface.z_range(), (0.0, 6.0));
+        assert_eq!(surface.at(0.5, 1.0), Some(1.5));
+        assert_eq!(surface.at(2.0, 4.0), Some(6.0));
+        assert_eq!(surface.at(2.5, 0.0), None);
+        let holes = Surface::sample("1 / x".to_owned(), ((-1.0, 1.0), (0.0, 1.0)), 3, false, |x, _| Some(1.0 / x));
+        assert_eq!(holes.heights[0][1], None);
+        assert_eq!(holes.at(-0.5, 0.5), None);
+        assert_eq!(holes.z_range(), (-1.0, 1.0));
+    }
+    #[test]
+    fn faces(){
+        let surface = Surface::sample("x * y".to_owned(), ((0.0, 1.0), (0.0, 1.0)), 4, true, |x, y| Some(x * y));
+        let faces = surface.faces();
+        assert_eq!(faces.len(), 9);
+        // from the back to the front, the last face is at the corner the grid is seen from
+        assert!(faces.windows(2).all(|x| x[0].depth >= x[1].depth));
+        assert_eq!(faces[8].corners[0], (0.0, 0.0));
+        assert!(faces[0].level > faces[8].level);
+        let ((x_min, x_max), (y_min, y_max)) = extent(&faces);
+        assert!((x_max + x_min).abs() < 1e-12 && y_min == 0.0 && y_max > 1.0);
+        // cells next to a sample without a height are left out
+        let holes = Surface::sample("1 / x".to_owned(), ((-1.0, 1.0), (0.0, 1.0)), 3, true, |x, _| Some(1.0 / x));
+        assert!(holes.faces().is_empty());
+    }
+    #[test]
+    fn polygons(){
+        let square = [(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)];
+        assert!(inside((1.0, 1.0), &square));
+        assert!(!inside((3.0, 1.0), &square));
+        assert!(!inside((1.0, -0.5), &square));
+        // above the point where the edges of a bow tie cross, between its halves
+        assert!(!inside((1.0, 1.5), &[(0.0, 0.0), (2.0, 2.0), (2.0, 0.0), (0.0, 2.0)]));
+    }
+    #[test]
+    fn colors(){
+        assert_eq!(scale_color(0.0), SCALE[0]);
+        assert_eq!(scale_color(1.0), SCALE[4]);
+        assert_eq!(scale_color(2.0), SCALE[4]);
+        assert_eq!(scale_color(0.125), [0x40, 0x2a, 0x70]);
+    }
+}
@@ -0,0 +1,164 @@
+//! writes plots as svg documents
+
+use std::fmt::Write;
+
+use super::{layout::{Layout, LEFT, TOP}, raster::Rgb, surface::{level, scale_color}, SampledPlot};
+
+/// the colours of the series, repeated when there are more series
+const COLORS: [&str; 6] = ["#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b"];
+
+/// the approximate width of a character of the labels, used to size the legend
+const CHAR_WIDTH: f64 = 7.0;
+
+/// the plot as an svg document with a frame, grid lines at the ticks unless turned off, the tick labels, the axis
+/// labels, the title and a legend when there is more than one series
+///
+/// the y range is chosen to fit every point, the series are clipped to the plot area. a plot3d is drawn as a rectangle
+/// for every cell of its grid or as a wireframe without axes, with its colour scale right of it
+pub fn render(plot: &SampledPlot, width: f64, height: f64) -> String {
+    let layout = Layout::new(plot, width, height, 1.0);
+    let Layout { left, right, top, bottom, .. } = layout;
+
+    let mut out = String::new();
+    let _ = writeln!(out, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">"#, num(width), num(height), num(width), num(height));
+    let _ = writeln!(out, r#"<rect width="100%" height="100%" fill="white"/>"#);
+    let _ = writeln!(out, r#"<defs><clipPath id="area"><rect x="{}" y="{}" width="{}" height="{}"/></clipPath></defs>"#, num(left), num(top), num(right - left), num(bottom - top));
+    if let Some(title) = &plot.title {
+        let _ = writeln!(out, r#"<text x="{}" y="{}" text-anchor="middle" font-family="sans-serif" font-size="16">{}</text>"#, num(width / 2.0), num(TOP + 16.0), escape(title));
+    }
+
+    match &plot.surface {
+        Some(surface) if surface.wireframe => {
+            let _ = writeln!(out, r#"<g stroke-width="1" stroke-linejoin="round">"#);
+            for face in layout.wireframe(surface) {
+                let points = face.corners.iter().map(|(x, y)| format!("{},{}", num(*x), num(*y))).collect::<Vec<_>>();
+                let _ = writeln!(out, r#"<polygon fill="white" stroke="{}" points="{}"/>"#, hex(scale_color(face.level)), points.join(" "));
+            }
+            let _ = writeln!(out, "</g>");
+        },
+        Some(surface) => {
+            let z_range = surface.z_range();
+            let count = surface.heights.len();
+            let at = |(min, max): (f64, f64), i: usize| min + (max - min) * i as f64 / (count - 1).max(1) as f64;
+            let _ = writeln!(out, r#"<g clip-path="url(#area)" shape-rendering="crispEdges">"#);
+            for row in 0..count.saturating_sub(1) {
+                for column in 0..count.saturating_sub(1) {
+                    let corners = [(column, row), (column + 1, row), (column, row + 1), (column + 1, row + 1)];
+                    let Some(heights) = corners.iter().map(|(i, j)| surface.heights[*j][*i]).collect::<Option<Vec<_>>>() else { continue };
+                    let (x1, x2) = (layout.x(at(surface.x_range, column)), layout.x(at(surface.x_range, column + 1)));
+                    let (y1, y2) = (layout.y(at(surface.y_range, row + 1)), layout.y(at(surface.y_range, row)));
+                    let color = hex(scale_color(level(heights.iter().sum::<f64>() / 4.0, z_range)));
+                    let _ = writeln!(out, r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{color}"/>"#, num(x1), num(y1), num(x2 - x1), num(y2 - y1));
+                }
+            }
+            let _ = writeln!(out, "</g>");
+        },
+        None if layout.grid => {
+            let _ = writeln!(out, r##"<g stroke="#e0e0e0" stroke-width="1">"##);
+            for x in &layout.x_ticks {
+                let _ = writeln!(out, r#"<line x1="{0}" y1="{1}" x2="{0}" y2="{2}"/>"#, num(layout.x(x.position)), num(top), num(bottom));
+            }
+            for y in &layout.y_ticks {
+                let _ = writeln!(out, r#"<line x1="{1}" y1="{0}" x2="{2}" y2="{0}"/>"#, num(layout.y(y.position)), num(left), num(right));
+            }
+            let _ = writeln!(out, "</g>");
+        },
+        None => {},
+    }
+    if let Some(scale) = &layout.color_scale {
+        let stops = (0..5).map(|i| format!(r#"<stop offset="{}" stop-color="{}"/>"#, num(i as f64 / 4.0), hex(scale_color(1.0 - i as f64 / 4.0)))).collect::<String>();
+        let _ = writeln!(out, r#"<defs><linearGradient id="scale" x1="0" y1="0" x2="0" y2="1">{stops}</linearGradient></defs>"#);
+        let _ = writeln!(out, r#"<rect x="{}" y="{}" width="{}" height="{}" fill="url(#scale)" stroke="black"/>"#, num(scale.left), num(scale.top), num(scale.right - scale.left), num(scale.bottom - scale.top));
+        let _ = writeln!(out, r#"<g font-family="sans-serif" font-size="12">"#);
+        for z in &scale.ticks {
+            let _ = writeln!(out, r#"<line x1="{1}" y1="{0}" x2="{2}" y2="{0}" stroke="black"/>"#, num(scale.y(z.position)), num(scale.right), num(scale.right + 4.0));
+            let _ = writeln!(out, r#"<text x="{}" y="{}">{}</text>"#, num(scale.right + 8.0), num(scale.y(z.position) + 4.0), escape(&z.label));
+        }
+        let _ = writeln!(out, "</g>");
+    }
+    // a wireframe has no axes, its x and y are not along the edges of the image
+    if !plot.surface.as_ref().is_some_and(|x| x.wireframe) {
+        let _ = writeln!(out, r#"<rect x="{}" y="{}" width="{}" height="{}" fill="none" stroke="black"/>"#, num(left), num(top), num(right - left), num(bottom - top));
+
+        let _ = writeln!(out, r#"<g font-family="sans-serif" font-size="12">"#);
+        for x in &layout.x_ticks {
+            let _ = writeln!(out, r#"<line x1="{0}" y1="{1}" x2="{0}" y2="{2}" stroke="black"/>"#, num(layout.x(x.position)), num(bottom), num(bottom + 4.0));
+            let _ = writeln!(out, r#"<text x="{}" y="{}" text-anchor="middle">{}</text>"#, num(layout.x(x.position)), num(bottom + 18.0), escape(&x.label));
+        }
+        for y in &layout.y_ticks {
+            let _ = writeln!(out, r#"<line x1="{1}" y1="{0}" x2="{2}" y2="{0}" stroke="black"/>"#, num(layout.y(y.position)), num(left - 4.0), num(left));
+            let _ = writeln!(out, r#"<text x="{}" y="{}" text-anchor="end">{}</text>"#, num(left - 8.0), num(layout.y(y.position) + 4.0), escape(&y.label));
+        }
+        if let Some(label) = &plot.axes.x_label {
+            let _ = writeln!(out, r#"<text x="{}" y="{}" text-anchor="middle">{}</text>"#, num((left + right) / 2.0), num(bottom + 38.0), escape(label));
+        }
+        if let Some(label) = &plot.axes.y_label {
+            let (x, y) = (left - LEFT - 6.0, (top + bottom) / 2.0);
+            let _ = writeln!(out, r#"<text x="{0}" y="{1}" text-anchor="middle" transform="rotate(-90 {0} {1})">{2}</text>"#, num(x), num(y), escape(label));
+        }
+        let _ = writeln!(out, "</g>");
+    }
+
+    let _ = writeln!(out, r#"<g clip-path="url(#area)" fill="none" stroke-width="2" stroke-linejoin="round">"#);
+    for (i, lines) in layout.lines.iter().enumerate() {
+        let color = COLORS[i % COLORS.len()];
+        for line in lines {
+            match &line[..] {
+                [(x, y)] => _ = writeln!(out, r#"<circle cx="{}" cy="{}" r="2" fill="{color}"/>"#, num(layout.x(*x)), num(layout.y(*y))),
+                line => {
+                    let points = line.iter().map(|(x, y)| format!("{},{}", num(layout.x(*x)), num(layout.y(*y)))).collect::<Vec<_>>();
+                    let _ = writeln!(out, r#"<polyline stroke="{color}" points="{}"/>"#, points.join(" "));
+                },
+            }
+        }
+    }
+    let _ = writeln!(out, "</g>");
+
+    if plot.series.len() > 1 {
+        let longest = plot.series.iter().map(|x| x.label.chars().count()).max().unwrap_or(0);
+        let (legend_width, legend_height) = (36.0 + longest as f64 * CHAR_WIDTH, 8.0 + plot.series.len() as f64 * 18.0);
+        let (x, y) = (right - legend_width - 8.0, top + 8.0);
+        let _ = writeln!(out, r#"<g font-family="sans-serif" font-size="12">"#);
+        let _ = writeln!(out, r#"<rect x="{}" y="{}" width="{}" height="{}" fill="white" stroke="black" stroke-opacity="0.3"/>"#, num(x), num(y), num(legend_width), num(legend_height));
+        for (i, series) in plot.series.iter().enumerate() {
+            let row = y + 16.0 + i as f64 * 18.0;
+            let _ = writeln!(out, r#"<line x1="{0}" y1="{2}" x2="{1}" y2="{2}" stroke="{3}" stroke-width="2"/>"#, num(x + 6.0), num(x + 26.0), num(row - 4.0), COLORS[i % COLORS.len()]);
+            let _ = writeln!(out, r#"<text x="{}" y="{}">{}</text>"#, num(x + 30.0), num(row), escape(&series.label));
+        }
+        let _ = writeln!(out, "</g>");
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+/// the coordinate with at most 2 decimals and without trailing zeros
+fn num(x: f64) -> String {
+    let out = format!("{x:.2}");
+    let out = out.trim_end_matches('0').trim_end_matches('.');
+    if out == "-0" { "0".to_owned() } else { out.to_owned() }
+}
+
+/// the colour as written in css, #rrggbb
+fn hex([r, g, b]: Rgb) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+/// the text with the characters which have a meaning in xml replaced by entities
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers(){
+        assert_eq!(num(12.0), "12");
+        assert_eq!(num(100.0), "100");
+        assert_eq!(num(0.126), "0.13");
+        assert_eq!(num(-0.001), "0");
+        assert_eq!(escape("a < b & \"c\""), "a &lt; b &amp; &quot;c&quot;");
+        assert_eq!(hex([0x44, 0x01, 0x54]), "#440154");
+    }
+}
@@ -0,0 +1,465 @@
+use crossterm::style::{Color, Stylize};
+
+use super::{axes::{axis_ticks, project, x_positions, Axes, Scale}, braille::{line_dots, BrailleCanvas}, raster::Rgb, sampler::clip, surface::{extent, inside, level, scale_color, Surface}, tick_step, Polyline, SampledPlot};
+
+/// the size in characters of plots in the repl
+pub const DEFAULT_SIZE: (usize, usize) = (72, 20);
+
+/// the colours of the series, repeated when there are more series
+const COLORS: [Color; 6] = [Color::Cyan, Color::Yellow, Color::Magenta, Color::Green, Color::Red, Color::Blue];
+/// the characters of a heatmap without colours from the lowest to the highest height
+const SHADES: [char; 9] = ['.', ':', '-', '=', '+', '*', '#', '%', '@'];
+/// the number of characters of the colour scale below a plot3d
+const SCALE_WIDTH: usize = 16;
+
+/// a single character of the grid, graph characters know the series they belong to
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Cell {
+    pub symbol: char,
+    pub series: Option<usize>,
+    /// the colour of the scale of a plot3d, used instead of the colour of the series
+    pub color: Option<Rgb>,
+}
+
+/// the characters the graphs are drawn in and the ranges they cover, used to find the point under the mouse
+///
+/// the ranges are positions along the axes, which are the logarithms of the values on a log scale
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct PlotArea {
+    /// the first column and row of the area
+    pub left: usize,
+    pub top: usize,
+    pub columns: usize,
+    pub rows: usize,
+    pub x_range: (f64, f64),
+    pub y_range: (f64, f64),
+}
+impl PlotArea {
+    /// the point at the middle of the character, None outside of the area
+    pub fn point(&self, column: usize, row: usize) -> Option<(f64, f64)> {
+        let (column, row) = (column.checked_sub(self.left)?, row.checked_sub(self.top)?);
+        if column >= self.columns || row >= self.rows {
+            return None;
+        }
+        let ((x_min, x_max), (y_min, y_max)) = (self.x_range, self.y_range);
+        // the first and last dots are at the ends of the ranges, a character is 2 dots wide and 4 dots high
+        let x = x_min + (column as f64 * 2.0 + 0.5) / (self.columns * 2 - 1).max(1) as f64 * (x_max - x_min);
+        let y = y_max - (row as f64 * 4.0 + 1.5) / (self.rows * 4 - 1).max(1) as f64 * (y_max - y_min);
+        Some((x, y))
+    }
+}
+
+/// characters in rows, the plot is drawn here before it is written to the terminal
+#[derive(PartialEq, Debug, Clone)]
+pub struct Grid {
+    width: usize,
+    cells: Vec<Cell>,
+    area: Option<PlotArea>,
+}
+impl Grid {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self { width, cells: vec![Cell { symbol: ' ', series: None, color: None }; width * height], area: None }
+    }
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    /// where the graphs are, None until a plot with room for graphs is drawn
+    pub fn area(&self) -> Option<PlotArea> {
+        self.area
+    }
+    pub fn height(&self) -> usize {
+        self.cells.len().checked_div(self.width).unwrap_or(0)
+    }
+    pub fn get(&self, column: usize, row: usize) -> Cell {
+        self.cells[row * self.width + column]
+    }
+    /// sets the character, positions outside of the grid are ignored
+    pub fn set(&mut self, column: usize, row: usize, symbol: char, series: Option<usize>) {
+        if column < self.width && row < self.height() {
+            self.cells[row * self.width + column] = Cell { symbol, series, color: None };
+        }
+    }
+    /// sets the character in a colour of the scale of a plot3d, positions outside of the grid are ignored
+    pub fn shade(&mut self, column: usize, row: usize, symbol: char, color: Rgb) {
+        if column < self.width && row < self.height() {
+            self.cells[row * self.width + column] = Cell { symbol, series: None, color: Some(color) };
+        }
+    }
+    /// writes the text starting at the position, cut off at the edge
+    pub fn text(&mut self, column: usize, row: usize, text: &str) {
+        for (i, c) in text.chars().enumerate() {
+            self.set(column + i, row, c, None);
+        }
+    }
+    /// every row without trailing spaces
+    pub fn lines(&self) -> Vec<String> {
+        self.cells.chunks(self.width.max(1))
+            .map(|x| x.iter().map(|x| x.symbol).collect::<String>().trim_end().to_owned())
+            .collect()
+    }
+    /// the rows with the graphs coloured by series, ending in a newline
+    pub fn render(&self, color: bool) -> String {
+        if !color {
+            return self.lines().into_iter().map(|x| x + "\n").collect();
+        }
+        let mut out = String::new();
+        for row in self.cells.chunks(self.width.max(1)) {
+            let end = row.iter().rposition(|x| x.symbol != ' ').map(|x| x + 1).unwrap_or(0);
+            for cell in &row[..end] {
+                match (cell.color, cell.series) {
+                    (Some([r, g, b]), _) => out.push_str(&cell.symbol.with(Color::Rgb { r, g, b }).to_string()),
+                    (None, Some(series)) => out.push_str(&cell.symbol.with(COLORS[series % COLORS.len()]).to_string()),
+                    (None, None) => out.push(cell.symbol),
+                }
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// draws the lines on a grid of the given size with axes and tick labels, each line is a part of a series
+///
+/// without a y range one is chosen to fit every point, with `equal_aspect` both ranges are widened so braille dots, which are
+/// about square, are as long along x as along y. the points and the ticks are placed on the scales of the axes, which
+/// also give the format of the labels and whether there is a dotted grid at the ticks
+pub fn render(series: &[Vec<Polyline>], x_range: (f64, f64), y_range: Option<(f64, f64)>, width: usize, height: usize, equal_aspect: bool, axes: &Axes) -> Grid {
+    let (scales, format) = (axes.scales(), axes.format.unwrap_or_default());
+    let series = series.iter().map(|x| project(x, scales)).collect::<Vec<_>>();
+    let lines = series.iter().flatten().cloned().collect::<Vec<_>>();
+    let x_range = x_positions(x_range, scales.0, &lines);
+    let fitted = y_range.and_then(|x| scales.1.range(x)).unwrap_or_else(|| super::y_range(&lines));
+    // about a tick every 3 rows and every 10 columns
+    let y_count = (height / 3).max(2);
+    let layout = |margin: usize| {
+        let (x_range, (y_min, y_max)) = match equal_aspect {
+            true => super::equal_aspect(x_range, fitted, (width.saturating_sub(margin + 1) * 2).saturating_sub(1) as f64, (height.saturating_sub(2) * 4).saturating_sub(1) as f64),
+            false => (x_range, fitted),
+        };
+        let (y_ticks, labels) = axis_ticks(scales.1, format, (y_min, y_max), y_count).into_iter().map(|x| (x.position, x.label)).unzip::<_, _, Vec<_>, Vec<_>>();
+        (x_range, (y_min, y_max), y_ticks, labels)
+    };
+    // labels wider than a third of the grid are cut so the plot keeps most of the columns
+    let width_of = |labels: &[String]| labels.iter().map(|x| x.chars().count()).max().unwrap_or(0).min(width / 3);
+    let (mut x_range, (mut y_min, mut y_max), mut y_ticks, mut labels) = layout(0);
+    let mut margin = width_of(&labels);
+    if equal_aspect {
+        // the labels take columns from the plot, which changes the ranges and so the labels
+        (x_range, (y_min, y_max), y_ticks, labels) = layout(margin);
+        margin = width_of(&labels);
+    }
+
+    let mut grid = Grid::new(width, height);
+    // the plot is right of the labels and the axis, above the axis and the labels
+    let columns = width.saturating_sub(margin + 1);
+    let rows = height.saturating_sub(2);
+    if columns == 0 || rows == 0 {
+        return grid;
+    }
+    let (x_min, x_max) = x_range;
+    let column_of = |x: f64| ((x - x_min) / (x_max - x_min) * (columns - 1) as f64).round() as usize;
+    let row_of = |y: f64| ((y_max - y) / (y_max - y_min) * (rows - 1) as f64).round() as usize;
+    grid.area = Some(PlotArea { left: margin + 1, top: 0, columns, rows, x_range, y_range: (y_min, y_max) });
+
+    // axes
+    for row in 0..rows {
+        grid.set(margin, row, '│', None);
+    }
+    grid.set(margin, rows, '└', None);
+    for column in 0..columns {
+        grid.set(margin + 1 + column, rows, '─', None);
+    }
+    for (tick, label) in y_ticks.iter().zip(&labels) {
+        let row = row_of(*tick);
+        grid.set(margin, row, '┤', None);
+        let label = cut_label(label, margin);
+        grid.text(margin - label.chars().count(), row, &label);
+    }
+    let x_count = (columns / 10).max(2);
+    let x_ticks = axis_ticks(scales.0, format, (x_min, x_max), x_count);
+    if axes.grid == Some(true) {
+        for tick in &y_ticks {
+            (0..columns).for_each(|column| grid.set(margin + 1 + column, row_of(*tick), '·', None));
+        }
+        for tick in &x_ticks {
+            (0..rows).for_each(|row| grid.set(margin + 1 + column_of(tick.position), row, '·', None));
+        }
+    }
+    // labels are only written when they do not touch the previous one
+    let mut free = margin + 1;
+    for tick in x_ticks {
+        let column = margin + 1 + column_of(tick.position);
+        grid.set(column, rows, '┬', None);
+        let label = tick.label;
+        let start = column.saturating_sub(label.chars().count() / 2).max(margin + 1);
+        if start >= free && start + label.chars().count() <= width {
+            grid.text(start, rows + 1, &label);
+            free = start + label.chars().count() + 1;
+        }
+    }
+
+    for (i, lines) in series.iter().enumerate() {
+        let mut canvas = BrailleCanvas::new(columns, rows);
+        let (dot_width, dot_height) = (canvas.dot_width(), canvas.dot_height());
+        let dot = |(x, y): (f64, f64)| (
+            ((x - x_min) / (x_max - x_min) * (dot_width - 1) as f64).round() as i64,
+            ((y_max - y) / (y_max - y_min) * (dot_height - 1) as f64).round() as i64,
+        );
+        // only the parts inside of the plot area, so lines far outside of a zoomed in view are not walked dot by dot
+        for line in lines.iter().flat_map(|x| clip(x, [(x_min, x_max), (y_min, y_max)])) {
+            let dots = line.iter().map(|x| dot(*x)).collect::<Vec<_>>();
+            match &dots[..] {
+                [single] => canvas.set(single.0, single.1),
+                dots => dots.windows(2).for_each(|x| canvas.line(x[0], x[1])),
+            }
+        }
+        for row in 0..rows {
+            for column in 0..columns {
+                if let Some(symbol) = canvas.cell(column, row) {
+                    grid.set(margin + 1 + column, row, symbol, Some(i));
+                }
+            }
+        }
+    }
+    grid
+}
+
+/// the label cut to at most the width, ending in … when it was cut
+fn cut_label(label: &str, width: usize) -> String {
+    match label.chars().count() > width {
+        true => label.chars().take(width.saturating_sub(1)).chain((width > 0).then_some('…')).collect(),
+        false => label.to_owned(),
+    }
+}
+
+/// draws the heights as a heatmap on the axes of [`render`], a character for every point of the plot area
+///
+/// in colour every character is a block in the colour of the scale, without colours higher points are drawn in
+/// denser characters
+pub fn render_heatmap(surface: &Surface, width: usize, height: usize, equal_aspect: bool, axes: &Axes, color: bool) -> Grid {
+    let axes = Axes { x_scale: Some(Scale::Linear), y_scale: Some(Scale::Linear), ..axes.clone() };
+    let mut grid = render(&[], surface.x_range, Some(surface.y_range), width, height, equal_aspect, &axes);
+    let Some(area) = grid.area() else { return grid };
+    let z_range = surface.z_range();
+    for row in area.top..area.top + area.rows {
+        for column in area.left..area.left + area.columns {
+            let Some(z) = area.point(column, row).and_then(|(x, y)| surface.at(x, y)) else { continue };
+            let level = level(z, z_range);
+            grid.shade(column, row, if color { '█' } else { shade(level) }, scale_color(level));
+        }
+    }
+    grid
+}
+
+/// draws the cells of the surface as seen from above a corner in braille dots, without axes
+///
+/// the cells are drawn from the back to the front, each one clears the dots inside of it before its edges are drawn so
+/// it hides the cells behind it. a character has the colour of the highest edge drawn in it
+pub fn render_wireframe(surface: &Surface, width: usize, height: usize) -> Grid {
+    let mut grid = Grid::new(width, height);
+    let faces = surface.faces();
+    let (dot_width, dot_height) = (width * 2, height * 4);
+    if faces.is_empty() || dot_width < 2 || dot_height < 2 {
+        return grid;
+    }
+    // braille dots are about square, so the projection keeps its shape and is centered
+    let ((x_min, x_max), (y_min, y_max)) = extent(&faces);
+    let scale = ((dot_width - 1) as f64 / (x_max - x_min)).min((dot_height - 1) as f64 / (y_max - y_min));
+    let (x_offset, y_offset) = (((dot_width - 1) as f64 - (x_max - x_min) * scale) / 2.0, ((dot_height - 1) as f64 - (y_max - y_min) * scale) / 2.0);
+    let dot = |(x, y): (f64, f64)| (x_offset + (x - x_min) * scale, y_offset + (y_max - y) * scale);
+    // the level of the edge drawn last at every dot, None where no edge is visible
+    let mut dots = vec![None; dot_width * dot_height];
+    for face in faces {
+        let corners = face.corners.map(dot);
+        let (left, right) = corners.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(a, b), (x, _)| (a.min(*x), b.max(*x)));
+        let (top, bottom) = corners.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(a, b), (_, y)| (a.min(*y), b.max(*y)));
+        for y in (top.ceil().max(0.0) as usize)..=(bottom.floor() as usize).min(dot_height - 1) {
+            for x in (left.ceil().max(0.0) as usize)..=(right.floor() as usize).min(dot_width - 1) {
+                if inside((x as f64, y as f64), &corners) {
+                    dots[y * dot_width + x] = None;
+                }
+            }
+        }
+        let round = |(x, y): (f64, f64)| (x.round() as i64, y.round() as i64);
+        for (from, to) in corners.iter().zip(corners.iter().cycle().skip(1)) {
+            for (x, y) in line_dots(round(*from), round(*to)) {
+                if (0..dot_width as i64).contains(&x) && (0..dot_height as i64).contains(&y) {
+                    dots[y as usize * dot_width + x as usize] = Some(face.level);
+                }
+            }
+        }
+    }
+    let mut canvas = BrailleCanvas::new(width, height);
+    let mut levels = vec![None::<f64>; width * height];
+    for (i, level) in dots.iter().enumerate() {
+        let Some(level) = level else { continue };
+        let (x, y) = (i % dot_width, i / dot_width);
+        canvas.set(x as i64, y as i64);
+        let cell = &mut levels[y / 4 * width + x / 2];
+        *cell = Some(cell.map_or(*level, |x| x.max(*level)));
+    }
+    for row in 0..height {
+        for column in 0..width {
+            if let (Some(symbol), Some(level)) = (canvas.cell(column, row), levels[row * width + column]) {
+                grid.shade(column, row, symbol, scale_color(level));
+            }
+        }
+    }
+    grid
+}
+
+/// the character of a heatmap without colours at the level from 0 to 1
+fn shade(level: f64) -> char {
+    SHADES[(level.clamp(0.0, 1.0) * (SHADES.len() - 1) as f64).round() as usize]
+}
+
+/// the lowest and highest height of the surface with the colours between them, followed by its label
+fn color_scale(surface: &Surface, axes: &Axes, color: bool) -> String {
+    let (min, max) = surface.z_range();
+    let step = tick_step(min, max, 4);
+    let bar = (0..SCALE_WIDTH)
+        .map(|i| i as f64 / (SCALE_WIDTH - 1) as f64)
+        .map(|level| match (color, scale_color(level)) {
+            (true, [r, g, b]) => '█'.with(Color::Rgb { r, g, b }).to_string(),
+            (false, _) => shade(level).to_string(),
+        })
+        .collect::<String>();
+    let format = axes.format.unwrap_or_default();
+    format!("{} {bar} {}  {}\n", format.format(min, step), format.format(max, step), surface.label)
+}
+
+/// the plot with its title centered above it and, when it has more than one series, a legend below it. a plot3d has
+/// its colour scale below it instead. the label of the y axis is written above the plot and the one of the x axis
+/// below it
+pub fn render_plot(plot: &SampledPlot, width: usize, height: usize, color: bool) -> String {
+    draw_plot(plot, width, height, color).0
+}
+
+/// renders the plot like [`render_plot`], also returning where its graphs are relative to the first line
+pub fn draw_plot(plot: &SampledPlot, width: usize, height: usize, color: bool) -> (String, Option<PlotArea>) {
+    let mut out = String::new();
+    let mut above = 0;
+    for line in [plot.title.as_ref().map(|x| format!("{x:^width$}")), plot.axes.y_label.clone()].into_iter().flatten() {
+        out.push_str(line.trim_end());
+        out.push('\n');
+        above += 1;
+    }
+    let grid = match &plot.surface {
+        Some(surface) if surface.wireframe => render_wireframe(surface, width, height),
+        Some(surface) => render_heatmap(surface, width, height, plot.equal_aspect, &plot.axes, color),
+        None => render(&plot.series.iter().map(|x| x.lines.clone()).collect::<Vec<_>>(), plot.x_range, plot.y_range, width, height, plot.equal_aspect, &plot.axes),
+    };
+    let area = grid.area().map(|x| PlotArea { top: x.top + above, ..x });
+    out.push_str(&grid.render(color));
+    if let Some(label) = &plot.axes.x_label {
+        out.push_str(format!("{label:^width$}").trim_end());
+        out.push('\n');
+    }
+    if let Some(surface) = &plot.surface {
+        out.push_str(&color_scale(surface, &plot.axes, color));
+    }
+    if plot.series.len() > 1 {
+        for (i, series) in plot.series.iter().enumerate() {
+            let mark = if color { "──".with(COLORS[i % COLORS.len()]).to_string() } else { "──".to_owned() };
+            out.push_str(&format!("{mark} {}\n", series.label));
+        }
+    }
+    (out, area)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plot::{sampler::{self, sample}, surface::Surface, Series};
+
+    #[test]
+    fn line(){
+        let lines = sample(0.0, 4.0, 9, Some);
+        let grid = render(&[lines], (0.0, 4.0), None, 12, 6, false, &Axes::default());
+        assert_eq!(grid.lines(), vec![
+            "4┤       ⢀⡠⠊",
+            " │     ⢀⠔⠁",
+            "2┤  ⢀⠔⠊⠁",
+            "0┤⡠⠊⠁",
+            " └┬────┬───┬",
+            "  0    2   4",
+        ].into_iter().map(str::to_owned).collect::<Vec<_>>());
+    }
+    #[test]
+    fn wide_labels(){
+        // huge values are written with an exponent and still leave columns for the curve
+        let grid = render(&[sample(-1.0, 1.0, 9, |x| Some(1.0e100 * x))], (-1.0, 1.0), None, 30, 8, false, &Axes::default());
+        assert_eq!(grid.lines()[0], " 1e100┤                   ⢀⠤⠒⠉");
+        assert!(grid.area.is_some());
+        // labels wider than a third of the grid are cut
+        assert_eq!(cut_label("123456789", 4), "123…");
+        assert_eq!(cut_label("1e5", 4), "1e5");
+        let grid = render(&[sample(0.0, 1.0, 9, |x| Some(1.23456789e100 * x))], (0.0, 1.0), None, 12, 8, false, &Axes::default());
+        assert!(grid.area.is_some());
+    }
+    #[test]
+    fn colors(){
+        let grid = render(&[sample(-1.0, 1.0, 20, |x| Some(x * x))], (-1.0, 1.0), None, 40, 10, false, &Axes::default());
+        assert_eq!(grid.width(), 40);
+        assert_eq!(grid.height(), 10);
+        let plain = grid.render(false);
+        assert_eq!(plain.lines().count(), 10);
+        assert!(grid.render(true).contains('\x1b'));
+        assert!(!plain.contains('\x1b'));
+        // the curve touches both top corners of the plot
+        assert_eq!(grid.lines()[0], "1.0┤⠱⡀                                ⢀⠎");
+        assert_eq!(grid.lines()[9], "    -1.0   -0.5      0.0     0.5");
+    }
+    #[test]
+    fn equal_aspect(){
+        let circle = sampler::sample_curve(0.0, std::f64::consts::TAU, 40, |t| Some((t.cos(), t.sin())));
+        let grid = render(&[circle], (-1.0, 1.0), None, 30, 10, true, &Axes::default());
+        // a circle of 8 rows of 4 dots is about 16 columns of 2 dots wide, not the whole width
+        let columns = grid.lines()[..8].iter()
+            .flat_map(|x| x.chars().skip_while(|c| *c != '│' && *c != '┤').skip(1).enumerate().filter(|(_, c)| *c != ' ').map(|(i, _)| i).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert!((16..=17).contains(&(columns.iter().max().unwrap() - columns.iter().min().unwrap() + 1)));
+    }
+    #[test]
+    fn title_and_legend(){
+        let series = |label: &str, y: f64| Series { label: label.to_owned(), lines: vec![vec![(0.0, y), (1.0, y)]] };
+        let plot = SampledPlot { title: Some("flat".to_owned()), x_range: (0.0, 1.0), y_range: None, series: vec![series("one", 1.0), series("two", 2.0)], output: None, size: Default::default(), equal_aspect: false, surface: None, axes: Default::default() };
+        let out = render_plot(&plot, 20, 6, false);
+        let lines = out.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "        flat");
+        assert_eq!(lines[7..], ["── one", "── two"]);
+        plot.series.iter().for_each(|x| assert!(out.contains(&x.label)));
+    }
+    #[test]
+    fn surfaces(){
+        let heatmap = Surface::sample("x + y".to_owned(), ((0.0, 1.0), (0.0, 1.0)), 5, false, |x, y| Some(x + y));
+        let grid = render_heatmap(&heatmap, 12, 6, false, &Axes::default(), false);
+        // denser characters towards the highest corner at the top right
+        assert_eq!(&grid.lines()[..4], ["1.0┤++**##%%", "   │==++**##", "0.5┤--==++**", "0.0┤::--==++"]);
+        assert!(grid.render(true).contains("\x1b[38;2;"));
+        let mut wireframe = heatmap;
+        wireframe.wireframe = true;
+        let plot = SampledPlot { title: None, x_range: (0.0, 1.0), y_range: Some((0.0, 1.0)), series: vec![], output: None, size: Default::default(), equal_aspect: false, surface: Some(wireframe), axes: Default::default() };
+        let (out, area) = draw_plot(&plot, 20, 8, false);
+        // no axes to pan or zoom, the colour scale below the drawing
+        assert_eq!(area, None);
+        assert!(!out.contains('│') && out.chars().any(|x| ('⠁'..='⣿').contains(&x)));
+        assert_eq!(out.lines().last(), Some("0.0 .::--==++**##%%@ 2.0  x + y"));
+    }
+    #[test]
+    fn area(){
+        let plot = SampledPlot { title: Some("flat".to_owned()), x_range: (0.0, 1.0), y_range: Some((0.0, 2.0)), series: vec![], output: None, size: Default::default(), equal_aspect: false, surface: None, axes: Default::default() };
+        let (out, area) = draw_plot(&plot, 20, 6, false);
+        let area = area.unwrap();
+        // below the title, right of the labels and the axis
+        assert_eq!((area.left, area.top, area.columns, area.rows), (2, 1, 18, 4));
+        assert!(out.lines().nth(1).unwrap().starts_with("2┤"));
+        let (x, y) = area.point(2, 1).unwrap();
+        // the middle of the first character, which covers about an eighteenth of the x range and a quarter of the y range
+        assert!(x > 0.0 && x < 0.05 && y < 2.0 && y > 1.75);
+        let (x, y) = area.point(19, 4).unwrap();
+        assert!(x < 1.0 && x > 0.95 && y > 0.0 && y < 0.25);
+        assert_eq!(area.point(1, 1), None);
+        assert_eq!(area.point(2, 5), None);
+    }
+}
@@ -0,0 +1,204 @@
+use logos::Logos;
+
+use salsa::Setter;
+
+use crate::{diagnostics::{syntax_errors, Diagnostic}, lexer::Token, parser::compile_source, spanned::Span, symbols::resolve::{resolve_program, Reference, Resolution}, ProgramSource};
+
+/// a replacement of the source text covered by the span
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub struct TextEdit {
+    pub span: Span,
+    pub new_text: String,
+}
+
+/// applies non overlapping edits to the source
+pub fn apply_edits(source: &str, edits: &[TextEdit]) -> String {
+    let mut edits = edits.iter().collect::<Vec<_>>();
+    edits.sort_by_key(|x| x.span.start);
+    let mut out = String::with_capacity(source.len());
+    let mut last = 0;
+    for edit in edits {
+        out.push_str(&source[last..edit.span.start]);
+        out.push_str(&edit.new_text);
+        last = edit.span.end;
+    }
+    out.push_str(&source[last..]);
+    out
+}
+
+/// renames the binding defined (or referenced) at the offset
+///
+/// every clause of a function and every reference resolving to the binding is renamed,
+/// the rename is refused when it would change what any name in the program resolves to,
+/// which is checked in `scratch`, whose text is replaced so renaming adds no inputs to the database
+pub fn rename(db: &mut dyn salsa::Database, source: ProgramSource, scratch: ProgramSource, offset: usize, new_name: &str) -> Result<Vec<TextEdit>, Diagnostic> {
+    let (edits, target, before) = edits(db, source, offset, new_name)?;
+    if edits.is_empty() {
+        return Ok(edits);
+    }
+    let text = apply_edits(source.raw_text(db), &edits);
+    scratch.set_raw_text(db).to(text);
+    check_bindings(db, &before, scratch, target, new_name, &edits)?;
+    Ok(edits)
+}
+
+/// the names and the sites of the definitions of a program with its references, kept while the database changes
+struct Bindings {
+    definitions: Vec<(String, Vec<Span>)>,
+    references: Vec<Reference>,
+}
+
+/// the edits renaming the binding at the offset, the index of its definition and the bindings before the rename
+fn edits(db: &dyn salsa::Database, source: ProgramSource, offset: usize, new_name: &str) -> Result<(Vec<TextEdit>, usize, Bindings), Diagnostic> {
+    let at = Span::new(offset, offset);
+    let (resolution, has_errors) = resolve_source(db, source);
+    if has_errors {
+        return Err(Diagnostic::error(at, "cannot rename in a program that does not parse"));
+    }
+    let Some(target) = resolution.definition_at(db, offset) else {
+        return Err(Diagnostic::error(at, "no binding to rename at this position"));
+    };
+    let definition = &resolution.definitions(db)[target];
+    let mut tokens = Token::lexer(new_name);
+    if !matches!((tokens.next(), tokens.next()), (Some(Ok(Token::Identifier(_))), None)) {
+        return Err(Diagnostic::error(definition.sites[0], format!("`{new_name}` is not a valid identifier")));
+    }
+    let bindings = Bindings {
+        definitions: resolution.definitions(db).iter().map(|x| (x.name.clone(), x.sites.clone())).collect(),
+        references: resolution.references(db).clone(),
+    };
+    if definition.name == new_name {
+        return Ok((vec![], target, bindings));
+    }
+
+    let mut edits = definition.sites.iter()
+        .copied()
+        .chain(resolution.references_to(db, target).map(|r| r.span))
+        .map(|span| TextEdit { span, new_text: new_name.to_owned() })
+        .collect::<Vec<_>>();
+    edits.sort_by_key(|x| x.span.start);
+    Ok((edits, target, bindings))
+}
+
+fn resolve_source(db: &dyn salsa::Database, source: ProgramSource) -> (Resolution<'_>, bool) {
+    let program = compile_source(db, source);
+    (resolve_program(db, program), !syntax_errors(db, source).is_empty())
+}
+
+/// resolves the renamed program and verifies every reference still points at the same binding
+fn check_bindings(
+    db: &dyn salsa::Database,
+    before: &Bindings,
+    renamed: ProgramSource,
+    target: usize,
+    new_name: &str,
+    edits: &[TextEdit]
+) -> Result<(), Diagnostic> {
+    let old_name = &before.definitions[target].0;
+    let (after, has_errors) = resolve_source(db, renamed);
+    if has_errors {
+        return Err(Diagnostic::error(before.definitions[target].1[0], format!("renaming `{old_name}` to `{new_name}` produces an invalid program")));
+    }
+
+    // moves an offset in the original source to the same position in the renamed source
+    let shift = |offset: usize| {
+        edits.iter()
+            .take_while(|x| x.span.end <= offset)
+            .fold(offset, |acc, x| acc + x.new_text.len() - (x.span.end - x.span.start))
+    };
+    // the definitions of the renamed program, indexed by the definitions of the original program
+    let mapped = before.definitions.iter()
+        .map(|(_, sites)| {
+            let site = shift(sites[0].start);
+            after.definitions(db).iter().position(|x| x.sites.iter().any(|s| s.start == site))
+        })
+        .collect::<Vec<_>>();
+    let site_of = |def: Option<usize>| def.map(|d| before.definitions[d].1[0]);
+
+    let merged = mapped.iter().enumerate()
+        .position(|(index, x)| index != target && x.is_some() && *x == mapped[target]);
+    if merged.is_some() {
+        return Err(Diagnostic::error(before.definitions[target].1[0], format!("renaming `{old_name}` to `{new_name}` would merge it with another definition"))
+            .with_note(site_of(merged), format!("`{new_name}` is already defined here")));
+    }
+    for reference in &before.references {
+        let resolved = after.references(db).iter()
+            .find(|x| x.span.start == shift(reference.span.start))
+            .and_then(|x| x.definition);
+        let expected = reference.definition.and_then(|d| mapped[d]);
+        if resolved == expected {
+            continue;
+        }
+        let other = |def: Option<usize>| mapped.iter().position(|x| x.is_some() && *x == def);
+        let error = if reference.definition == Some(target) {
+            Diagnostic::error(reference.span, format!("renaming `{old_name}` to `{new_name}` would make this reference resolve to another binding"))
+                .with_note(site_of(other(resolved)), format!("it would be captured by this definition of `{new_name}`"))
+        } else {
+            Diagnostic::error(reference.span, format!("renaming `{old_name}` to `{new_name}` would capture this reference to `{new_name}`"))
+                .with_note(site_of(reference.definition), "which currently refers to this definition")
+        };
+        return Err(error);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::GraphingDatabase;
+
+    use super::*;
+
+    fn renamed(src: &str, offset: usize, new_name: &str) -> Result<String, Diagnostic> {
+        let mut db = GraphingDatabase::default();
+        let code = ProgramSource::new(&db, src.to_owned());
+        let scratch = ProgramSource::new(&db, String::new());
+        rename(&mut db, code, scratch, offset, new_name).map(|edits| apply_edits(src, &edits))
+    }
+
+    #[test]
+    fn variable(){
+        let out = renamed("let a = 1; let b = a + { a };", 4, "c").unwrap();
+        assert_eq!(out, "let c = 1; let b = c + { c };");
+    }
+    #[test]
+    fn from_reference(){
+        let out = renamed("let a = 1; let b = a;", 19, "value").unwrap();
+        assert_eq!(out, "let value = 1; let b = value;");
+    }
+    #[test]
+    fn all_clauses(){
+        let out = renamed("fn fib(0) = 1; fn fib(1) = 1; fn fib(x) = fib(x - 1) + fib(x - 2);", 3, "f").unwrap();
+        assert_eq!(out, "fn f(0) = 1; fn f(1) = 1; fn f(x) = f(x - 1) + f(x - 2);");
+    }
+    #[test]
+    fn parameter_only_in_clause(){
+        let out = renamed("fn foo(x) = x; fn bar(x) = x;", 7, "y").unwrap();
+        assert_eq!(out, "fn foo(y) = y; fn bar(x) = x;");
+    }
+    #[test]
+    fn captured_by_inner_binding(){
+        let err = renamed("let a = 1; let y = { let b = 2; a + b };", 4, "b").unwrap_err();
+        assert_eq!(err.span, Span::new(32, 33));
+        assert_eq!(err.notes[0].span, Some(Span::new(25, 26)));
+    }
+    #[test]
+    fn captures_outer_reference(){
+        let err = renamed("let b = 1; let y = { let a = 2; a + b };", 25, "b").unwrap_err();
+        assert_eq!(err.span, Span::new(36, 37));
+    }
+    #[test]
+    fn merges_functions(){
+        let err = renamed("fn foo(x) = x; fn bar(x) = x;", 3, "bar").unwrap_err();
+        assert_eq!(err.notes[0].span, Some(Span::new(18, 21)));
+    }
+    #[test]
+    fn shadowing_is_allowed(){
+        let out = renamed("let a = 1; let b = 2; let c = b;", 15, "a").unwrap();
+        assert_eq!(out, "let a = 1; let a = 2; let c = a;");
+    }
+    #[test]
+    fn invalid_name(){
+        assert!(renamed("let a = 1;", 4, "let").is_err());
+        assert!(renamed("let a = 1;", 4, "a b").is_err());
+    }
+}
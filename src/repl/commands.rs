@@ -0,0 +1,318 @@
+use std::{fs, path::Path};
+
+use crate::{plot::{sample_function, terminal, DEFAULT_RANGE}, diagnostics::{syntax_errors, Diagnostic}, lexer::{lex_source, Token}, parser::{compile_source, parse_expression, serialize::ProgramNode}, spanned::Span, symbols::{create_scope_parent_table, create_symbol_table, resolve::{resolve_program, Resolution}, ScopeId}, GraphingDatabase, ParseError, ProgramSource};
+
+use super::session::Session;
+
+pub const HELP: &str = "\
+:type <expr>    show the inferred type of an expression
+:ast <expr>     show the syntax tree of an expression
+:tokens <src>   show the tokens of the source
+:symbols        show the names defined in every scope
+:scopes         show the tree of scopes
+:plot <f> [min max]  plot a function of one argument, from -10 to 10 by default
+:save <file>    write the live definitions to a file, as json when it ends in .json
+:load <file>    add the definitions of a file written by :save
+:undo           undo the last change to the definitions
+:reset          forget every definition
+:help           show this message
+";
+
+/// a line starting with `:`, handled by the repl instead of being added to the session
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Command {
+    Type(String),
+    Ast(String),
+    Tokens(String),
+    Symbols,
+    Scopes,
+    Plot(String),
+    Save(String),
+    /// loading reports like an input, so it is handled by the repl
+    Load(String),
+    Undo,
+    Reset,
+    Help,
+}
+impl Command {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim();
+        let (name, argument) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let argument = argument.trim().to_owned();
+        let with_argument = |command: fn(String) -> Self| if argument.is_empty() {
+            Err(format!("usage: {name} <input>"))
+        } else {
+            Ok(command(argument.clone()))
+        };
+        let without_argument = |command: Self| if argument.is_empty() {
+            Ok(command)
+        } else {
+            Err(format!("{name} takes no input"))
+        };
+        match name {
+            ":type" | ":t" => with_argument(Self::Type),
+            ":ast" => with_argument(Self::Ast),
+            ":tokens" => with_argument(Self::Tokens),
+            ":symbols" => without_argument(Self::Symbols),
+            ":scopes" => without_argument(Self::Scopes),
+            ":plot" => with_argument(Self::Plot),
+            ":save" => with_argument(Self::Save),
+            ":load" => with_argument(Self::Load),
+            ":undo" => without_argument(Self::Undo),
+            ":reset" => without_argument(Self::Reset),
+            ":help" | ":h" => without_argument(Self::Help),
+            _ => Err(format!("unknown command `{name}`, see :help")),
+        }
+    }
+    /// the text the spans of the diagnostics refer to
+    pub fn input(&self) -> &str {
+        match self {
+            Self::Type(x) | Self::Ast(x) | Self::Tokens(x) => x,
+            _ => "",
+        }
+    }
+    /// runs the command, colour is only used for plots
+    pub fn execute(&self, session: &mut Session, color: bool) -> Result<String, Vec<Diagnostic>> {
+        match self {
+            Self::Type(x) => session.expression_type(x).map(|ty| format!("{ty}\n")),
+            Self::Ast(x) => {
+                let code = session.scratch(x.clone());
+                ast(session.db(), code)
+            },
+            Self::Tokens(x) => {
+                let code = session.scratch(x.clone());
+                Ok(tokens(session.db(), code))
+            },
+            Self::Symbols => Ok(symbols(session.db(), session.source())),
+            Self::Scopes => Ok(scopes(session.db(), session.source())),
+            Self::Plot(x) => Ok(plot(session, x, color)),
+            Self::Save(path) => Ok(save(session, Path::new(path))),
+            Self::Load(_) => unreachable!("loading is handled by the repl"),
+            Self::Undo => Ok(match session.undo() {
+                Some(recomputed) => recomputed_message(&recomputed),
+                None => "nothing to undo\n".to_owned(),
+            }),
+            Self::Reset => {
+                session.reset();
+                Ok(String::new())
+            },
+            Self::Help => Ok(HELP.to_owned()),
+        }
+    }
+}
+
+/// whether the file holds a session saved as json
+pub fn is_json(path: &Path) -> bool {
+    path.extension().is_some_and(|x| x == "json")
+}
+
+fn plot(session: &Session, argument: &str, color: bool) -> String {
+    let parts = argument.split_whitespace().collect::<Vec<_>>();
+    let range = match parts[1..] {
+        [] => DEFAULT_RANGE,
+        [min, max] => match (min.parse::<f64>(), max.parse::<f64>()) {
+            (Ok(min), Ok(max)) if min.is_finite() && max.is_finite() && min < max => (min, max),
+            _ => return format!("`{min} {max}` is not a range, the start has to be below the end\n"),
+        },
+        _ => return "usage: :plot <f> [min max]\n".to_owned(),
+    };
+    let (width, height) = terminal::DEFAULT_SIZE;
+    // two samples for every column of braille dots
+    match sample_function(session.db(), session.source(), parts[0], range, width * 4) {
+        Ok(lines) => terminal::render(&[lines], range, None, width, height, false, &Default::default()).render(color),
+        Err(message) => format!("{message}\n"),
+    }
+}
+
+fn save(session: &Session, path: &Path) -> String {
+    let text = if is_json(path) {
+        serde_json::to_string_pretty(&session.save_json()).expect("sessions serialize to json") + "\n"
+    } else {
+        session.save()
+    };
+    match fs::write(path, text) {
+        Ok(()) => {
+            let count = session.names().len();
+            let names = if count == 1 { "name" } else { "names" };
+            format!("saved {count} {names} to {}\n", path.display())
+        },
+        Err(err) => format!("unable to write {}: {err}\n", path.display()),
+    }
+}
+
+/// lists the definitions which were evaluated again
+pub fn recomputed_message(names: &[String]) -> String {
+    if names.is_empty() {
+        String::new()
+    } else {
+        format!("recomputed {}\n", names.join(", "))
+    }
+}
+
+/// the syntax tree of the source parsed as a single expression
+pub fn ast(db: &GraphingDatabase, code: ProgramSource) -> Result<String, Vec<Diagnostic>> {
+    let parsed = parse_expression(db, code);
+    let mut errors = lex_source(db, code).tokens(db).iter()
+        .filter(|x| x.0 == Token::Error)
+        .map(|x| Diagnostic::error(x.1, "invalid token"))
+        .collect::<Vec<_>>();
+    errors.extend(parse_expression::accumulated::<ParseError>(db, code).into_iter()
+        .map(|x| Diagnostic::error(Span::new(x.start, x.end), x.message.clone()))
+    );
+    match parsed {
+        Some(expression) if errors.is_empty() => Ok(salsa::attach(db, || format!("{:#?}\n", *expression))),
+        _ => Err(errors),
+    }
+}
+
+/// the syntax tree of every statement of the program
+pub fn program_ast(db: &GraphingDatabase, source: ProgramSource) -> Result<String, Vec<Diagnostic>> {
+    let errors = syntax_errors(db, source);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    let program = compile_source(db, source);
+    Ok(salsa::attach(db, || program.statements(db).iter().map(|x| format!("{x:#?}\n")).collect()))
+}
+
+/// the syntax tree of the program as a single line of json, see [`ProgramNode`]
+pub fn program_json(db: &GraphingDatabase, source: ProgramSource) -> Result<String, Vec<Diagnostic>> {
+    let errors = syntax_errors(db, source);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    let node = ProgramNode::new(db, compile_source(db, source));
+    Ok(serde_json::to_string(&node).expect("syntax trees serialize to json") + "\n")
+}
+
+/// every token of the source with its span
+pub fn tokens(db: &GraphingDatabase, code: ProgramSource) -> String {
+    lex_source(db, code).tokens(db).iter()
+        .map(|(token, span)| format!("{}..{} {token:?}\n", span.start, span.end))
+        .collect()
+}
+
+/// the names bound directly in each scope
+pub fn symbols(db: &GraphingDatabase, source: ProgramSource) -> String {
+    let program = compile_source(db, source);
+    let resolution = resolve_program(db, program);
+    let mut out = String::new();
+    for (scope, node) in create_symbol_table(db, program).items(db) {
+        out.push_str(&scope_name(db, resolution, *scope));
+        out.push('\n');
+        for function in node.functions(db) {
+            out.push_str(&format!("    fn {}\n", function.text(db)));
+        }
+        for variable in node.variables(db) {
+            out.push_str(&format!("    {}\n", variable.text(db)));
+        }
+    }
+    out
+}
+
+/// the scopes as a tree with every scope below its parent
+pub fn scopes(db: &GraphingDatabase, source: ProgramSource) -> String {
+    let program = compile_source(db, source);
+    let resolution = resolve_program(db, program);
+    let table = create_scope_parent_table(db, program).table(db);
+    let mut out = String::new();
+    let mut stack = vec![(ScopeId::Program(program), 0)];
+    while let Some((scope, depth)) = stack.pop() {
+        out.push_str(&"    ".repeat(depth));
+        out.push_str(&scope_name(db, resolution, scope));
+        out.push('\n');
+        // pushed in reverse so the children are printed in source order
+        stack.extend(table.iter().rev().filter(|x| x.1 == scope).map(|x| (x.0, depth + 1)));
+    }
+    out
+}
+
+fn scope_name<'db>(db: &'db GraphingDatabase, resolution: Resolution<'db>, scope: ScopeId<'db>) -> String {
+    let span = resolution.scopes(db).iter()
+        .find(|x| x.0 == scope)
+        .map(|x| format!(" {}..{}", x.1.start, x.1.end))
+        .unwrap_or_default();
+    match scope {
+        ScopeId::Program(_) => "program".to_owned(),
+        ScopeId::Function(x) => format!("fn {}{span}", x.name(db).text(db)),
+        ScopeId::Block(_) => format!("block{span}"),
+        ScopeId::Plot(x) => format!("plot {}{span}", x.variables(db).map(|x| x.text(db).clone()).collect::<Vec<_>>().join(", ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(src: &str) -> Session {
+        let mut session = Session::default();
+        session.submit(src);
+        session
+    }
+
+    #[test]
+    fn parse(){
+        assert_eq!(Command::parse(":type a + 1"), Ok(Command::Type("a + 1".to_owned())));
+        assert_eq!(Command::parse(" :help "), Ok(Command::Help));
+        assert!(Command::parse(":type").is_err());
+        assert!(Command::parse(":reset now").is_err());
+        assert!(Command::parse(":plot").is_err());
+    }
+    #[test]
+    fn type_of(){
+        let mut session = session("fn half(x: f32) -> f32 = x / 2;");
+        assert_eq!(Command::parse(":type half(3)").unwrap().execute(&mut session, false), Ok("f32\n".to_owned()));
+        let errors = Command::parse(":type half(y)").unwrap().execute(&mut session, false).unwrap_err();
+        assert_eq!(errors[0].span, Span::new(5, 6));
+    }
+    #[test]
+    fn ast_and_tokens(){
+        let mut session = Session::default();
+        let out = Command::parse(":ast a * 2").unwrap().execute(&mut session, false).unwrap();
+        assert!(out.starts_with("Binary(\n"));
+        assert!(out.contains("text: \"a\""));
+        assert_eq!(Command::parse(":ast a *").unwrap().execute(&mut session, false).unwrap_err().len(), 1);
+        let tokens = Command::parse(":tokens let a = 1;").unwrap().execute(&mut session, false);
+        assert_eq!(tokens.unwrap(), "0..3 Let\n4..5 Identifier(\"a\")\n6..7 Equals\n8..9 Int(1)\n9..10 Semicolon\n");
+        let db = GraphingDatabase::default();
+        let program = program_ast(&db, ProgramSource::new(&db, "let a = 1; fn f(x) = x;".to_owned())).unwrap();
+        assert!(program.starts_with("Variable(\n"));
+        assert!(program.contains("Function(\n"));
+        assert!(program_ast(&db, ProgramSource::new(&db, "let a = ;".to_owned())).is_err());
+        let json = program_json(&db, ProgramSource::new(&db, "let a = 1;".to_owned())).unwrap();
+        assert!(json.starts_with("{\"statements\":[{\"span\":{\"start\":4,\"end\":9}"));
+        assert!(json.ends_with("}\n") && json.lines().count() == 1);
+    }
+    #[test]
+    fn symbols_and_scopes(){
+        let session = session("let a = 1;\nfn foo(x) = { let y = x; y };");
+        let symbols = symbols(session.db(), session.source());
+        assert_eq!(symbols, "block 23..39\n    y\nfn foo 14..39\n    x\nprogram\n    fn foo\n    a\n");
+        let scopes = scopes(session.db(), session.source());
+        assert_eq!(scopes, "program\n    fn foo 14..39\n        block 23..39\n");
+    }
+    #[test]
+    fn plot(){
+        let mut session = session("fn sq(x) = x * x;");
+        let out = Command::parse(":plot sq").unwrap().execute(&mut session, false).unwrap();
+        assert_eq!(out.lines().count(), terminal::DEFAULT_SIZE.1);
+        assert!(out.starts_with("100┤"));
+        assert!(out.lines().last().unwrap().contains("-10"));
+        let out = Command::parse(":plot sq 0 1").unwrap().execute(&mut session, false).unwrap();
+        assert!(out.starts_with("1.0┤"));
+        assert_eq!(Command::parse(":plot sq 1 0").unwrap().execute(&mut session, false), Ok("`1 0` is not a range, the start has to be below the end\n".to_owned()));
+        assert_eq!(Command::parse(":plot sq -inf 1").unwrap().execute(&mut session, false), Ok("`-inf 1` is not a range, the start has to be below the end\n".to_owned()));
+        assert_eq!(Command::parse(":plot pi").unwrap().execute(&mut session, false), Ok("`pi` is not a function\n".to_owned()));
+    }
+    #[test]
+    fn reset_and_undo(){
+        let mut session = session("let a = 1;");
+        Command::Reset.execute(&mut session, false).unwrap();
+        assert_eq!(session.text(), "");
+        // the definition is the same as when it was last evaluated so nothing is recomputed
+        assert_eq!(Command::Undo.execute(&mut session, false), Ok(String::new()));
+        assert_eq!(session.text(), "let a = 1;");
+        Command::Undo.execute(&mut session, false).unwrap();
+        assert_eq!(Command::Undo.execute(&mut session, false), Ok("nothing to undo\n".to_owned()));
+    }
+}
@@ -0,0 +1,206 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+
+/// what the terminal should do after a key press
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum EditorAction {
+    /// the line changed or the cursor moved, redraw it
+    Redraw,
+    /// the line was accepted
+    Submit(String),
+    /// tab was pressed, complete the text before the cursor
+    Complete,
+    /// the line was thrown away with ctrl-c
+    Cancel,
+    /// ctrl-d on an empty line
+    Exit,
+}
+
+/// a single line of input with a cursor and a history to scroll through
+///
+/// the editor only tracks state, drawing the line is left to the terminal so the editing can be tested without one
+#[derive(Debug, Default)]
+pub struct LineEditor {
+    line: Vec<char>,
+    /// position of the cursor in characters
+    cursor: usize,
+    history: Vec<String>,
+    /// the history entry shown, None while editing a new line
+    history_index: Option<usize>,
+    /// the new line kept aside while scrolling through the history
+    draft: Vec<char>,
+}
+impl LineEditor {
+    pub fn with_history(history: Vec<String>) -> Self {
+        Self { history, ..Self::default() }
+    }
+    pub fn line(&self) -> String {
+        self.line.iter().collect()
+    }
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+    pub fn before_cursor(&self) -> String {
+        self.line[..self.cursor].iter().collect()
+    }
+    /// inserts the text at the cursor and moves the cursor after it
+    pub fn insert(&mut self, text: &str) {
+        for c in text.chars() {
+            self.line.insert(self.cursor, c);
+            self.cursor += 1;
+        }
+    }
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+    pub fn handle(&mut self, key: KeyEvent) -> EditorAction {
+        if key.kind == KeyEventKind::Release {
+            return EditorAction::Redraw;
+        }
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Char('c') if control => {
+                self.reset();
+                return EditorAction::Cancel;
+            },
+            KeyCode::Char('d') if control => {
+                if self.line.is_empty() {
+                    return EditorAction::Exit;
+                }
+                self.delete();
+            },
+            KeyCode::Char('a') if control => self.cursor = 0,
+            KeyCode::Char('e') if control => self.cursor = self.line.len(),
+            KeyCode::Char(c) => self.insert(c.encode_utf8(&mut [0; 4])),
+            KeyCode::Tab => return EditorAction::Complete,
+            KeyCode::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.line.remove(self.cursor);
+            },
+            KeyCode::Delete => self.delete(),
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(self.line.len()),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.line.len(),
+            KeyCode::Up => self.history_up(),
+            KeyCode::Down => self.history_down(),
+            KeyCode::Enter => {
+                let line = self.line();
+                if !line.trim().is_empty() && self.history.last() != Some(&line) {
+                    self.history.push(line.clone());
+                }
+                self.reset();
+                return EditorAction::Submit(line);
+            },
+            _ => {}
+        }
+        EditorAction::Redraw
+    }
+    fn delete(&mut self) {
+        if self.cursor < self.line.len() {
+            self.line.remove(self.cursor);
+        }
+    }
+    fn reset(&mut self) {
+        self.line.clear();
+        self.cursor = 0;
+        self.history_index = None;
+        self.draft.clear();
+    }
+    fn show(&mut self, line: Vec<char>) {
+        self.cursor = line.len();
+        self.line = line;
+    }
+    fn history_up(&mut self) {
+        let index = match self.history_index {
+            Some(0) => return,
+            Some(x) => x - 1,
+            None if self.history.is_empty() => return,
+            None => {
+                self.draft = std::mem::take(&mut self.line);
+                self.history.len() - 1
+            },
+        };
+        self.history_index = Some(index);
+        self.show(self.history[index].chars().collect());
+    }
+    fn history_down(&mut self) {
+        let Some(index) = self.history_index else { return };
+        if index + 1 < self.history.len() {
+            self.history_index = Some(index + 1);
+            self.show(self.history[index + 1].chars().collect());
+        } else {
+            self.history_index = None;
+            let draft = std::mem::take(&mut self.draft);
+            self.show(draft);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+    fn control(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL)
+    }
+    fn type_text(editor: &mut LineEditor, text: &str) {
+        text.chars().for_each(|c| { editor.handle(key(KeyCode::Char(c))); });
+    }
+
+    #[test]
+    fn cursor_movement(){
+        let mut editor = LineEditor::default();
+        type_text(&mut editor, "let = 1;");
+        (0..5).for_each(|_| { editor.handle(key(KeyCode::Left)); });
+        type_text(&mut editor, " a");
+        assert_eq!(editor.line(), "let a = 1;");
+        editor.handle(key(KeyCode::Home));
+        editor.handle(key(KeyCode::Delete));
+        assert_eq!(editor.cursor(), 0);
+        editor.handle(key(KeyCode::End));
+        editor.handle(key(KeyCode::Backspace));
+        editor.handle(key(KeyCode::Right));
+        assert_eq!(editor.line(), "et a = 1");
+        assert_eq!(editor.cursor(), 8);
+        editor.handle(key(KeyCode::Left));
+        assert_eq!(editor.handle(key(KeyCode::Tab)), EditorAction::Complete);
+        assert_eq!(editor.before_cursor(), "et a = ");
+        editor.insert("b + ");
+        assert_eq!(editor.handle(key(KeyCode::Enter)), EditorAction::Submit("et a = b + 1".to_owned()));
+        assert_eq!(editor.line(), "");
+    }
+    #[test]
+    fn history(){
+        let mut editor = LineEditor::with_history(vec!["let a = 1;".to_owned()]);
+        type_text(&mut editor, "a * 2");
+        editor.handle(key(KeyCode::Enter));
+        type_text(&mut editor, "dra");
+        editor.handle(key(KeyCode::Up));
+        assert_eq!(editor.line(), "a * 2");
+        editor.handle(key(KeyCode::Up));
+        editor.handle(key(KeyCode::Up));
+        assert_eq!(editor.line(), "let a = 1;");
+        editor.handle(key(KeyCode::Down));
+        editor.handle(key(KeyCode::Down));
+        assert_eq!(editor.line(), "dra");
+        assert_eq!(editor.cursor(), 3);
+        editor.handle(key(KeyCode::Enter));
+        editor.handle(key(KeyCode::Enter));
+        assert_eq!(editor.history(), ["let a = 1;", "a * 2", "dra"]);
+    }
+    #[test]
+    fn control_keys(){
+        let mut editor = LineEditor::default();
+        type_text(&mut editor, "abc");
+        assert_eq!(editor.handle(control('c')), EditorAction::Cancel);
+        assert_eq!(editor.line(), "");
+        assert_eq!(editor.handle(control('d')), EditorAction::Exit);
+        type_text(&mut editor, "ab");
+        editor.handle(control('a'));
+        assert_eq!(editor.handle(control('d')), EditorAction::Redraw);
+        assert_eq!(editor.line(), "b");
+    }
+}
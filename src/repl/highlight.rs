@@ -0,0 +1,108 @@
+use crossterm::style::{Attribute, Color, ContentStyle, Stylize};
+use logos::Logos;
+
+use crate::{lexer::Token, spanned::Span};
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum TokenClass {
+    Keyword,
+    Literal,
+    Operator,
+    Identifier,
+    Punctuation,
+    Comment,
+    Error,
+}
+impl TokenClass {
+    pub fn of(token: &Token) -> Self {
+        match token {
+            Token::Let | Token::Fn | Token::If | Token::Else | Token::Plot | Token::Plot3d | Token::For | Token::In | Token::With
+                | Token::To | Token::Polar | Token::Slider => Self::Keyword,
+            Token::Int(_) | Token::Float(_) | Token::True | Token::False | Token::String(_) => Self::Literal,
+            Token::Identifier(_) | Token::Wildcard => Self::Identifier,
+            Token::Equals | Token::DoubleEquals | Token::NotEquals | Token::GreaterEquals | Token::LessEquals
+                | Token::Ampersand | Token::Pipe | Token::GreaterThan | Token::LessThan
+                | Token::Plus | Token::Minus | Token::Star | Token::Slash | Token::Percent | Token::Bang | Token::Caret
+                | Token::DotDot => Self::Operator,
+            Token::LParen | Token::RParen | Token::LBrace | Token::RBrace | Token::LBracket | Token::RBracket
+                | Token::Comma | Token::Semicolon | Token::DPoint => Self::Punctuation,
+            Token::Comment(_) => Self::Comment,
+            Token::Error => Self::Error,
+        }
+    }
+    fn style(self) -> ContentStyle {
+        let style = ContentStyle::new();
+        match self {
+            Self::Keyword => style.magenta().bold(),
+            Self::Literal => style.yellow(),
+            Self::Operator => style.cyan(),
+            Self::Identifier => style,
+            Self::Punctuation => style.dark_grey(),
+            Self::Comment => style.with(Color::DarkGreen).italic(),
+            Self::Error => style.white().on_red(),
+        }
+    }
+}
+
+/// whether output to a terminal should be coloured, see https://no-color.org
+pub fn color_enabled(is_terminal: bool) -> bool {
+    is_terminal && std::env::var_os("NO_COLOR").is_none_or(|x| x.is_empty())
+}
+
+/// the line with every token coloured by its class, the names covered by the unresolved spans are underlined
+///
+/// the visible text is unchanged so the cursor position can be computed from the plain line
+pub fn highlight(line: &str, unresolved: &[Span]) -> String {
+    let mut out = String::new();
+    let mut last = 0;
+    for (token, span) in Token::lexer(line).spanned() {
+        out.push_str(&line[last..span.start]);
+        last = span.end;
+        let token = token.unwrap_or(Token::Error);
+        let mut style = TokenClass::of(&token).style();
+        if unresolved.iter().any(|x| x.start == span.start && x.end == span.end) {
+            style = style.attribute(Attribute::Underlined);
+        }
+        out.push_str(&style.apply(&line[span]).to_string());
+    }
+    out.push_str(&line[last..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// removes the escape sequences, leaving the visible text
+    fn visible(text: &str) -> String {
+        let mut out = String::new();
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            if c == '\x1b' {
+                chars.by_ref().find(|x| x.is_ascii_alphabetic());
+            } else {
+                out.push(c);
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn classes(){
+        let classes = Token::lexer("let a = 1.5 $ // c").map(|x| TokenClass::of(&x.unwrap_or(Token::Error))).collect::<Vec<_>>();
+        assert_eq!(classes, vec![TokenClass::Keyword, TokenClass::Identifier, TokenClass::Operator, TokenClass::Literal, TokenClass::Error, TokenClass::Comment]);
+    }
+    #[test]
+    fn keeps_text(){
+        let line = "  fn foo(x) = { x * 2 }; é";
+        let out = highlight(line, &[]);
+        assert_ne!(out, line);
+        assert_eq!(visible(&out), line);
+    }
+    #[test]
+    fn underlines_unresolved(){
+        let out = highlight("a + b", &[Span::new(4, 5)]);
+        assert!(out.ends_with(&format!("{}", ContentStyle::new().attribute(Attribute::Underlined).apply("b"))));
+        assert!(!out.starts_with(&format!("{}", ContentStyle::new().attribute(Attribute::Underlined).apply("a"))));
+    }
+}
@@ -0,0 +1,425 @@
+use std::{fs, io::{self, BufRead, IsTerminal, Write}, path::{Path, PathBuf}};
+
+use crossterm::{cursor::{self, MoveToColumn, MoveUp}, event::{self, DisableMouseCapture, EnableMouseCapture, Event}, execute, queue, terminal::{self, Clear, ClearType}};
+
+use crate::{builtins::builtin, completion::complete, diagnostics::render, lexer::open_delimiters, parser::compile_source, plot::{terminal::{draw_plot, render_plot, PlotArea, DEFAULT_SIZE}, write_file, ImageSize}, spanned::Span, symbols::resolve::resolve_program};
+
+pub mod commands;
+pub mod editor;
+pub mod highlight;
+pub mod session;
+pub mod view;
+
+use commands::{is_json, recomputed_message, Command};
+use editor::{EditorAction, LineEditor};
+use highlight::{color_enabled, highlight};
+use session::{Response, SavedSession, Session};
+use view::{action, Action, View};
+
+const PROMPT: &str = "> ";
+const CONTINUATION_PROMPT: &str = "... ";
+/// the number of history entries kept on disk
+const HISTORY_LIMIT: usize = 1000;
+
+/// what pressing tab does to the line
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum TabCompletion {
+    /// text to insert at the cursor
+    Insert(String),
+    /// the completion is ambiguous, these are the names to choose from
+    Candidates(Vec<String>),
+    None,
+}
+
+/// collects lines until the brackets are balanced and hands the complete input to the session
+#[derive(Default)]
+pub struct Repl {
+    session: Session,
+    /// the lines of an input with unbalanced brackets
+    pending: String,
+    color: bool,
+    /// the text of the plot statement drawn last, when nothing was written after it
+    shown: Option<String>,
+}
+impl Repl {
+    pub fn new(color: bool) -> Self {
+        Self { color, ..Self::default() }
+    }
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+    pub fn prompt(&self) -> &'static str {
+        if self.pending.is_empty() { PROMPT } else { CONTINUATION_PROMPT }
+    }
+    /// drops the lines of an unfinished input
+    pub fn cancel(&mut self) {
+        self.pending.clear();
+    }
+    /// the plot statement at the end of the last output, which can still be panned and zoomed
+    pub fn take_shown(&mut self) -> Option<String> {
+        self.shown.take()
+    }
+    /// feeds a single line, returns the output once the input is complete
+    pub fn line(&mut self, line: &str) -> Option<String> {
+        if self.pending.is_empty() && line.trim_start().starts_with(':') {
+            return Some(self.command(line));
+        }
+        if !self.pending.is_empty() {
+            self.pending.push('\n');
+        }
+        self.pending.push_str(line);
+        if open_delimiters(&self.pending) > 0 {
+            return None;
+        }
+        let input = std::mem::take(&mut self.pending);
+        let response = self.session.submit(&input);
+        Some(self.output("<repl>", &input, &response))
+    }
+    /// the session text followed by the pending lines and the line being typed, with the offset of that line
+    fn with_line(&self, line: &str) -> (String, usize) {
+        let mut text = self.session.text().to_owned();
+        for part in [self.pending.as_str(), line] {
+            if !part.is_empty() {
+                text.push('\n');
+                text.push_str(part);
+            }
+        }
+        let offset = text.len() - line.len();
+        (text, offset)
+    }
+    /// the names in the line which do not resolve to any definition, relative to the line
+    pub fn unresolved(&mut self, line: &str) -> Vec<Span> {
+        let (text, offset) = self.with_line(line);
+        let source = self.session.scratch(text);
+        let db = self.session.db();
+        resolve_program(db, compile_source(db, source)).references(db).iter()
+            .filter(|x| x.definition.is_none() && builtin(&x.name).is_none() && x.span.start >= offset)
+            .map(|x| Span::new(x.span.start - offset, x.span.end - offset))
+            .collect()
+    }
+    /// completes the identifier in front of the cursor using the names visible at that point of the session
+    pub fn complete(&mut self, before_cursor: &str) -> TabCompletion {
+        if !before_cursor.ends_with(|c: char| c.is_ascii_alphanumeric() || c == '_') {
+            return TabCompletion::Insert("    ".to_owned());
+        }
+        let (text, _) = self.with_line(before_cursor);
+        let offset = text.len();
+        let source = self.session.scratch(text);
+        let (span, candidates) = complete(self.session.db(), source, offset);
+        let typed = span.end - span.start;
+        let labels = candidates.into_iter().map(|x| x.label).collect::<Vec<_>>();
+        let Some(first) = labels.first() else { return TabCompletion::None };
+        let common = labels.iter().fold(first.len(), |len, x| {
+            first.bytes().zip(x.bytes()).take(len).take_while(|(a, b)| a == b).count()
+        });
+        if labels.len() == 1 || common > typed {
+            TabCompletion::Insert(first[typed..common].to_owned())
+        } else {
+            TabCompletion::Candidates(labels)
+        }
+    }
+    /// adds the definitions of a file written by `:save`, reporting them like an input
+    pub fn load(&mut self, path: &Path) -> String {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) => return format!("unable to read {}: {err}\n", path.display()),
+        };
+        if is_json(path) {
+            return match serde_json::from_str::<SavedSession>(&text) {
+                Ok(saved) => {
+                    let response = self.session.load_json(&saved);
+                    self.output(&path.display().to_string(), &saved.statements.join("\n"), &response)
+                },
+                Err(err) => format!("unable to load {}: {err}\n", path.display()),
+            };
+        }
+        let response = self.session.load(&text);
+        self.output(&path.display().to_string(), &text, &response)
+    }
+    fn command(&mut self, line: &str) -> String {
+        let command = match Command::parse(line) {
+            Ok(command) => command,
+            Err(message) => return format!("{message}\n"),
+        };
+        if let Command::Load(path) = &command {
+            return self.load(Path::new(path));
+        }
+        match command.execute(&mut self.session, self.color) {
+            Ok(out) => out,
+            Err(diagnostics) => render(&diagnostics, "<repl>", command.input(), self.color),
+        }
+    }
+    fn output(&mut self, name: &str, input: &str, response: &Response) -> String {
+        let mut out = String::new();
+        for (name, ty) in &response.definitions {
+            out.push_str(&format!("{name}: {ty}\n"));
+        }
+        out.push_str(&recomputed_message(&response.recomputed));
+        if let Some(ty) = &response.value {
+            out.push_str(&format!("{ty}\n"));
+        }
+        out.push_str(&render(&response.diagnostics, name, input, self.color));
+        let (width, height) = DEFAULT_SIZE;
+        self.shown = None;
+        for (statement, plot) in &response.plots {
+            match &plot.output {
+                Some(output) => match write_file(plot, Path::new(output), ImageSize::default()) {
+                    Ok(()) => out.push_str(&format!("plot written to {output}\n")),
+                    Err(message) => out.push_str(&format!("{message}\n")),
+                },
+                None => {
+                    out.push_str(&render_plot(plot, width, height, self.color));
+                    self.shown = Some(statement.clone());
+                },
+            }
+        }
+        out
+    }
+}
+
+/// runs the repl on the terminal, falling back to reading plain lines when stdin is not a terminal
+///
+/// the definitions of the file are loaded first, like `:load`
+pub fn run(load: Option<&Path>) -> io::Result<()> {
+    let terminal = io::stdin().is_terminal() && io::stdout().is_terminal();
+    let mut repl = Repl::new(terminal && color_enabled(true));
+    if let Some(path) = load {
+        print!("{}", repl.load(path));
+    }
+    if terminal {
+        run_terminal(repl)
+    } else {
+        run_lines(repl, io::stdin().lock(), io::stdout().lock())
+    }
+}
+
+/// reads the input line by line without echo or line editing, used for piped input
+pub fn run_lines(mut repl: Repl, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    for line in input.lines() {
+        if let Some(out) = repl.line(&line?) {
+            write!(output, "{out}")?;
+        }
+    }
+    output.flush()
+}
+
+/// restores the terminal when the repl stops, including on errors
+struct RawMode;
+impl RawMode {
+    fn enable() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        Ok(Self)
+    }
+}
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn run_terminal(mut repl: Repl) -> io::Result<()> {
+    let path = history_path();
+    let history = path.as_ref()
+        .and_then(|x| fs::read_to_string(x).ok())
+        .map(|x| x.lines().map(str::to_owned).collect())
+        .unwrap_or_default();
+    let mut editor = LineEditor::with_history(history);
+    let mut stdout = io::stdout();
+    let raw = RawMode::enable()?;
+    draw(&mut stdout, &mut repl, &editor)?;
+    loop {
+        let Event::Key(key) = event::read()? else { continue };
+        match editor.handle(key) {
+            EditorAction::Redraw => {},
+            EditorAction::Submit(line) => {
+                write!(stdout, "\r\n")?;
+                if let Some(out) = repl.line(&line) {
+                    // raw mode does not return the cursor to the start of the line
+                    write!(stdout, "{}", out.replace('\n', "\r\n"))?;
+                    if let Some(plot) = repl.take_shown() {
+                        explore(&mut stdout, &mut repl, &plot)?;
+                    }
+                }
+            },
+            EditorAction::Complete => match repl.complete(&editor.before_cursor()) {
+                TabCompletion::Insert(text) => editor.insert(&text),
+                TabCompletion::Candidates(names) => write!(stdout, "\r\n{}\r\n", names.join("  "))?,
+                TabCompletion::None => {},
+            },
+            EditorAction::Cancel => {
+                repl.cancel();
+                write!(stdout, "^C\r\n")?;
+            },
+            EditorAction::Exit => break,
+        }
+        draw(&mut stdout, &mut repl, &editor)?;
+    }
+    write!(stdout, "\r\n")?;
+    drop(raw);
+    if let Some(path) = path {
+        let history = editor.history();
+        let kept = &history[history.len().saturating_sub(HISTORY_LIMIT)..];
+        fs::write(path, kept.iter().map(|x| format!("{x}\n")).collect::<String>())?;
+    }
+    Ok(())
+}
+
+fn draw(stdout: &mut impl Write, repl: &mut Repl, editor: &LineEditor) -> io::Result<()> {
+    let prompt = repl.prompt();
+    let mut line = editor.line();
+    let cursor = prompt.chars().count() + editor.cursor();
+    if repl.color {
+        line = highlight(&line, &repl.unresolved(&line));
+    }
+    queue!(stdout, MoveToColumn(0), Clear(ClearType::CurrentLine))?;
+    write!(stdout, "{prompt}{line}")?;
+    execute!(stdout, MoveToColumn(cursor as u16))
+}
+
+/// pans and zooms the plot which was just drawn and changes the sliders until q is pressed, drawing it again in place
+/// after every change
+///
+/// the line below the plot shows the point under the mouse, the selected slider and the keys, or why sampling the plot
+/// failed
+fn explore(stdout: &mut impl Write, repl: &mut Repl, statement: &str) -> io::Result<()> {
+    let (width, height) = DEFAULT_SIZE;
+    let Ok(plot) = repl.session.plot(statement) else { return Ok(()) };
+    let (text, area) = draw_plot(&plot, width, height, repl.color);
+    let Some(mut area) = area else { return Ok(()) };
+    let mut view = View::new(area.x_range, area.y_range).with_scales(plot.axes.scales()).with_sliders(repl.session.sliders());
+    // the plot is already on the screen, right above the cursor
+    let mut drawn = text.lines().count();
+    let mut status = view.status();
+    execute!(stdout, EnableMouseCapture)?;
+    loop {
+        queue!(stdout, MoveToColumn(0), Clear(ClearType::CurrentLine))?;
+        write!(stdout, "{status}")?;
+        stdout.flush()?;
+        // where the graphs are on the screen, the status line is right below the plot
+        // terminals which do not report the cursor only lose the point under the mouse
+        let screen = cursor::position().ok().map(|(_, row)| PlotArea { top: (row as usize + area.top).saturating_sub(drawn), ..area });
+        let Some(action) = action(&event::read()?, screen.as_ref()) else { continue };
+        if action == Action::Quit {
+            break;
+        }
+        if view.apply(action) {
+            for slider in &view.sliders {
+                repl.session.set_slider(&slider.name, slider.value);
+            }
+            match repl.session.plot_in(statement, view.window()) {
+                Ok(plot) => {
+                    let (text, new_area) = draw_plot(&plot, width, height, repl.color);
+                    queue!(stdout, MoveToColumn(0), MoveUp(drawn as u16), Clear(ClearType::FromCursorDown))?;
+                    write!(stdout, "{}", text.replace('\n', "\r\n"))?;
+                    drawn = text.lines().count();
+                    area = new_area.unwrap_or(area);
+                },
+                Err(diagnostics) => {
+                    status = diagnostics.first().map_or_else(String::new, |x| x.message.clone());
+                    continue;
+                },
+            }
+        }
+        status = view.status();
+    }
+    execute!(stdout, DisableMouseCapture, MoveToColumn(0), Clear(ClearType::CurrentLine))
+}
+
+fn history_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|x| PathBuf::from(x).join(".graphing_language_history"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn continuation(){
+        let mut repl = Repl::default();
+        assert_eq!(repl.line("fn foo(x) = {"), None);
+        assert_eq!(repl.prompt(), CONTINUATION_PROMPT);
+        assert_eq!(repl.line("    x * 2"), None);
+        assert_eq!(repl.line("};").as_deref(), Some("foo: fn(_) -> _\n"));
+        assert_eq!(repl.prompt(), PROMPT);
+        assert_eq!(repl.session().text(), "fn foo(x) = {\n    x * 2\n};");
+    }
+    #[test]
+    fn piped_lines(){
+        let mut out = vec![];
+        run_lines(Repl::default(), "let a = (1 +\n 2);\na * 2.0\n".as_bytes(), &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "a: {integer}\n{float}\n");
+    }
+    #[test]
+    fn tab_completion(){
+        let mut repl = Repl::default();
+        repl.line("let value = 1; fn various(x) = x;");
+        assert_eq!(repl.complete("let b = val"), TabCompletion::Insert("ue".to_owned()));
+        assert_eq!(repl.complete("let b = va"), TabCompletion::Candidates(vec!["various".to_owned(), "value".to_owned()]));
+        assert_eq!(repl.complete("let b = asi"), TabCompletion::Insert("n".to_owned()));
+        assert_eq!(repl.complete("let b = co"), TabCompletion::Insert("s".to_owned()));
+        assert_eq!(repl.complete("let b = q"), TabCompletion::None);
+        assert_eq!(repl.complete("let b = "), TabCompletion::Insert("    ".to_owned()));
+    }
+    #[test]
+    fn unresolved(){
+        let mut repl = Repl::default();
+        repl.line("let a = 1;");
+        assert_eq!(repl.unresolved("let b = a + c * sin(pi);"), vec![Span::new(12, 13)]);
+        assert_eq!(repl.unresolved("let b = a + c"), vec![]);
+    }
+    #[test]
+    fn commands(){
+        let mut repl = Repl::default();
+        repl.line("let a = 1.0;");
+        assert_eq!(repl.line(":type a * 2").as_deref(), Some("{float}\n"));
+        assert_eq!(repl.line(":what").as_deref(), Some("unknown command `:what`, see :help\n"));
+        repl.line("let b = a;");
+        assert_eq!(repl.line("let a = 2;").as_deref(), Some("a: {integer}\nrecomputed b\n"));
+        repl.line(":reset");
+        assert!(repl.line(":type a").unwrap().contains("cannot find `a` in this scope"));
+    }
+    #[test]
+    fn plot_statements(){
+        let mut repl = Repl::default();
+        let out = repl.line("let k = 2; plot k * x for x in 0..1;").unwrap();
+        assert!(out.starts_with("k: {integer}\n2.0┤"));
+        assert_eq!(out.lines().count(), 1 + DEFAULT_SIZE.1);
+        // the plot at the end of the output can be panned and zoomed
+        assert_eq!(repl.take_shown().as_deref(), Some("plot k * x for x in 0..1;"));
+        assert_eq!(repl.take_shown(), None);
+        // plots are not kept, so they are not drawn again or saved
+        assert_eq!(repl.session().text(), "let k = 2;");
+        assert!(repl.line("plot y for x in 0..1;").unwrap().contains("cannot find `y` in this scope"));
+        assert!(repl.line("plot x for x in k..0;").unwrap().contains("the range 2..0 is empty"));
+        assert_eq!(repl.session().text(), "let k = 2;");
+        let path = std::env::temp_dir().join(format!("graphing_language_plot_{}.svg", std::process::id()));
+        let out = repl.line(&format!("plot x for x in 0..k to {:?};", path.display().to_string())).unwrap();
+        assert_eq!(out, format!("plot written to {}\n", path.display()));
+        assert_eq!(repl.take_shown(), None);
+        assert!(fs::read_to_string(&path).unwrap().starts_with("<svg"));
+        fs::remove_file(path).unwrap();
+        assert_eq!(repl.line("plot x for x in 0..k to \"plot.pdf\";").as_deref(), Some("cannot write a plot to plot.pdf, the file has to end in .svg or .png\n"));
+    }
+    #[test]
+    fn save_and_load(){
+        let dir = std::env::temp_dir().join(format!("graphing_language_repl_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut repl = Repl::default();
+        repl.line("let a = 1;");
+        repl.line("let b = ;");
+        repl.line("let a = 2.5;");
+        for name in ["session.gl", "session.json"] {
+            let path = dir.join(name);
+            assert_eq!(repl.line(&format!(":save {}", path.display())), Some(format!("saved 1 name to {}\n", path.display())));
+            let mut loaded = Repl::default();
+            assert_eq!(loaded.line(&format!(":load {}", path.display())).as_deref(), Some("a: {float}\n"));
+            assert_eq!(loaded.session().text(), "let a = 2.5;");
+        }
+        assert_eq!(fs::read_to_string(dir.join("session.gl")).unwrap(), "let a = 2.5;\n");
+        assert!(repl.line(":load /nonexistent.gl").unwrap().starts_with("unable to read"));
+        repl.line("let c = 3;");
+        let path = dir.join("two.gl");
+        assert_eq!(repl.line(&format!(":save {}", path.display())), Some(format!("saved 2 names to {}\n", path.display())));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
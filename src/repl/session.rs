@@ -0,0 +1,796 @@
+use std::sync::{Arc, Mutex};
+
+use logos::Logos;
+use salsa::{plumbing::AsId, Setter};
+use serde::{Deserialize, Serialize};
+
+use crate::{diagnostics::{check_source, syntax_errors, Diagnostic}, eval::{evaluate_source_with, run_source, Evaluator, RunError, SliderState, Value}, infer::{infer_definition, InferredType}, lexer::{split_statements, Token}, parser::{compile_source, literal::Literal, pattern::Pattern, statement::Statement, Program}, parser::plot::Plot, plot::{combine_plot, sample_plot_series, SampledPlot, SampledSeries, Window}, spanned::Span, symbols::{resolve::{resolve_program, DefinitionKind, Resolution}, ScopeId}, GraphingDatabase, ProgramSource};
+
+/// the queries whose executions are recorded, to report what had to be computed again
+const OBSERVED: [&str; 2] = ["evaluate_definition", "live_series"];
+/// the name the value of a bare expression is bound to while inferring its type
+const EXPRESSION_NAME: &str = "it";
+
+/// the result of a single input
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct Response {
+    /// the top level names defined by the input with their types
+    pub definitions: Vec<(String, InferredType)>,
+    /// the type of the expression at the end of the input
+    pub value: Option<InferredType>,
+    /// earlier definitions which were evaluated again because something they depend on changed
+    pub recomputed: Vec<String>,
+    /// problems with the input, the spans are relative to the input
+    pub diagnostics: Vec<Diagnostic>,
+    /// the plot statements of the input with their text, evaluated after its definitions
+    pub plots: Vec<(String, SampledPlot)>,
+}
+
+/// a session written as json, the definitions are only written for other tools and ignored when loading
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct SavedSession {
+    /// the live statements in order
+    pub statements: Vec<String>,
+    #[serde(skip_deserializing)]
+    pub definitions: Vec<SavedDefinition>,
+}
+/// a top level name with its type and, when the session could be evaluated, its value
+#[derive(PartialEq, Debug, Clone, Serialize)]
+pub struct SavedDefinition {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+}
+
+/// identifies what a top level statement defines, a later statement with the same key replaces it
+#[derive(PartialEq, Eq, Debug, Clone)]
+enum StatementKey {
+    /// a function clause with the shape of its arguments, clauses with other literal patterns are kept
+    Function(String, Vec<String>),
+    /// the names bound by a let statement
+    Variable(Vec<String>),
+}
+impl StatementKey {
+    fn new(db: &dyn salsa::Database, statement: &Statement<'_>) -> Self {
+        match statement {
+            Statement::Function(x) => Self::Function(
+                x.name(db).text(db).clone(),
+                x.args(db).iter().map(|(pattern, _)| shape(pattern)).collect(),
+            ),
+            Statement::Variable(x) => {
+                let mut names = vec![];
+                bound_names(db, x.name(db), &mut names);
+                Self::Variable(names)
+            },
+            Statement::Plot(_) => unreachable!("plots are drawn once and never kept in the session"),
+        }
+    }
+    /// whether the statement with this key makes the statement with the other key obsolete
+    fn replaces(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Function(..), Self::Function(..)) => self == other,
+            (Self::Variable(new), Self::Variable(old)) => !old.is_empty() && old.iter().all(|x| new.contains(x)),
+            _ => false,
+        }
+    }
+    fn names(&self) -> Vec<String> {
+        match self {
+            Self::Function(name, _) => vec![name.clone()],
+            Self::Variable(names) => names.clone(),
+        }
+    }
+}
+/// whether the let statement uses an earlier definition of a name it binds, like `let a = a + 1;`
+///
+/// replacing the earlier definition would leave the name referring to itself, so the statement is added after
+/// it and shadows it instead
+fn shadows<'db>(db: &'db dyn salsa::Database, resolution: Resolution<'db>, statement: &Statement<'db>) -> bool {
+    let Statement::Variable(variable) = statement else {
+        return false;
+    };
+    let start = statement.span(db).start;
+    let body = variable.body(db).span();
+    let names = StatementKey::new(db, statement).names();
+    let definitions = resolution.definitions(db);
+    resolution.references(db).iter()
+        .filter(|x| body.start <= x.span.start && x.span.end <= body.end && names.contains(&x.name))
+        .filter_map(|x| x.definition)
+        .any(|x| definitions[x].sites.iter().all(|site| site.start < start))
+}
+/// the pattern with every binding replaced by `_`, two clauses with the same shape match the same arguments
+fn shape(pattern: &Pattern<'_>) -> String {
+    match pattern {
+        Pattern::Wildcard(_) | Pattern::Variable(_) => "_".to_owned(),
+        Pattern::Literal(x) => match &**x {
+            Literal::AbstractInt(x) => x.to_string(),
+            Literal::AbstractFloat(x) => x.to_string(),
+        },
+        Pattern::Tuple(x) => format!("({})", x.iter().map(|ell| shape(ell)).collect::<Vec<_>>().join(", ")),
+    }
+}
+fn bound_names(db: &dyn salsa::Database, pattern: &Pattern<'_>, names: &mut Vec<String>) {
+    match pattern {
+        Pattern::Variable(x) => names.push(x.text(db).clone()),
+        Pattern::Tuple(x) => x.iter().for_each(|ell| bound_names(db, ell, names)),
+        _ => {}
+    }
+}
+/// whether the source contains anything besides whitespace and comments
+fn has_tokens(source: &str) -> bool {
+    Token::lexer(source).any(|x| !matches!(x, Ok(Token::Comment(_))))
+}
+
+/// a top level statement which is part of the session
+#[derive(PartialEq, Eq, Debug, Clone)]
+struct LiveStatement {
+    text: String,
+    key: StatementKey,
+}
+
+/// the statements accepted so far, kept in a single source so only the changed statements are parsed again
+///
+/// a statement defining the same names as an earlier statement replaces it at its position, so the definitions
+/// depending on it see the new definition. a let statement using the earlier definition, like `let a = a + 1;`,
+/// shadows it instead
+pub struct Session {
+    db: GraphingDatabase,
+    source: ProgramSource,
+    statements: Vec<LiveStatement>,
+    /// the statements before each change, the last change is undone first
+    history: Vec<Vec<LiveStatement>>,
+    /// the plot shown last, kept so sampling it again only samples the plotted expressions which changed
+    live: LivePlot,
+    /// every slider set so far, also given to the live plot
+    sliders: Vec<SliderInput>,
+    /// the observed queries executed since the events were last taken with their keys
+    executed: Arc<Mutex<Vec<(&'static str, salsa::Id)>>>,
+    /// a source for text which is only looked at and never kept, like the line being typed, set again for every use
+    /// as inputs of the database are never freed
+    scratch: ProgramSource,
+}
+impl Default for Session {
+    fn default() -> Self {
+        let executed = Arc::new(Mutex::new(vec![]));
+        let db = GraphingDatabase::with_event_callback({
+            let executed = executed.clone();
+            move |event| if let salsa::EventKind::WillExecute { database_key } = event.kind
+                && let Some(query) = OBSERVED.iter().find(|x| format!("{database_key:?}").starts_with(**x))
+            {
+                executed.lock().expect("the lock is never held while panicking").push((*query, database_key.key_index()));
+            }
+        });
+        let source = ProgramSource::new(&db, String::new());
+        let live = LivePlot::new(&db, ProgramSource::new(&db, String::new()), Window::default(), vec![]);
+        let scratch = ProgramSource::new(&db, String::new());
+        Self { db, source, statements: vec![], history: vec![], live, sliders: vec![], executed, scratch }
+    }
+}
+impl Session {
+    pub fn db(&self) -> &GraphingDatabase {
+        &self.db
+    }
+    pub fn source(&self) -> ProgramSource {
+        self.source
+    }
+    pub fn text(&self) -> &str {
+        self.source.raw_text(&self.db)
+    }
+    /// the scratch source holding the text, which stays valid until the scratch source is used again
+    pub fn scratch(&mut self, text: String) -> ProgramSource {
+        self.scratch.set_raw_text(&mut self.db).to(text);
+        self.scratch
+    }
+    /// infers the type of an expression evaluated after the statements of the session
+    ///
+    /// the spans of the diagnostics are relative to the expression
+    pub fn expression_type(&mut self, expression: &str) -> Result<InferredType, Vec<Diagnostic>> {
+        let source_len = self.text().len();
+        let prefix = format!("{}\nlet {EXPRESSION_NAME} = ", self.text());
+        let code = self.scratch(format!("{prefix}{expression};"));
+        let db = &self.db;
+        let diagnostics = relative(check_source(db, code), prefix.len());
+        if !diagnostics.is_empty() {
+            return Err(diagnostics);
+        }
+        let resolution = resolve_program(db, compile_source(db, code));
+        let index = resolution.definitions(db).iter()
+            .rposition(|x| x.name == EXPRESSION_NAME && x.sites.iter().any(|x| x.start >= source_len))
+            .expect("the expression is bound by the appended let statement");
+        Ok(infer_definition(db, resolution, index))
+    }
+    /// the top level names defined by the live statements
+    pub fn names(&self) -> Vec<String> {
+        let mut out = Vec::<String>::new();
+        for name in self.statements.iter().flat_map(|x| x.key.names()) {
+            if !out.contains(&name) {
+                out.push(name);
+            }
+        }
+        out
+    }
+    /// the live statements as a source file, loading it gives the same definitions
+    pub fn save(&self) -> String {
+        self.statements.iter().map(|x| format!("{}\n", x.text)).collect()
+    }
+    /// the live statements with the type and value of every top level name
+    pub fn save_json(&self) -> SavedSession {
+        let values = run_source(&self.db, self.source).unwrap_or_default();
+        let definitions = self.names().into_iter()
+            .map(|name| SavedDefinition {
+                ty: evaluate_definition(&self.db, LiveName::new(&self.db, self.source, name.clone())).to_string(),
+                value: values.iter().rfind(|x| x.0 == name).map(|x| x.1.clone()),
+                name,
+            })
+            .collect();
+        SavedSession { statements: self.statements.iter().map(|x| x.text.clone()).collect(), definitions }
+    }
+    /// adds the statements of a saved session as a single input, so only the statements which differ from the
+    /// current ones are parsed and evaluated again
+    pub fn load(&mut self, text: &str) -> Response {
+        self.submit(text)
+    }
+    pub fn load_json(&mut self, saved: &SavedSession) -> Response {
+        self.load(&saved.statements.join("\n"))
+    }
+    /// forgets every statement
+    pub fn reset(&mut self) {
+        self.history.push(std::mem::take(&mut self.statements));
+        self.update();
+    }
+    /// restores the statements from before the last change and returns the definitions evaluated again,
+    /// None when there is nothing to undo
+    pub fn undo(&mut self) -> Option<Vec<String>> {
+        self.statements = self.history.pop()?;
+        Some(self.update())
+    }
+    /// adds the statements of the input to the session, the input is dropped when it does not parse
+    ///
+    /// text after the last `;` is a bare expression, only its type is reported and it is not added to the session
+    pub fn submit(&mut self, input: &str) -> Response {
+        let tail = split_statements(input).last()
+            .filter(|(start, _)| !input[*start..].trim_end().ends_with(';'))
+            .map(|x| x.0)
+            .unwrap_or(input.len());
+        let mut response = self.define(&input[..tail]);
+        if !response.diagnostics.is_empty() || !has_tokens(&input[tail..]) {
+            return response;
+        }
+        match self.expression_type(&input[tail..]) {
+            Ok(ty) => response.value = Some(ty),
+            Err(diagnostics) => response.diagnostics.extend(diagnostics.into_iter().map(|x| shift(x, 0, tail as isize))),
+        }
+        response
+    }
+    fn define(&mut self, input: &str) -> Response {
+        let mut response = Response::default();
+        let chunks = split_statements(input).into_iter()
+            .filter(|(start, end)| has_tokens(&input[*start..*end]))
+            .collect::<Vec<_>>();
+        if chunks.is_empty() {
+            return response;
+        }
+        // the input is checked on its own first, so the spans of the errors are relative to it
+        let previous = self.text().to_owned();
+        let offset = if previous.is_empty() { 0 } else { previous.len() + 1 };
+        let text = if previous.is_empty() { input.to_owned() } else { format!("{previous}\n{input}") };
+        self.source.set_raw_text(&mut self.db).to(text);
+        let errors = syntax_errors(&self.db, self.source);
+        if !errors.is_empty() {
+            self.source.set_raw_text(&mut self.db).to(previous);
+            response.diagnostics = relative(errors, offset);
+            return response;
+        }
+        let program = compile_source(&self.db, self.source);
+        // plots are drawn once after the definitions of the input and not kept
+        let (plots, chunks) = program.statements(&self.db).iter()
+            .filter(|x| x.span(&self.db).start >= offset)
+            .zip(chunks)
+            .partition::<Vec<_>, _>(|(statement, _)| matches!(statement, Statement::Plot(_)));
+        let plots = plots.into_iter().map(|x| x.1).collect::<Vec<_>>();
+        if chunks.is_empty() {
+            self.source.set_raw_text(&mut self.db).to(previous);
+            self.plots(input, &plots, &mut response);
+            return response;
+        }
+        let resolution = resolve_program(&self.db, program);
+        let (added, chunks) = chunks.into_iter()
+            .map(|(statement, (start, end))| ((LiveStatement {
+                text: input[start..end].trim().to_owned(),
+                key: StatementKey::new(&self.db, statement),
+            }, shadows(&self.db, resolution, statement)), (start, end)))
+            .unzip::<_, _, Vec<_>, Vec<_>>();
+
+        let mut statements = self.statements.clone();
+        // the positions of the statements of this input, these never replace each other
+        let mut placed = vec![];
+        for (statement, shadows) in added {
+            let replaced = statements.iter().enumerate()
+                .filter(|(i, x)| !shadows && !placed.contains(i) && statement.key.replaces(&x.key))
+                .map(|(i, _)| i)
+                .collect::<Vec<_>>();
+            let position = match (replaced.first(), &statement.key) {
+                (Some(first), _) => *first,
+                // new clauses are placed after the existing clauses of the function
+                (None, StatementKey::Function(name, _)) => statements.iter()
+                    .rposition(|x| matches!(&x.key, StatementKey::Function(other, _) if other == name))
+                    .map(|x| x + 1)
+                    .unwrap_or(statements.len()),
+                (None, StatementKey::Variable(_)) => statements.len(),
+            };
+            for i in replaced.iter().rev() {
+                statements.remove(*i);
+            }
+            for i in &mut placed {
+                *i -= replaced.iter().filter(|x| **x < *i).count();
+            }
+            let position = position - replaced.iter().filter(|x| **x < position).count();
+            placed.iter_mut().filter(|x| **x >= position).for_each(|x| *x += 1);
+            placed.push(position);
+            statements.insert(position, statement);
+        }
+        self.history.push(std::mem::replace(&mut self.statements, statements));
+        let recomputed = self.update();
+
+        // the start of each statement of the input in the source and in the input
+        let mut starts = vec![];
+        let mut start = 0;
+        for statement in &self.statements {
+            starts.push(start);
+            start += statement.text.len() + 1;
+        }
+        let locations = placed.iter().zip(&chunks)
+            .map(|(i, (chunk_start, _))| {
+                let chunk = &input[*chunk_start..];
+                (starts[*i], self.statements[*i].text.len(), chunk_start + chunk.len() - chunk.trim_start().len())
+            })
+            .collect::<Vec<_>>();
+        let to_input = |span: Span| locations.iter()
+            .find(|(start, len, _)| *start <= span.start && span.start <= start + len)
+            .map(|(start, _, input_start)| *input_start as isize - *start as isize);
+        for diagnostic in check_source(&self.db, self.source) {
+            if let Some(delta) = to_input(diagnostic.span) {
+                response.diagnostics.push(shift(diagnostic, 0, delta));
+            }
+        }
+        let program = compile_source(&self.db, self.source);
+        let resolution = resolve_program(&self.db, program);
+        let mut names = vec![];
+        for (i, definition) in resolution.definitions(&self.db).iter().enumerate() {
+            let top_level = definition.scope == ScopeId::Program(program) && definition.kind != DefinitionKind::Parameter;
+            if top_level && definition.sites.iter().any(|x| to_input(*x).is_some()) && !names.contains(&definition.name) {
+                names.push(definition.name.clone());
+                response.definitions.push((definition.name.clone(), infer_definition(&self.db, resolution, i)));
+            }
+        }
+        response.recomputed = recomputed.into_iter().filter(|x| !names.contains(x)).collect();
+        self.plots(input, &plots, &mut response);
+        response
+    }
+    /// draws the plots of the input, given by their range in the input
+    fn plots(&mut self, input: &str, plots: &[(usize, usize)], response: &mut Response) {
+        for (start, end) in plots {
+            match self.plot(&input[*start..*end]) {
+                Ok(plot) => response.plots.push((input[*start..*end].trim().to_owned(), plot)),
+                Err(diagnostics) => response.diagnostics.extend(diagnostics.into_iter().map(|x| shift(x, 0, *start as isize))),
+            }
+        }
+    }
+    /// evaluates a plot statement after the live statements without adding it to the session
+    ///
+    /// the spans of the diagnostics are relative to the plot
+    pub fn plot(&mut self, plot: &str) -> Result<SampledPlot, Vec<Diagnostic>> {
+        self.plot_in(plot, Window::default())
+    }
+    /// evaluates a plot statement like [`Session::plot`] over the ranges of the window, used to pan and zoom
+    ///
+    /// the plotted expressions are only sampled again when the statements, the window or the sliders they use changed
+    pub fn plot_in(&mut self, plot: &str, window: Window) -> Result<SampledPlot, Vec<Diagnostic>> {
+        let prefix = format!("{}\n", self.text());
+        let code = format!("{prefix}{plot}");
+        let source = self.live.source(&self.db);
+        if *source.raw_text(&self.db) != code {
+            source.set_raw_text(&mut self.db).to(code);
+        }
+        if self.live.window(&self.db) != window {
+            self.live.set_window(&mut self.db).to(window);
+        }
+        if self.executed.lock().expect("the lock is never held while panicking").len() > 1000 {
+            self.take_executed("live_series");
+        }
+        let (diagnostics, message) = match live_plot(&self.db, self.live).clone() {
+            Ok(plot) => return Ok(plot),
+            Err(RunError::Invalid(diagnostics)) => {
+                let message = format!("the definitions have errors: {}", diagnostics[0].message);
+                (diagnostics, message)
+            },
+            Err(RunError::Failed(_, err)) => {
+                let message = format!("evaluating the definitions failed: {}", err.message);
+                (vec![err.into()], message)
+            },
+        };
+        match relative(diagnostics, prefix.len()) {
+            // the problems are in the live statements
+            diagnostics if diagnostics.is_empty() => Err(vec![Diagnostic::error(Span::new(0, plot.trim_end().len()), message)]),
+            diagnostics => Err(diagnostics),
+        }
+    }
+    /// the sliders of the live statements at the values they are set to
+    pub fn sliders(&self) -> Vec<SliderState> {
+        let values = self.sliders.iter()
+            .map(|x| (x.name(&self.db).clone(), x.value(&self.db)))
+            .collect::<Vec<_>>();
+        match evaluate_source_with(&self.db, self.source, &values) {
+            Ok((evaluator, _)) => evaluator.sliders().to_vec(),
+            Err(_) => vec![],
+        }
+    }
+    /// sets the slider with the name, the next plot only samples the plotted expressions using it again
+    pub fn set_slider(&mut self, name: &str, value: f64) {
+        match self.sliders.iter().find(|x| x.name(&self.db) == name) {
+            Some(slider) if slider.value(&self.db) == value => {},
+            Some(slider) => {
+                slider.set_value(&mut self.db).to(value);
+            },
+            None => {
+                self.sliders.push(SliderInput::new(&self.db, name.to_owned(), value));
+                self.live.set_sliders(&mut self.db).to(self.sliders.clone());
+            },
+        }
+    }
+    /// removes the keys of the query executed since they were last taken and returns them
+    fn take_executed(&self, query: &str) -> Vec<salsa::Id> {
+        let mut executed = self.executed.lock().expect("the lock is never held while panicking");
+        let (taken, kept) = std::mem::take(&mut *executed).into_iter().partition::<Vec<_>, _>(|x| x.0 == query);
+        *executed = kept;
+        taken.into_iter().map(|x| x.1).collect()
+    }
+    /// writes the live statements to the source and evaluates every definition, returning the names of the
+    /// definitions salsa had to evaluate again
+    fn update(&mut self) -> Vec<String> {
+        let text = self.statements.iter().map(|x| x.text.as_str()).collect::<Vec<_>>().join("\n");
+        self.source.set_raw_text(&mut self.db).to(text);
+        self.take_executed("evaluate_definition");
+        let names = self.names().into_iter()
+            .map(|x| LiveName::new(&self.db, self.source, x))
+            .collect::<Vec<_>>();
+        for name in &names {
+            evaluate_definition(&self.db, *name);
+        }
+        let evaluated = self.take_executed("evaluate_definition");
+        names.iter()
+            .filter(|x| evaluated.contains(&x.as_id()))
+            .map(|x| x.text(&self.db).clone())
+            .collect()
+    }
+}
+
+/// a top level name of a source
+#[salsa::interned(debug)]
+pub struct LiveName<'db> {
+    pub source: ProgramSource,
+    #[returns(ref)]
+    pub text: String,
+}
+
+/// the type of a top level definition, only executed again when the definition or one of the definitions it
+/// depends on changed
+#[salsa::tracked]
+pub fn evaluate_definition<'db>(db: &'db dyn salsa::Database, name: LiveName<'db>) -> InferredType {
+    // reading the closure makes this depend on the text of every definition used
+    definition_closure(db, name);
+    definition_type(db, name)
+}
+
+#[salsa::tracked]
+fn definition_type<'db>(db: &'db dyn salsa::Database, name: LiveName<'db>) -> InferredType {
+    let program = compile_source(db, name.source(db));
+    let resolution = resolve_program(db, program);
+    match top_level_definition(db, resolution, program, name.text(db)) {
+        Some(index) => infer_definition(db, resolution, index),
+        None => InferredType::Unknown,
+    }
+}
+
+/// the name and source of the definition and of every top level definition it uses directly or indirectly
+#[salsa::tracked(returns(ref))]
+fn definition_closure<'db>(db: &'db dyn salsa::Database, name: LiveName<'db>) -> Vec<(String, String)> {
+    let program = compile_source(db, name.source(db));
+    let resolution = resolve_program(db, program);
+    let queue = top_level_definition(db, resolution, program, name.text(db)).into_iter().collect();
+    closure(db, name.source(db), queue)
+}
+
+/// the name and source of the top level definitions in the queue and of every top level definition they use
+fn closure(db: &dyn salsa::Database, source: ProgramSource, mut queue: Vec<usize>) -> Vec<(String, String)> {
+    let text = source.raw_text(db);
+    let program = compile_source(db, source);
+    let resolution = resolve_program(db, program);
+    let definitions = resolution.definitions(db);
+    let mut seen = vec![];
+    let mut out = vec![];
+    while let Some(index) = queue.pop() {
+        if seen.contains(&index) {
+            continue;
+        }
+        seen.push(index);
+        let definition = &definitions[index];
+        let spans = definition.statements.iter().map(|x| x.span(db)).collect::<Vec<_>>();
+        out.push((definition.name.clone(), spans.iter().map(|x| &text[x.start..x.end]).collect::<Vec<_>>().join("\n")));
+        queue.extend(top_level_references(db, source, &spans));
+    }
+    out.sort();
+    out
+}
+
+/// the top level definitions referenced inside of the spans
+fn top_level_references(db: &dyn salsa::Database, source: ProgramSource, spans: &[Span]) -> Vec<usize> {
+    let program = compile_source(db, source);
+    let resolution = resolve_program(db, program);
+    let definitions = resolution.definitions(db);
+    resolution.references(db).iter()
+        .filter(|x| spans.iter().any(|span| span.start <= x.span.start && x.span.end <= span.end))
+        .filter_map(|x| x.definition)
+        .filter(|x| definitions[*x].scope == ScopeId::Program(program))
+        .collect()
+}
+
+/// a slider of the session with the value it is set to, every slider is its own input so setting one only
+/// samples the plotted expressions using it again
+#[salsa::input(debug)]
+pub struct SliderInput {
+    #[returns(ref)]
+    pub name: String,
+    pub value: f64,
+}
+
+/// the plot shown by the repl, the plot statement is the last statement of the source
+#[salsa::input(debug)]
+pub struct LivePlot {
+    pub source: ProgramSource,
+    pub window: Window,
+    #[returns(ref)]
+    pub sliders: Vec<SliderInput>,
+}
+
+/// samples the live plot, every plotted expression is sampled by [`live_series`]
+#[salsa::tracked(returns(ref))]
+fn live_plot(db: &dyn salsa::Database, plot: LivePlot) -> Result<SampledPlot, RunError> {
+    let (statement, spans) = live_statement(db, plot)?;
+    let (mut evaluator, values) = evaluate_live(db, plot, &spans)?;
+    let parts = (0..statement.series(db).len())
+        .map(|i| live_series(db, plot, i).clone())
+        .collect::<Result<Vec<_>, _>>()?;
+    combine_plot(db, &mut evaluator, statement, plot.window(db), parts).map_err(|err| RunError::Failed(values, err))
+}
+
+/// samples a plotted expression of the live plot, only executed again when the source, the window or the value of a
+/// slider it uses changed
+#[salsa::tracked(returns(ref))]
+fn live_series(db: &dyn salsa::Database, plot: LivePlot, index: usize) -> Result<SampledSeries, RunError> {
+    let (statement, mut spans) = live_statement(db, plot)?;
+    spans.push(statement.series(db)[index].span());
+    let (mut evaluator, values) = evaluate_live(db, plot, &spans)?;
+    let source = plot.source(db);
+    sample_plot_series(db, &mut evaluator, source.raw_text(db), statement, plot.window(db), index)
+        .map_err(|err| RunError::Failed(values, err))
+}
+
+/// the plot statement of the live plot with the spans of its range and options
+fn live_statement(db: &dyn salsa::Database, plot: LivePlot) -> Result<(Plot<'_>, Vec<Span>), RunError> {
+    let source = plot.source(db);
+    let diagnostics = check_source(db, source);
+    if !diagnostics.is_empty() {
+        return Err(RunError::Invalid(diagnostics));
+    }
+    let Some(Statement::Plot(statement)) = compile_source(db, source).statements(db).last() else {
+        unreachable!("the live plot ends in a plot statement")
+    };
+    Ok((*statement, statement.settings(db).map(|x| x.span()).collect()))
+}
+
+/// evaluates the source of the live plot with the sliders used inside of the spans at their values
+///
+/// only the values of these sliders are read, so changing another slider does not evaluate the spans again. the other
+/// sliders are at the start of their range, which is only noticed when evaluating fails and then every slider is read
+fn evaluate_live<'db>(db: &'db dyn salsa::Database, plot: LivePlot, spans: &[Span]) -> Result<(Evaluator<'db>, Vec<(String, Value)>), RunError> {
+    let source = plot.source(db);
+    let used = closure(db, source, top_level_references(db, source, spans));
+    let values = |all: bool| plot.sliders(db).iter()
+        .filter(|x| all || used.iter().any(|(name, _)| name == x.name(db)))
+        .map(|x| (x.name(db).clone(), x.value(db)))
+        .collect::<Vec<_>>();
+    match evaluate_source_with(db, source, &values(false)) {
+        Err(RunError::Failed(..)) => evaluate_source_with(db, source, &values(true)),
+        result => result,
+    }
+}
+
+/// the last top level definition of the name
+fn top_level_definition<'db>(db: &'db dyn salsa::Database, resolution: Resolution<'db>, program: Program<'db>, name: &str) -> Option<usize> {
+    resolution.definitions(db).iter()
+        .rposition(|x| x.name == name && x.scope == ScopeId::Program(program) && x.kind != DefinitionKind::Parameter)
+}
+
+
+/// keeps the diagnostics at or after the offset and makes their spans relative to it
+fn relative(diagnostics: Vec<Diagnostic>, offset: usize) -> Vec<Diagnostic> {
+    diagnostics.into_iter()
+        .filter(|x| x.span.start >= offset)
+        .map(|x| shift(x, offset, -(offset as isize)))
+        .collect()
+}
+/// moves the spans of the diagnostic, notes pointing before the start lose their span
+fn shift(mut diagnostic: Diagnostic, start: usize, delta: isize) -> Diagnostic {
+    diagnostic.span = move_span(diagnostic.span, delta);
+    for note in &mut diagnostic.notes {
+        note.span = note.span.filter(|x| x.start >= start).map(|x| move_span(x, delta));
+    }
+    diagnostic
+}
+fn move_span(span: Span, delta: isize) -> Span {
+    Span::new(span.start.saturating_add_signed(delta), span.end.saturating_add_signed(delta))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn types(response: &Response) -> Vec<String> {
+        response.definitions.iter().map(|(name, ty)| format!("{name}: {ty}")).collect()
+    }
+
+    #[test]
+    fn appends_definitions(){
+        let mut session = Session::default();
+        assert_eq!(types(&session.submit("let a = 1.5;")), vec!["a: {float}"]);
+        assert_eq!(types(&session.submit("fn double(x: u8) -> u8 = x * 2; let b = a;")), vec!["double: fn(u8) -> u8", "b: {float}"]);
+        assert_eq!(session.text(), "let a = 1.5;\nfn double(x: u8) -> u8 = x * 2;\nlet b = a;");
+    }
+    #[test]
+    fn bare_expression(){
+        let mut session = Session::default();
+        session.submit("let a = 2;");
+        let response = session.submit("let b = 1.0; a * b");
+        assert_eq!(types(&response), vec!["b: {float}"]);
+        assert_eq!(response.value, Some(InferredType::AbstractFloat));
+        assert_eq!(session.text(), "let a = 2;\nlet b = 1.0;");
+        let response = session.submit("c + 1");
+        assert_eq!(response.diagnostics, vec![Diagnostic::error(Span::new(0, 1), "cannot find `c` in this scope")]);
+        assert_eq!(session.submit("let d = 1; // one").value, None);
+    }
+    #[test]
+    fn rejects_syntax_errors(){
+        let mut session = Session::default();
+        session.submit("let a = 1;");
+        let response = session.submit("let b = ;");
+        assert_eq!(response.diagnostics.len(), 1);
+        assert_eq!(response.diagnostics[0].span.start, 8);
+        assert_eq!(session.text(), "let a = 1;");
+        // unresolved names are kept
+        let response = session.submit("let c = d;");
+        assert_eq!(response.diagnostics[0].span, Span::new(8, 9));
+        assert_eq!(session.text(), "let a = 1;\nlet c = d;");
+    }
+    #[test]
+    fn redefinition_replaces(){
+        let mut session = Session::default();
+        session.submit("let a = 1; let b = a * 2; fn foo(x) = x; let c = 3;");
+        let response = session.submit("let a = 1.5;");
+        assert_eq!(types(&response), vec!["a: {float}"]);
+        assert_eq!(response.recomputed, vec!["b"]);
+        let response = session.submit("  fn foo(y) = y * b;");
+        assert_eq!(response.recomputed, Vec::<String>::new());
+        assert_eq!(session.text(), "let a = 1.5;\nlet b = a * 2;\nfn foo(y) = y * b;\nlet c = 3;");
+        // the replaced statement keeps its position, the spans still point into the input
+        let response = session.submit("let b = z;");
+        assert_eq!(response.diagnostics, vec![Diagnostic::error(Span::new(8, 9), "cannot find `z` in this scope")]);
+        assert_eq!(response.recomputed, vec!["foo"]);
+    }
+    #[test]
+    fn redefinition_using_itself(){
+        let mut session = Session::default();
+        session.submit("let a = 1; let b = a * 2;");
+        let response = session.submit("let a = a + 1;");
+        assert_eq!(response.diagnostics, vec![]);
+        assert_eq!(types(&response), vec!["a: {integer}"]);
+        assert_eq!(response.recomputed, Vec::<String>::new());
+        // the earlier definition is shadowed, so b keeps its value
+        assert_eq!(session.text(), "let a = 1;\nlet b = a * 2;\nlet a = a + 1;");
+        let values = run_source(&session.db, session.source).unwrap();
+        assert_eq!(values.iter().filter(|x| x.0 == "a").map(|x| x.1.clone()).collect::<Vec<_>>(), vec![Value::Int(1), Value::Int(2)]);
+        assert!(values.contains(&("b".to_owned(), Value::Int(2))));
+        // a definition which does not use the earlier one still replaces it
+        session.submit("let a = 5;");
+        assert_eq!(session.text(), "let a = 5;\nlet b = a * 2;");
+    }
+    #[test]
+    fn clauses(){
+        let mut session = Session::default();
+        session.submit("fn fib(0) = 1;");
+        session.submit("let x = fib(3);");
+        session.submit("fn fib(1) = 1;");
+        session.submit("fn fib(n) = fib(n - 1) + fib(n - 2);");
+        session.submit("fn fib(0) = 0;");
+        assert_eq!(session.text(), "fn fib(0) = 0;\nfn fib(1) = 1;\nfn fib(n) = fib(n - 1) + fib(n - 2);\nlet x = fib(3);");
+        // statements in the same input do not replace each other
+        session.submit("let y = 1; let y = y + 1;");
+        assert_eq!(session.text().matches("let y").count(), 2);
+    }
+    #[test]
+    fn save_and_load(){
+        let mut session = Session::default();
+        session.submit("let a = 1;");
+        session.submit("let b = ;");
+        session.submit("let b = a * 2.0; fn f(x) = x;");
+        session.submit("let a = 3;");
+        assert_eq!(session.save(), "let a = 3;\nlet b = a * 2.0;\nfn f(x) = x;\n");
+        let mut loaded = Session::default();
+        assert_eq!(types(&loaded.load(&session.save())), vec!["f: fn(_) -> _", "a: {integer}", "b: {float}"]);
+        assert_eq!(loaded.text(), session.text());
+        // loading the same file again changes nothing
+        assert_eq!(loaded.load(&session.save()).recomputed, Vec::<String>::new());
+        assert_eq!(loaded.text(), session.text());
+
+        let saved = session.save_json();
+        let json = serde_json::to_value(&saved).unwrap();
+        assert_eq!(json["definitions"][1], serde_json::json!({"name": "b", "type": "{float}", "value": 6.0}));
+        assert_eq!(json["definitions"][2], serde_json::json!({"name": "f", "type": "fn(_) -> _"}));
+        let parsed = serde_json::from_value::<SavedSession>(json).unwrap();
+        let mut loaded = Session::default();
+        loaded.load_json(&parsed);
+        assert_eq!(loaded.text(), session.text());
+    }
+    #[test]
+    fn undo(){
+        let mut session = Session::default();
+        session.submit("let a = 1; let b = a;");
+        session.submit("let a = 2;");
+        assert_eq!(session.undo(), Some(vec!["a".to_owned(), "b".to_owned()]));
+        assert_eq!(session.text(), "let a = 1;\nlet b = a;");
+        session.reset();
+        assert_eq!(session.text(), "");
+        session.undo();
+        session.undo();
+        assert_eq!(session.text(), "");
+        assert_eq!(session.undo(), None);
+    }
+    #[test]
+    fn plot_windows(){
+        let mut session = Session::default();
+        session.submit("let k = 2;");
+        let response = session.submit("plot k * x for x in 0..1;");
+        assert_eq!(response.plots[0].0, "plot k * x for x in 0..1;");
+        assert_eq!(response.plots[0].1.x_range, (0.0, 1.0));
+        let window = Window { x_range: Some((-4.0, -2.0)), y_range: Some((-1.0, 1.0)), ..Default::default() };
+        let plot = session.plot_in("plot k * x for x in 0..1;", window).unwrap();
+        assert_eq!((plot.x_range, plot.y_range), ((-4.0, -2.0), Some((-1.0, 1.0))));
+        let lines = &plot.series[0].lines;
+        assert!(lines.iter().flatten().all(|(x, y)| (-4.0..=-2.0).contains(x) && *y == 2.0 * x));
+    }
+    #[test]
+    fn sliders(){
+        let mut session = Session::default();
+        session.submit("let a = slider(0..10, step 0.5); let b = slider(1..2); fn f(x) = a * x;");
+        let names = session.sliders().iter().map(|x| (x.name.clone(), x.value)).collect::<Vec<_>>();
+        assert_eq!(names, vec![("a".to_owned(), 0.0), ("b".to_owned(), 1.0)]);
+        let plot = "plot f(x), b for x in 0..1;";
+        session.set_slider("a", 1.0);
+        session.plot(plot).unwrap();
+        session.take_executed("live_series");
+        session.set_slider("a", 3.0);
+        let sampled = session.plot(plot).unwrap();
+        assert!(sampled.series[0].lines.iter().flatten().all(|(x, y)| *y == 3.0 * x));
+        // only the series using a is sampled again, through the function using it
+        assert_eq!(session.take_executed("live_series").len(), 1);
+        assert_eq!(session.sliders()[0].value, 3.0);
+        // setting the value it already has changes nothing
+        session.set_slider("a", 3.0);
+        session.plot(plot).unwrap();
+        assert!(session.take_executed("live_series").is_empty());
+        // the window samples every series again
+        session.plot_in(plot, Window { x_range: Some((0.0, 2.0)), ..Default::default() }).unwrap();
+        assert_eq!(session.take_executed("live_series").len(), 2);
+    }
+}
@@ -0,0 +1,260 @@
+use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers, MouseEventKind};
+
+use crate::{eval::SliderState, plot::{axes::Scale, format_tick, terminal::PlotArea, Window}};
+
+/// how far the arrow keys move the view, relative to its size
+const PAN: f64 = 0.1;
+/// how much a single step of zooming in shrinks the view
+const ZOOM: f64 = 1.25;
+/// how far the view can be zoomed in and out, relative to the size it started with
+const ZOOM_LIMIT: f64 = 1e6;
+/// how many steps the braces move a slider
+const LARGE_SLIDE: i32 = 10;
+
+/// a change of the plot view, from a key or the mouse
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Action {
+    /// moves the view by fractions of its width and height, positive is right and up
+    Pan(f64, f64),
+    /// shrinks the view by the factor, keeping the point in the same place, or the middle without a point
+    Zoom { factor: f64, at: Option<(f64, f64)> },
+    /// goes back to the ranges the plot started with
+    Reset,
+    /// the mouse moved to the point, None when it left the graphs
+    Cursor(Option<(f64, f64)>),
+    /// moves the selected slider by a number of its steps, positive is up
+    Slide(i32),
+    /// selects the next slider, after the last one the first
+    NextSlider,
+    Quit,
+}
+
+/// the ranges an interactive plot is shown over, the point under the mouse and the sliders of the session
+///
+/// the view only tracks state, drawing and sampling are left to the terminal so it can be tested without one. the
+/// ranges and the cursor are positions along the axes, so a log scale pans and zooms by factors
+#[derive(PartialEq, Debug, Clone)]
+pub struct View {
+    /// the ranges the plot started with
+    home: ((f64, f64), (f64, f64)),
+    pub x_range: (f64, f64),
+    pub y_range: (f64, f64),
+    pub cursor: Option<(f64, f64)>,
+    /// the scales of the x and the y axis
+    scales: (Scale, Scale),
+    pub sliders: Vec<SliderState>,
+    /// the index of the slider changed by the keys
+    selected: usize,
+}
+impl View {
+    pub fn new(x_range: (f64, f64), y_range: (f64, f64)) -> Self {
+        Self { home: (x_range, y_range), x_range, y_range, cursor: None, scales: Default::default(), sliders: vec![], selected: 0 }
+    }
+    pub fn with_sliders(self, sliders: Vec<SliderState>) -> Self {
+        Self { sliders, ..self }
+    }
+    pub fn with_scales(self, scales: (Scale, Scale)) -> Self {
+        Self { scales, ..self }
+    }
+    /// the ranges to sample and draw the plot over
+    pub fn window(&self) -> Window {
+        let (x_scale, y_scale) = self.scales;
+        let values = |scale: Scale, (min, max): (f64, f64)| (scale.value(min), scale.value(max));
+        Window { x_range: Some(values(x_scale, self.x_range)), y_range: Some(values(y_scale, self.y_range)), ..Default::default() }
+    }
+    /// the point under the mouse with the keys, shown below the plot
+    pub fn status(&self) -> String {
+        // precise to about a thousandth of the view
+        let coordinate = |x: f64, scale: Scale, (min, max): (f64, f64)| {
+            let value = scale.value(x);
+            format_tick(value, (scale.value(x + (max - min) / 1e3) - value).abs())
+        };
+        let cursor = match self.cursor {
+            Some((x, y)) => format!("x {}, y {}", coordinate(x, self.scales.0, self.x_range), coordinate(y, self.scales.1, self.y_range)),
+            None => "x -, y -".to_owned(),
+        };
+        let Some(slider) = self.sliders.get(self.selected) else {
+            return format!("{cursor}  arrows pan, +/- or scroll zoom, r resets, q exits");
+        };
+        let next = if self.sliders.len() > 1 { ", tab next" } else { "" };
+        format!("{cursor}  {} = {}  [/] slide{next}, arrows pan, +/- zoom, q exits", slider.name, slider_value(slider))
+    }
+    /// applies the action, returns whether the ranges or a slider changed and the plot has to be sampled again
+    pub fn apply(&mut self, action: Action) -> bool {
+        let before = (self.x_range, self.y_range);
+        match action {
+            Action::Pan(dx, dy) => {
+                let shift = |(min, max): (f64, f64), by: f64| (min + by * (max - min), max + by * (max - min));
+                self.x_range = shift(self.x_range, dx);
+                self.y_range = shift(self.y_range, dy);
+            },
+            Action::Zoom { factor, at } => {
+                let middle = |(min, max): (f64, f64)| (min + max) / 2.0;
+                let (x, y) = at.unwrap_or((middle(self.x_range), middle(self.y_range)));
+                let (width, home_width) = (self.x_range.1 - self.x_range.0, self.home.0.1 - self.home.0.0);
+                // keeps the size within the limits, relative to the start along x as both axes are zoomed together
+                let factor = factor.clamp(width / home_width / ZOOM_LIMIT, width / home_width * ZOOM_LIMIT);
+                let zoom = |(min, max): (f64, f64), at: f64| (at - (at - min) / factor, at + (max - at) / factor);
+                self.x_range = zoom(self.x_range, x);
+                self.y_range = zoom(self.y_range, y);
+            },
+            Action::Reset => (self.x_range, self.y_range) = self.home,
+            Action::Cursor(point) => self.cursor = point,
+            Action::Slide(steps) => if let Some(slider) = self.sliders.get_mut(self.selected) {
+                let (min, max) = slider.range;
+                // stays on the steps counted from the start of the range
+                let at = ((slider.value - min) / slider.step).round() + steps as f64;
+                let value = (min + at * slider.step).clamp(min, max);
+                if value != slider.value {
+                    slider.value = value;
+                    return true;
+                }
+            },
+            Action::NextSlider => self.selected = (self.selected + 1) % self.sliders.len().max(1),
+            Action::Quit => {},
+        }
+        before != (self.x_range, self.y_range)
+    }
+}
+
+/// the value of the slider with as many decimals as its step has, at most twelve
+fn slider_value(slider: &SliderState) -> String {
+    let decimals = (0..12usize)
+        .find(|x| {
+            let scaled = slider.step * 10f64.powi(*x as i32);
+            (scaled - scaled.round()).abs() < 1e-9 * scaled.max(1.0)
+        })
+        .unwrap_or(12);
+    format!("{:.decimals$}", slider.value)
+}
+
+/// the action for the key or mouse event, the area of the graphs on the screen gives the points under the mouse
+pub fn action(event: &Event, area: Option<&PlotArea>) -> Option<Action> {
+    let zoom_in = |at| Action::Zoom { factor: ZOOM, at };
+    let zoom_out = |at| Action::Zoom { factor: 1.0 / ZOOM, at };
+    match event {
+        Event::Key(key) if key.kind != KeyEventKind::Release => Some(match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::Quit,
+            KeyCode::Left => Action::Pan(-PAN, 0.0),
+            KeyCode::Right => Action::Pan(PAN, 0.0),
+            KeyCode::Up => Action::Pan(0.0, PAN),
+            KeyCode::Down => Action::Pan(0.0, -PAN),
+            KeyCode::Char('+' | '=') => zoom_in(None),
+            KeyCode::Char('-') => zoom_out(None),
+            KeyCode::Char('r') => Action::Reset,
+            KeyCode::Char('[') => Action::Slide(-1),
+            KeyCode::Char(']') => Action::Slide(1),
+            KeyCode::Char('{') => Action::Slide(-LARGE_SLIDE),
+            KeyCode::Char('}') => Action::Slide(LARGE_SLIDE),
+            KeyCode::Tab => Action::NextSlider,
+            KeyCode::Char('q') | KeyCode::Esc => Action::Quit,
+            _ => return None,
+        }),
+        Event::Mouse(mouse) => {
+            let point = area.and_then(|x| x.point(mouse.column as usize, mouse.row as usize));
+            match mouse.kind {
+                MouseEventKind::ScrollUp => Some(zoom_in(point)),
+                MouseEventKind::ScrollDown => Some(zoom_out(point)),
+                MouseEventKind::Moved | MouseEventKind::Drag(_) => Some(Action::Cursor(point)),
+                _ => None,
+            }
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyEvent, MouseEvent};
+
+    use super::*;
+
+    #[test]
+    fn pan(){
+        let mut view = View::new((0.0, 10.0), (-1.0, 1.0));
+        assert!(view.apply(Action::Pan(0.1, 0.0)));
+        assert_eq!((view.x_range, view.y_range), ((1.0, 11.0), (-1.0, 1.0)));
+        assert!(view.apply(Action::Pan(0.0, -0.5)));
+        assert_eq!(view.y_range, (-2.0, 0.0));
+        assert_eq!(view.window(), Window { x_range: Some((1.0, 11.0)), y_range: Some((-2.0, 0.0)), ..Default::default() });
+        assert!(view.apply(Action::Reset));
+        assert_eq!((view.x_range, view.y_range), ((0.0, 10.0), (-1.0, 1.0)));
+        assert!(!view.apply(Action::Reset));
+    }
+    #[test]
+    fn zoom(){
+        let mut view = View::new((0.0, 8.0), (0.0, 4.0));
+        assert!(view.apply(Action::Zoom { factor: 2.0, at: None }));
+        assert_eq!((view.x_range, view.y_range), ((2.0, 6.0), (1.0, 3.0)));
+        // the point under the mouse stays in place
+        assert!(view.apply(Action::Zoom { factor: 0.5, at: Some((2.0, 1.0)) }));
+        assert_eq!((view.x_range, view.y_range), ((2.0, 10.0), (1.0, 5.0)));
+        // limited to a millionth of the size the plot started with
+        for _ in 0..200 {
+            view.apply(Action::Zoom { factor: ZOOM, at: None });
+        }
+        let width = view.x_range.1 - view.x_range.0;
+        assert!((width - 8e-6).abs() < 1e-12);
+        assert!(!view.apply(Action::Zoom { factor: ZOOM, at: None }));
+    }
+    #[test]
+    fn cursor(){
+        let mut view = View::new((0.0, 1.0), (0.0, 1.0));
+        assert!(!view.apply(Action::Cursor(Some((0.5, 0.25)))));
+        assert_eq!(view.cursor, Some((0.5, 0.25)));
+        assert!(view.status().starts_with("x 0.500, y 0.250  arrows pan"));
+        view.apply(Action::Cursor(None));
+        assert!(view.status().starts_with("x -, y -"));
+        assert!(!view.apply(Action::Quit));
+        // positions on a log scale are shown and sampled as values
+        let mut view = View::new((0.0, 3.0), (0.0, 1.0)).with_scales((Scale::Log, Scale::Linear));
+        view.apply(Action::Cursor(Some((2.0, 0.5))));
+        assert!(view.status().starts_with("x 100.0, y 0.500"));
+        assert_eq!(view.window(), Window { x_range: Some((1.0, 1000.0)), y_range: Some((0.0, 1.0)), ..Default::default() });
+    }
+    #[test]
+    fn sliders(){
+        let slider = |name: &str, value| SliderState { name: name.to_owned(), range: (0.0, 1.0), step: 0.25, value };
+        let mut view = View::new((0.0, 1.0), (0.0, 1.0));
+        assert!(!view.apply(Action::Slide(1)));
+        let mut view = view.with_sliders(vec![slider("a", 0.0), slider("b", 0.1)]);
+        assert!(view.status().starts_with("x -, y -  a = 0.00  [/] slide, tab next"));
+        assert!(view.apply(Action::Slide(1)));
+        assert_eq!(view.sliders[0].value, 0.25);
+        // limited to the range
+        assert!(view.apply(Action::Slide(LARGE_SLIDE)));
+        assert_eq!(view.sliders[0].value, 1.0);
+        assert!(!view.apply(Action::Slide(1)));
+        assert!(!view.apply(Action::NextSlider));
+        // values between the steps move onto them
+        assert!(view.apply(Action::Slide(1)));
+        assert_eq!(view.sliders[1].value, 0.25);
+        assert_eq!((view.x_range, view.y_range), ((0.0, 1.0), (0.0, 1.0)));
+        view.apply(Action::NextSlider);
+        assert!(view.status().contains("a = 1.00"));
+    }
+    #[test]
+    fn events(){
+        let key = |code| Event::Key(KeyEvent::new(code, KeyModifiers::NONE));
+        assert_eq!(action(&key(KeyCode::Left), None), Some(Action::Pan(-PAN, 0.0)));
+        assert_eq!(action(&key(KeyCode::Up), None), Some(Action::Pan(0.0, PAN)));
+        assert_eq!(action(&key(KeyCode::Char('+')), None), Some(Action::Zoom { factor: ZOOM, at: None }));
+        assert_eq!(action(&key(KeyCode::Char('-')), None), Some(Action::Zoom { factor: 1.0 / ZOOM, at: None }));
+        assert_eq!(action(&key(KeyCode::Char('r')), None), Some(Action::Reset));
+        assert_eq!(action(&key(KeyCode::Char('q')), None), Some(Action::Quit));
+        assert_eq!(action(&Event::Key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)), None), Some(Action::Quit));
+        assert_eq!(action(&key(KeyCode::Char(']')), None), Some(Action::Slide(1)));
+        assert_eq!(action(&key(KeyCode::Char('{')), None), Some(Action::Slide(-LARGE_SLIDE)));
+        assert_eq!(action(&key(KeyCode::Tab), None), Some(Action::NextSlider));
+        assert_eq!(action(&key(KeyCode::Char('x')), None), None);
+        let area = PlotArea { left: 2, top: 3, columns: 10, rows: 5, x_range: (0.0, 1.0), y_range: (0.0, 1.0) };
+        let mouse = |kind, column, row| Event::Mouse(MouseEvent { kind, column, row, modifiers: KeyModifiers::NONE });
+        let Some(Action::Zoom { factor, at: Some((x, y)) }) = action(&mouse(MouseEventKind::ScrollUp, 2, 3), Some(&area)) else {
+            panic!("scrolling up zooms in at the mouse")
+        };
+        assert_eq!(factor, ZOOM);
+        assert!(x < 0.1 && y > 0.9);
+        assert_eq!(action(&mouse(MouseEventKind::Moved, 0, 0), Some(&area)), Some(Action::Cursor(None)));
+        assert_eq!(action(&mouse(MouseEventKind::ScrollDown, 0, 0), None), Some(Action::Zoom { factor: 1.0 / ZOOM, at: None }));
+    }
+}
@@ -1,10 +1,11 @@
 use std::ops::{Deref, DerefMut};
 
 use chumsky::{extra::ParserExtra, input::Input, Parser};
+use serde::Serialize;
 
 
 
-#[derive(PartialEq, Eq, Debug, Hash, salsa::Update, Clone, Copy)]
+#[derive(PartialEq, Eq, Debug, Hash, salsa::Update, Clone, Copy, Serialize)]
 pub struct Span {
     pub start: usize,
     pub end: usize
@@ -16,6 +17,10 @@ impl Span {
             end
         }
     }
+    /// the span moved forward by the offset
+    pub fn shift(self, offset: usize) -> Self {
+        Self::new(self.start + offset, self.end + offset)
+    }
     pub fn merge(self, other: Self) -> Self {
         Self {
             start: self.start.min(other.start),
@@ -41,9 +46,10 @@ impl chumsky::span::Span for Span {
 
 
 
-#[derive(PartialEq, Eq, Debug, Hash, salsa::Update, Clone)]
+#[derive(PartialEq, Eq, Debug, Hash, salsa::Update, Clone, Serialize)]
 pub struct Spanned<T: PartialEq + salsa::Update> {
     span: Span,
+    #[serde(rename = "node")]
     inner: T
 }
 impl<T: PartialEq + salsa::Update> Spanned<T> {
@@ -53,6 +59,9 @@ impl<T: PartialEq + salsa::Update> Spanned<T> {
             inner
         }
     }
+    pub fn span(&self) -> Span {
+        self.span
+    }
     pub fn into_inner(self) -> T {
         self.inner
     }
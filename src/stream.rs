@@ -1,12 +1,13 @@
 use chumsky::input::{Input, ValueInput};
 
-use crate::Span;
+use crate::spanned::Span;
 
 pub struct Stream<I: Iterator> {
     parts: Vec<I::Item>,
     iter: I,
 }
 impl<I: Iterator> Stream<I> {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_iter<J: IntoIterator<IntoIter = I>>(iter: J) -> Self {
         Self {
             parts: vec![],
@@ -41,7 +42,11 @@ where I: Iterator<Item = (T, Span)> + 'a,
         unsafe { Self::next(cache, cursor) }
     }
     unsafe fn span(cache: &mut Self::Cache, range: std::ops::Range<&Self::Cursor>) -> Self::Span {
-        let span_a = cache.parts.get(*range.start).map(|x| x.1.start).unwrap_or(0);
+        let span_a = match cache.parts.get(*range.start) {
+            Some(x) => x.1.start,
+            // the end of input starts after the last token
+            None => cache.parts.last().map(|x| x.1.end).unwrap_or(0),
+        };
         let span_b = cache.parts.get(*range.end - 1).map(|x| x.1.end).unwrap_or(usize::MAX);
         Span::new(span_a, span_b)
     }
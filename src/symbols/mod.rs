@@ -1,11 +1,15 @@
-use std::collections::{BTreeMap, HashMap};
+use crate::{ids::{FunctionId, VariableId}, parser::{block::BlockExpression, expression::Expression, function::Function, pattern::Pattern, plot::Plot, statement::Statement, variable::Variable, Program}};
 
-use crate::{ids::{FunctionId, VariableId}, parser::{block::BlockExpression, expression::Expression, pattern::Pattern, statement::Statement, Program}, spanned::Spanned};
+pub mod resolve;
 
 #[derive(PartialEq, Eq, Debug, Hash, salsa::Update, Clone, Copy)]
 pub enum ScopeId<'db>{
     Program(Program<'db>),
-    Block(BlockExpression<'db>)
+    Block(BlockExpression<'db>),
+    /// the scope holding the arguments of a single function clause
+    Function(Function<'db>),
+    /// the scope holding the variable of a plot, only the plotted expressions are inside of it
+    Plot(Plot<'db>),
 }
 
 
@@ -18,48 +22,67 @@ pub struct ScopeParentTable<'db>{
 
 #[salsa::tracked]
 pub fn create_scope_parent_table<'db>(db: &'db dyn salsa::Database, program: Program<'db>) -> ScopeParentTable<'db> {
-    println!("create parent table for program");
     let mut map = Vec::new();
     for statement in program.statements(db) {
-        let body = match statement {
-            Statement::Function(x) => &**x.body(db),
-            Statement::Variable(x) => &*x.body(db)
-        };
-        build_scope_parent_table_expression(db, body, Some(ScopeId::Program(program)), &mut map);
-
+        build_scope_parent_table_statement(db, statement, ScopeId::Program(program), &mut map);
     }
     ScopeParentTable::new(db, map)
 }
+fn build_scope_parent_table_statement<'db>(
+    db: &'db dyn salsa::Database,
+    statement: &Statement<'db>,
+    parent: ScopeId<'db>,
+    map: &mut Vec<(ScopeId<'db>, ScopeId<'db>)>
+){
+    match statement {
+        Statement::Function(x) => {
+            map.push((ScopeId::Function(*x), parent));
+            build_scope_parent_table_expression(db, x.body(db), ScopeId::Function(*x), map);
+        },
+        Statement::Variable(x) => build_scope_parent_table_expression(db, &x.body(db), parent, map),
+        Statement::Plot(x) => {
+            map.push((ScopeId::Plot(*x), parent));
+            for expression in x.expressions(db) {
+                build_scope_parent_table_expression(db, expression, ScopeId::Plot(*x), map);
+            }
+            for expression in x.settings(db) {
+                build_scope_parent_table_expression(db, expression, parent, map);
+            }
+        },
+    }
+}
 fn build_scope_parent_table_expression<'db>(
     db: &'db dyn salsa::Database,
     expression: &Expression<'db>,
-    parent: Option<ScopeId<'db>>,
+    parent: ScopeId<'db>,
     map: &mut Vec<(ScopeId<'db>, ScopeId<'db>)>
 ){
     match expression {
         Expression::Block(x) => build_scope_parent_table(db, *x, parent, map),
+        Expression::Negate(x) => build_scope_parent_table_expression(db, x, parent, map),
         Expression::Binary(l, _, r) => {
-            build_scope_parent_table_expression(db, &**l, parent, map);
-            build_scope_parent_table_expression(db, &**r, parent, map);
+            build_scope_parent_table_expression(db, l, parent, map);
+            build_scope_parent_table_expression(db, r, parent, map);
         },
+        Expression::FunctionCall(_, parts) | Expression::Tuple(parts) => {
+            parts.iter().for_each(|arg| build_scope_parent_table_expression(db, arg, parent, map));
+        },
+        Expression::Slider(x) => x.settings().for_each(|x| build_scope_parent_table_expression(db, x, parent, map)),
         _ => {}
     }
 }
 fn build_scope_parent_table<'db>(
     db: &'db dyn salsa::Database,
     node: BlockExpression<'db>,
-    parent: Option<ScopeId<'db>>,
+    parent: ScopeId<'db>,
     map: &mut Vec<(ScopeId<'db>, ScopeId<'db>)>
 ) {
-    if let Some(parent) = parent {
-        map.push((ScopeId::Block(node), parent));
-    }
+    map.push((ScopeId::Block(node), parent));
     for statement in node.statements(db) {
-        let body = match &**statement {
-            Statement::Function(x) => &**x.body(db),
-            Statement::Variable(x) => &*x.body(db)
-        };
-        build_scope_parent_table_expression(db,body, parent, map);
+        build_scope_parent_table_statement(db, statement, ScopeId::Block(node), map);
+    }
+    if let Some(expr) = node.return_expr(db) {
+        build_scope_parent_table_expression(db, expr, ScopeId::Block(node), map);
     }
 }
 
@@ -71,6 +94,19 @@ pub fn get_parent_scope<'db>(db: &'db dyn salsa::Database, scope: ScopeId<'db>,
         .map(|x| x.1)
 }
 
+/// the symbols of the scope followed by those of every enclosing scope, innermost first
+pub fn visible_symbols<'db>(db: &'db dyn salsa::Database, program: Program<'db>, scope: ScopeId<'db>) -> Vec<SymbolNode<'db>> {
+    let parents = create_scope_parent_table(db, program);
+    let table = create_symbol_table(db, program);
+    let mut out = vec![];
+    let mut current = Some(scope);
+    while let Some(scope) = current {
+        out.extend(table.items(db).iter().filter(|x| x.0 == scope).map(|x| x.1));
+        current = get_parent_scope(db, scope, parents);
+    }
+    out
+}
+
 #[salsa::tracked(debug)]
 pub struct SymbolNode<'db>{
     #[returns(ref)]
@@ -87,62 +123,73 @@ pub struct SymbolTable<'db>{
 
 #[salsa::tracked]
 pub fn create_symbol_table<'db>(db: &'db dyn salsa::Database, program: Program<'db>) -> SymbolTable<'db> {
-    println!("creating symbol table for program");
     let mut items = Vec::new();
-    let mut functions = vec![];
-    let mut variables = vec![];
-
-    for statement in program.statements(db) {
-        let body = match statement {
-            Statement::Function(x) => {
-                functions.push(*x.name(db));
-                &**x.body(db)
-            },
-            Statement::Variable(x) => {
-                let pattern = &**x.name(db);
-                build_symbol_table_pattern(db, pattern, &mut variables);
-                &*x.body(db)
-            }
-        };
-        build_symbol_table_expression(db, body, &mut items);
-
-    }
-    items.push((ScopeId::Program(program), SymbolNode::new(db, functions, variables)));
-
+    build_symbol_table(db, ScopeId::Program(program), program.statements(db).iter(), None, &mut items);
     SymbolTable::new(db, items)
 }
-fn build_symbol_table<'db>(
+fn build_symbol_table<'a, 'db: 'a>(
     db: &'db dyn salsa::Database,
     id: ScopeId<'db>,
-    statements: &[Spanned<Statement<'db>>],
+    statements: impl Iterator<Item = &'a Statement<'db>>,
+    return_expr: Option<&Expression<'db>>,
     map: &mut Vec<(ScopeId<'db>, SymbolNode<'db>)>
 ) {
     let mut functions = vec![];
     let mut variables = vec![];
     for statement in statements {
-        let body = match &**statement {
+        match statement {
             Statement::Function(x) => {
                 functions.push(*x.name(db));
-                &**x.body(db)
+                map.extend(function_symbol_table(db, *x).iter().copied());
             },
             Statement::Variable(x) => {
-                let pattern = &**x.name(db);
-                build_symbol_table_pattern(db, pattern, &mut variables);
-                &*x.body(db)
-            }
+                build_symbol_table_pattern(x.name(db), &mut variables);
+                map.extend(variable_symbol_table(db, *x).iter().copied());
+            },
+            Statement::Plot(x) => map.extend(plot_symbol_table(db, *x).iter().copied()),
         };
-        build_symbol_table_expression(db, body, map);
+    }
+    if let Some(expr) = return_expr {
+        build_symbol_table_expression(db, expr, map);
     }
     map.push((id, SymbolNode::new(db, functions, variables)));
 }
+/// the scopes inside a single function, kept per statement so unchanged statements are not walked again
+#[salsa::tracked(returns(ref))]
+fn function_symbol_table<'db>(db: &'db dyn salsa::Database, function: Function<'db>) -> Vec<(ScopeId<'db>, SymbolNode<'db>)> {
+    let mut map = vec![];
+    let mut variables = vec![];
+    for (pattern, _) in function.args(db) {
+        build_symbol_table_pattern(&pattern, &mut variables);
+    }
+    build_symbol_table_expression(db, function.body(db), &mut map);
+    map.push((ScopeId::Function(function), SymbolNode::new(db, vec![], variables)));
+    map
+}
+/// the scopes inside the body of a single variable
+#[salsa::tracked(returns(ref))]
+fn variable_symbol_table<'db>(db: &'db dyn salsa::Database, variable: Variable<'db>) -> Vec<(ScopeId<'db>, SymbolNode<'db>)> {
+    let mut map = vec![];
+    build_symbol_table_expression(db, &variable.body(db), &mut map);
+    map
+}
+/// the scope of the variables of a single plot and the scopes inside of its expressions
+#[salsa::tracked(returns(ref))]
+fn plot_symbol_table<'db>(db: &'db dyn salsa::Database, plot: Plot<'db>) -> Vec<(ScopeId<'db>, SymbolNode<'db>)> {
+    let mut map = vec![];
+    for expression in plot.expressions(db).chain(plot.settings(db)) {
+        build_symbol_table_expression(db, expression, &mut map);
+    }
+    map.push((ScopeId::Plot(plot), SymbolNode::new(db, vec![], plot.variables(db).map(|x| *x).collect())));
+    map
+}
 fn build_symbol_table_pattern<'db>(
-    db: &'db dyn salsa::Database,
     pattern: &Pattern<'db>,
     variables: &mut Vec<VariableId<'db>>
 ){
     match pattern {
         Pattern::Variable(x) => variables.push(**x),
-        Pattern::Tuple(x) => x.iter().for_each(|ell| build_symbol_table_pattern(db, &**ell, variables)),
+        Pattern::Tuple(x) => x.iter().for_each(|ell| build_symbol_table_pattern(ell, variables)),
         _ => {}
     }
 }
@@ -152,16 +199,26 @@ fn build_symbol_table_expression<'db>(
     map: &mut Vec<(ScopeId<'db>, SymbolNode<'db>)>
 ){
     match expression {
-        Expression::Block(x) => build_symbol_table(db, ScopeId::Block(*x), x.statements(db), map),
+        Expression::Block(x) => build_symbol_table(
+            db,
+            ScopeId::Block(*x),
+            x.statements(db).iter().map(|x| &**x),
+            x.return_expr(db).as_deref(),
+            map
+        ),
+        Expression::Negate(x) => build_symbol_table_expression(db, x, map),
         Expression::Binary(l, _, r) => {
-            build_symbol_table_expression(db, &**l, map);
-            build_symbol_table_expression(db, &**r, map);
+            build_symbol_table_expression(db, l, map);
+            build_symbol_table_expression(db, r, map);
         },
+        Expression::FunctionCall(_, parts) | Expression::Tuple(parts) => {
+            parts.iter().for_each(|arg| build_symbol_table_expression(db, arg, map));
+        },
+        Expression::Slider(x) => x.settings().for_each(|x| build_symbol_table_expression(db, x, map)),
         _ => {}
     }
 }
 
-
 /*
 
 
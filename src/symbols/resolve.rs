@@ -0,0 +1,273 @@
+use crate::{parser::{expression::Expression, function::Function, pattern::Pattern, plot::Plot, statement::Statement, Program}, spanned::{Span, Spanned}};
+
+use super::ScopeId;
+
+#[derive(PartialEq, Eq, Debug, Hash, salsa::Update, Clone, Copy)]
+pub enum DefinitionKind {
+    /// a name bound by a let statement
+    Variable,
+    /// a name bound by the arguments of a function clause or by the variable of a plot
+    Parameter,
+    /// a function, all clauses with the same name in the same scope are a single definition
+    Function,
+}
+
+/// a single binding introduced somewhere in the program
+#[derive(PartialEq, Eq, Debug, Hash, salsa::Update, Clone)]
+pub struct Definition<'db> {
+    pub name: String,
+    pub kind: DefinitionKind,
+    /// the scope the name is bound in
+    pub scope: ScopeId<'db>,
+    /// the span of every name introducing this binding, one for each clause of a function
+    pub sites: Vec<Span>,
+    /// the statements introducing this binding, the function clause or the plot for parameters
+    pub statements: Vec<Statement<'db>>,
+}
+
+/// a name used inside of an expression
+#[derive(PartialEq, Eq, Debug, Hash, salsa::Update, Clone)]
+pub struct Reference {
+    pub name: String,
+    pub span: Span,
+    /// index into the definitions of the resolution, None when the name is not bound in any reachable scope
+    pub definition: Option<usize>,
+}
+
+/// links every name used in the program to the binding it refers to
+#[salsa::tracked(debug)]
+pub struct Resolution<'db> {
+    #[returns(ref)]
+    pub definitions: Vec<Definition<'db>>,
+    #[returns(ref)]
+    pub references: Vec<Reference>,
+    /// the source covered by each scope, the program covers the entire source
+    #[returns(ref)]
+    pub scopes: Vec<(ScopeId<'db>, Span)>,
+}
+impl<'db> Resolution<'db> {
+    /// the definition whose name or one of its references covers the offset
+    pub fn definition_at(&self, db: &'db dyn salsa::Database, offset: usize) -> Option<usize> {
+        let contains = |span: &Span| span.start <= offset && offset <= span.end;
+        self.definitions(db).iter()
+            .position(|def| def.sites.iter().any(contains))
+            .or_else(|| self.references(db).iter()
+                .find(|r| contains(&r.span))
+                .and_then(|r| r.definition)
+            )
+    }
+    /// the innermost scope containing the offset
+    pub fn scope_at(&self, db: &'db dyn salsa::Database, offset: usize) -> Option<ScopeId<'db>> {
+        self.scopes(db).iter()
+            .filter(|(_, span)| span.start <= offset && offset <= span.end)
+            .min_by_key(|(_, span)| span.end - span.start)
+            .map(|(scope, _)| *scope)
+    }
+    pub fn references_to(&self, db: &'db dyn salsa::Database, definition: usize) -> impl Iterator<Item = &'db Reference> {
+        self.references(db).iter()
+            .filter(move |r| r.definition == Some(definition))
+    }
+}
+
+/// resolves every name in the program
+///
+/// let statements are only visible to the statements following them, functions are visible in their entire scope
+/// so they can be (mutually) recursive
+#[salsa::tracked]
+pub fn resolve_program<'db>(db: &'db dyn salsa::Database, program: Program<'db>) -> Resolution<'db> {
+    let mut resolver = Resolver {
+        db,
+        definitions: vec![],
+        references: vec![],
+        frames: vec![],
+        scopes: vec![],
+    };
+    resolver.scope(ScopeId::Program(program), Span::new(0, usize::MAX), program.statements(db).iter(), None);
+    Resolution::new(db, resolver.definitions, resolver.references, resolver.scopes)
+}
+
+struct Resolver<'db> {
+    db: &'db dyn salsa::Database,
+    definitions: Vec<Definition<'db>>,
+    references: Vec<Reference>,
+    /// the bindings visible at the current point, the innermost scope is last
+    frames: Vec<Vec<(String, usize)>>,
+    scopes: Vec<(ScopeId<'db>, Span)>,
+}
+impl<'db> Resolver<'db> {
+    fn define(&mut self, name: String, kind: DefinitionKind, scope: ScopeId<'db>, site: Span, statement: Statement<'db>) {
+        let index = self.definitions.len();
+        self.frames.last_mut()
+            .expect("definitions are always made inside of a scope")
+            .push((name.clone(), index));
+        self.definitions.push(Definition { name, kind, scope, sites: vec![site], statements: vec![statement] });
+    }
+    fn lookup(&self, name: &str) -> Option<usize> {
+        self.frames.iter().rev()
+            .flat_map(|frame| frame.iter().rev())
+            .find(|(binding, _)| binding == name)
+            .map(|(_, index)| *index)
+    }
+    fn reference(&mut self, name: String, span: Span) {
+        let definition = self.lookup(&name);
+        self.references.push(Reference { name, span, definition });
+    }
+
+    fn scope<'a>(
+        &mut self,
+        id: ScopeId<'db>,
+        span: Span,
+        statements: impl Iterator<Item = &'a Statement<'db>> + Clone,
+        return_expr: Option<&Spanned<Expression<'db>>>
+    ) where 'db: 'a {
+        self.scopes.push((id, span));
+        self.frames.push(vec![]);
+        // functions are hoisted so every statement in the scope can call them
+        for statement in statements.clone() {
+            let Statement::Function(function) = statement else { continue };
+            let name = function.name(self.db);
+            let text = name.text(self.db).clone();
+            let existing = self.definitions.iter_mut()
+                .find(|def| def.kind == DefinitionKind::Function && def.scope == id && def.name == text);
+            match existing {
+                Some(def) => {
+                    def.sites.push(name.span());
+                    def.statements.push(statement.clone());
+                },
+                None => self.define(text, DefinitionKind::Function, id, name.span(), statement.clone()),
+            }
+        }
+        for statement in statements {
+            match statement {
+                Statement::Function(function) => self.function(*function),
+                Statement::Variable(variable) => {
+                    self.expression(&variable.body(self.db));
+                    self.pattern(variable.name(self.db), DefinitionKind::Variable, id, statement);
+                },
+                Statement::Plot(plot) => self.plot(*plot),
+            }
+        }
+        if let Some(expr) = return_expr {
+            self.expression(expr);
+        }
+        self.frames.pop();
+    }
+    fn function(&mut self, function: Function<'db>) {
+        let statement = Statement::Function(function);
+        let body = function.body(self.db);
+        self.scopes.push((ScopeId::Function(function), function.name(self.db).span().merge(body.span())));
+        self.frames.push(vec![]);
+        for (pattern, _) in function.args(self.db) {
+            self.pattern(&pattern, DefinitionKind::Parameter, ScopeId::Function(function), &statement);
+        }
+        self.expression(body);
+        self.frames.pop();
+    }
+    fn plot(&mut self, plot: Plot<'db>) {
+        for expression in plot.settings(self.db) {
+            self.expression(expression);
+        }
+        let series = plot.series(self.db);
+        let span = series.iter().fold(plot.variable(self.db).span(), |span, x| span.merge(x.span()));
+        self.scopes.push((ScopeId::Plot(plot), span));
+        self.frames.push(vec![]);
+        for variable in plot.variables(self.db) {
+            self.define(variable.text(self.db).clone(), DefinitionKind::Parameter, ScopeId::Plot(plot), variable.span(), Statement::Plot(plot));
+        }
+        for expression in plot.expressions(self.db) {
+            self.expression(expression);
+        }
+        self.frames.pop();
+    }
+    fn pattern(&mut self, pattern: &Pattern<'db>, kind: DefinitionKind, scope: ScopeId<'db>, statement: &Statement<'db>) {
+        match pattern {
+            Pattern::Variable(x) => self.define(x.text(self.db).clone(), kind, scope, x.span(), statement.clone()),
+            Pattern::Tuple(x) => x.iter().for_each(|ell| self.pattern(ell, kind, scope, statement)),
+            _ => {}
+        }
+    }
+    fn expression(&mut self, expression: &Spanned<Expression<'db>>) {
+        match &**expression {
+            Expression::Variable(x) => self.reference(x.text(self.db).clone(), x.span()),
+            Expression::FunctionCall(name, args) => {
+                self.reference(name.text(self.db).clone(), name.span());
+                args.iter().for_each(|arg| self.expression(arg));
+            },
+            Expression::Negate(x) => self.expression(x),
+            Expression::Tuple(parts) => parts.iter().for_each(|x| self.expression(x)),
+            Expression::Binary(l, _, r) => {
+                self.expression(l);
+                self.expression(r);
+            },
+            Expression::Block(x) => self.scope(
+                ScopeId::Block(*x),
+                expression.span(),
+                x.statements(self.db).iter().map(|x| &**x),
+                x.return_expr(self.db).as_ref()
+            ),
+            Expression::Slider(x) => x.settings().for_each(|x| self.expression(x)),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{lexer::lex_source, parser::compile_tokenstream, GraphingDatabase, ProgramSource};
+
+    use super::*;
+
+    /// the source text of the definition every reference resolves to, in order
+    fn resolved(src: &str) -> Vec<(String, Option<usize>)> {
+        let db = GraphingDatabase::default();
+        let code = ProgramSource::new(&db, src.to_owned());
+        let program = compile_tokenstream(&db, lex_source(&db, code));
+        let resolution = resolve_program(&db, program);
+        resolution.references(&db).iter()
+            .map(|r| (r.name.clone(), r.definition.map(|d| resolution.definitions(&db)[d].sites[0].start)))
+            .collect()
+    }
+
+    #[test]
+    fn sequential_let(){
+        let out = resolved("let a = 1; let a = a + 1; let b = a;");
+        assert_eq!(out, vec![("a".to_owned(), Some(4)), ("a".to_owned(), Some(15))]);
+    }
+    #[test]
+    fn unresolved(){
+        let out = resolved("let a = b;");
+        assert_eq!(out, vec![("b".to_owned(), None)]);
+    }
+    #[test]
+    fn parameters_shadow(){
+        let out = resolved("let x = 1; fn foo(x) = x; let y = x;");
+        assert_eq!(out, vec![("x".to_owned(), Some(18)), ("x".to_owned(), Some(4))]);
+    }
+    #[test]
+    fn clauses_share_definition(){
+        let db = GraphingDatabase::default();
+        let code = ProgramSource::new(&db, "fn fib(0) = 1; fn fib(x) = fib(x - 1) + fib(x - 2);".to_owned());
+        let program = compile_tokenstream(&db, lex_source(&db, code));
+        let resolution = resolve_program(&db, program);
+        let fib = resolution.definitions(&db).iter()
+            .position(|d| d.name == "fib")
+            .unwrap();
+        assert_eq!(resolution.definitions(&db)[fib].sites.len(), 2);
+        assert_eq!(resolution.references_to(&db, fib).count(), 2);
+    }
+    #[test]
+    fn block_scope(){
+        let out = resolved("let y = { let a = 2; a }; let z = a;");
+        assert_eq!(out, vec![("a".to_owned(), Some(14)), ("a".to_owned(), None)]);
+    }
+    #[test]
+    fn plot_scope(){
+        let out = resolved("let x = 5; plot x * 2 for x in 0..x with y 0..x; let y = x;");
+        let names = out.iter().map(|x| x.1).collect::<Vec<_>>();
+        // the range and the options see the outer x, the plotted expression its own
+        assert_eq!(names, vec![Some(4), Some(4), Some(26), Some(4)]);
+        // the second range only sees the outer names
+        let out = resolved("let a = 1; plot x == y for x in 0..a, y in x..a;");
+        assert_eq!(out.iter().map(|x| x.1).collect::<Vec<_>>(), vec![Some(4), None, Some(4), Some(27), Some(38)]);
+    }
+}
@@ -0,0 +1,84 @@
+//! runs every `tests/cases/*.gl` file and compares the syntax tree, diagnostics, evaluation output and plots
+//! with the `.expected` file next to it, every plot is also written as svg and compared with the `<case>-<n>.svg` file
+//!
+//! run with `BLESS=1 cargo test --test golden` to write the current output to the expected files
+
+use std::{fs, path::{Path, PathBuf}};
+
+use graphing_language::{diagnostics::{check_source, render}, eval::RunError, parser::{compile_source, serialize::ProgramNode}, plot::{plot_source, terminal::render_plot, FileFormat, ImageSize}, GraphingDatabase, ProgramSource};
+
+/// the size of the plots in characters, smaller than in the repl to keep the expected files readable
+const PLOT_SIZE: (usize, usize) = (40, 12);
+
+/// every section of the output of a case and its plots as svg, the diagnostics and output are left out when empty
+fn snapshot(name: &str, text: &str) -> (String, Vec<String>) {
+    let db = GraphingDatabase::default();
+    let source = ProgramSource::new(&db, text.to_owned());
+    let tree = ProgramNode::new(&db, compile_source(&db, source));
+    let mut out = format!("== ast ==\n{}\n", serde_json::to_string_pretty(&tree).expect("syntax trees serialize to json"));
+    let diagnostics = check_source(&db, source);
+    if !diagnostics.is_empty() {
+        out.push_str(&format!("== diagnostics ==\n{}", render(&diagnostics, name, text, false)));
+        return (out, vec![]);
+    }
+    out.push_str("== output ==\n");
+    let (values, plots, error) = match plot_source(&db, source) {
+        Ok((values, plots)) => (values, plots, None),
+        Err(RunError::Failed(values, err)) => (values, vec![], Some(err)),
+        Err(RunError::Invalid(_)) => unreachable!("the diagnostics are empty"),
+    };
+    for (name, value) in values {
+        out.push_str(&format!("{name} = {value}\n"));
+    }
+    for plot in &plots {
+        out.push_str(&render_plot(plot, PLOT_SIZE.0, PLOT_SIZE.1, false));
+    }
+    if let Some(err) = error {
+        out.push_str(&render(&[err.into()], name, text, false));
+    }
+    let svgs = plots.iter()
+        .map(|x| String::from_utf8(FileFormat::Svg.render(x, ImageSize::default())).expect("svg images are text"))
+        .collect();
+    (out, svgs)
+}
+
+/// compares the output with the file or writes it when blessing, the failure message if they differ
+fn compare(path: &Path, actual: &str, bless: bool) -> Option<String> {
+    let name = path.file_name().expect("snapshots are files").to_string_lossy();
+    if bless {
+        fs::write(path, actual).expect("snapshot files are writable");
+        return None;
+    }
+    match fs::read_to_string(path) {
+        Ok(expected) if expected == actual => None,
+        Ok(expected) => Some(format!("{name} differs\n--- expected\n{expected}--- actual\n{actual}")),
+        Err(_) => Some(format!("{name} is missing, run with BLESS=1 to create it")),
+    }
+}
+
+fn cases() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/cases");
+    let mut cases = fs::read_dir(dir).expect("the cases directory exists")
+        .map(|x| x.expect("the cases directory is readable").path())
+        .filter(|x| x.extension().is_some_and(|x| x == "gl"))
+        .collect::<Vec<_>>();
+    cases.sort();
+    cases
+}
+
+#[test]
+fn golden(){
+    let bless = std::env::var_os("BLESS").is_some_and(|x| !x.is_empty());
+    let mut failures = vec![];
+    for case in cases() {
+        let name = case.file_name().expect("cases are files").to_string_lossy().into_owned();
+        let text = fs::read_to_string(&case).expect("cases are readable");
+        let (actual, svgs) = snapshot(&name, &text);
+        failures.extend(compare(&case.with_extension("expected"), &actual, bless));
+        let stem = case.file_stem().expect("cases are files").to_string_lossy();
+        for (i, svg) in svgs.iter().enumerate() {
+            failures.extend(compare(&case.with_file_name(format!("{stem}-{}.svg", i + 1)), svg, bless));
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}